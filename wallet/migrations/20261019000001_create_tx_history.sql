@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS tx_history (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    address CHAR(42) NOT NULL,
+    counterparty CHAR(42) NULL,
+    direction VARCHAR(8) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    asset VARCHAR(42) NOT NULL,
+    amount VARCHAR(78) NOT NULL,
+    amount_formatted VARCHAR(100) NULL,
+    symbol VARCHAR(32) NULL,
+    decimals TINYINT UNSIGNED NULL,
+    tx_hash CHAR(66) NOT NULL,
+    log_index BIGINT NOT NULL DEFAULT -1,
+    block_number BIGINT UNSIGNED NOT NULL,
+    block_hash CHAR(66) NOT NULL,
+    block_timestamp BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_tx_history_entry (address, tx_hash, log_index),
+    KEY idx_tx_history_address_block (address, block_number, id),
+    KEY idx_tx_history_block (block_number)
+);
+
+CREATE TABLE IF NOT EXISTS indexer_cursor (
+    name VARCHAR(64) NOT NULL,
+    block_number BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (name)
+);
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS api_keys (
+    id CHAR(36) NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    prefix VARCHAR(16) NOT NULL,
+    key_hash CHAR(64) NOT NULL,
+    scopes VARCHAR(64) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NULL,
+    revoked_at BIGINT UNSIGNED NULL,
+    rotated_to CHAR(36) NULL,
+    last_used_at BIGINT UNSIGNED NULL,
+    last_used_ip VARCHAR(45) NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_api_keys_hash (key_hash)
+);
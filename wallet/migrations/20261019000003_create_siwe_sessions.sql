@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS siwe_nonces (
+    nonce VARCHAR(32) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    used_at BIGINT UNSIGNED NULL,
+    PRIMARY KEY (nonce),
+    KEY idx_siwe_nonces_expires (expires_at)
+);
+
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    id CHAR(36) NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    address CHAR(42) NOT NULL,
+    scopes VARCHAR(64) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    last_used_at BIGINT UNSIGNED NULL,
+    last_used_ip VARCHAR(45) NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_auth_sessions_hash (token_hash)
+);
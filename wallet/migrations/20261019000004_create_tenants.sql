@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS tenants (
+    id CHAR(36) NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_tenants_name (name)
+);
+
+-- NULL for platform keys, which are not restricted to one tenant
+ALTER TABLE api_keys ADD COLUMN tenant_id CHAR(36) NULL AFTER prefix;
+CREATE INDEX idx_api_keys_tenant ON api_keys (tenant_id);
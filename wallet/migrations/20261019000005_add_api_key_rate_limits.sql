@@ -0,0 +1,4 @@
+-- Per-key overrides of rate_limit.read / rate_limit.expensive, NULL uses the configured budget
+ALTER TABLE api_keys
+    ADD COLUMN read_rate_limit INT UNSIGNED NULL AFTER scopes,
+    ADD COLUMN expensive_rate_limit INT UNSIGNED NULL AFTER read_rate_limit;
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    -- API key or session the Idempotency-Key was sent with, keys are scoped per caller
+    principal VARCHAR(64) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    method VARCHAR(8) NOT NULL,
+    path VARCHAR(255) NOT NULL,
+    -- SHA-256 of method, path and body
+    fingerprint CHAR(64) NOT NULL,
+    -- NULL while the first request is running
+    status_code SMALLINT UNSIGNED NULL,
+    response_body MEDIUMTEXT NULL,
+    tx_hash CHAR(66) NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    completed_at BIGINT UNSIGNED NULL,
+    expires_at BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (principal, idempotency_key),
+    KEY idx_idempotency_keys_expires (expires_at),
+    KEY idx_idempotency_keys_tx_hash (tx_hash)
+);
@@ -0,0 +1,45 @@
+CREATE TABLE IF NOT EXISTS outbound_txs (
+    id CHAR(36) NOT NULL,
+    -- Owner of the sending key, NULL for platform keys
+    tenant_id CHAR(36) NULL,
+    kind VARCHAR(8) NOT NULL,
+    from_address CHAR(42) NOT NULL,
+    to_address CHAR(42) NOT NULL,
+    -- Token contract of ERC20 transfers
+    token CHAR(42) NULL,
+    amount VARCHAR(100) NOT NULL,
+    -- API request the transaction was built from
+    request TEXT NOT NULL,
+    nonce BIGINT UNSIGNED NULL,
+    gas_limit VARCHAR(78) NULL,
+    gas_price VARCHAR(78) NULL,
+    max_fee_per_gas VARCHAR(78) NULL,
+    max_priority_fee_per_gas VARCHAR(78) NULL,
+    -- RLP encoded signed transaction, hex
+    signed_payload MEDIUMTEXT NULL,
+    tx_hash CHAR(66) NULL,
+    status VARCHAR(16) NOT NULL,
+    stuck BOOLEAN NOT NULL DEFAULT FALSE,
+    block_number BIGINT UNSIGNED NULL,
+    confirmations BIGINT UNSIGNED NULL,
+    error TEXT NULL,
+    created_at BIGINT UNSIGNED NOT NULL,
+    updated_at BIGINT UNSIGNED NOT NULL,
+    submitted_at BIGINT UNSIGNED NULL,
+    finalized_at BIGINT UNSIGNED NULL,
+    PRIMARY KEY (id),
+    KEY idx_outbound_txs_status (status, created_at),
+    KEY idx_outbound_txs_tenant (tenant_id, created_at),
+    KEY idx_outbound_txs_hash (tx_hash)
+);
+
+CREATE TABLE IF NOT EXISTS outbound_tx_transitions (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    tx_id CHAR(36) NOT NULL,
+    from_status VARCHAR(16) NULL,
+    to_status VARCHAR(16) NOT NULL,
+    detail TEXT NULL,
+    at BIGINT UNSIGNED NOT NULL,
+    PRIMARY KEY (id),
+    KEY idx_outbound_tx_transitions_tx (tx_id, id)
+);
@@ -0,0 +1,3 @@
+-- Sessions end on logout or when an admin revokes them, not only when they expire
+ALTER TABLE auth_sessions
+    ADD COLUMN revoked_at BIGINT UNSIGNED NULL AFTER expires_at;
@@ -0,0 +1,9 @@
+-- Each tenant owning or watching an address gets its own copy of the rows, so
+-- history is scoped by the database and not only by the in-memory watch list.
+-- The platform's copy, which existing rows become, has an empty tenant_id.
+ALTER TABLE tx_history
+    ADD COLUMN tenant_id CHAR(36) NOT NULL DEFAULT '' AFTER id,
+    DROP INDEX uk_tx_history_entry,
+    ADD UNIQUE KEY uk_tx_history_entry (tenant_id, address, tx_hash, log_index),
+    DROP INDEX idx_tx_history_address_block,
+    ADD KEY idx_tx_history_address_block (tenant_id, address, block_number, id);
@@ -0,0 +1,5 @@
+-- Symbol and decimals of the amount, so it can be returned formatted. Rows
+-- recorded before are ETH when they have no token, base units otherwise.
+ALTER TABLE outbound_txs
+    ADD COLUMN symbol VARCHAR(32) NULL AFTER amount,
+    ADD COLUMN decimals TINYINT UNSIGNED NULL AFTER symbol;
@@ -21,7 +21,7 @@ impl ChainAdapter for BitcoinAdapter {
         ChainId::Bitcoin
     }
 
-    async fn get_balance(&self, _address: &Address) -> Result<Balance> {
+    async fn get_balance(&self, _address: &Address) -> Result<Amount> {
         Err(anyhow::anyhow!(
             "Bitcoin adapter not yet implemented. Add bitcoin-rpc or similar dependency."
         ))
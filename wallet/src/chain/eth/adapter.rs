@@ -1,27 +1,27 @@
 use crate::chain::ChainAdapter;
+use crate::chain::eth::RpcPool;
 use crate::types::*;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use ethers::middleware::Middleware;
-use ethers::providers::{Http, Provider, Ws};
+use ethers::providers::{Provider, Ws};
 use ethers::types::{Address as EthAddress, H256, TransactionRequest, U256};
-use ethers::utils::{format_ether, parse_ether};
 
 /// Ethereum chain adapter implementation
 pub struct EthereumAdapter {
-    http_provider: Provider<Http>,
+    http_provider: Provider<RpcPool>,
     ws_provider: Option<Provider<Ws>>,
 }
 
 impl EthereumAdapter {
-    pub fn new(http_provider: Provider<Http>, ws_provider: Option<Provider<Ws>>) -> Self {
+    pub fn new(http_provider: Provider<RpcPool>, ws_provider: Option<Provider<Ws>>) -> Self {
         Self {
             http_provider,
             ws_provider,
         }
     }
 
-    pub fn http_provider(&self) -> &Provider<Http> {
+    pub fn http_provider(&self) -> &Provider<RpcPool> {
         &self.http_provider
     }
 
@@ -36,20 +36,15 @@ impl ChainAdapter for EthereumAdapter {
         ChainId::Ethereum
     }
 
-    async fn get_balance(&self, address: &Address) -> Result<Balance> {
+    async fn get_balance(&self, address: &Address) -> Result<Amount> {
         if address.chain_id != ChainId::Ethereum {
             return Err(anyhow!("Address chain mismatch"));
         }
 
         let eth_addr: EthAddress = address.value.parse()?;
         let balance = self.http_provider.get_balance(eth_addr, None).await?;
-        let balance_str = format_ether(balance);
 
-        Ok(Balance::new(
-            balance_str,
-            18, // ETH has 18 decimals
-            "ETH".to_string(),
-        ))
+        Ok(Amount::new(balance, Asset::ether()))
     }
 
     async fn build_tx(&self, req: TxRequest) -> Result<UnsignedTx> {
@@ -59,7 +54,10 @@ impl ChainAdapter for EthereumAdapter {
 
         let from_addr: EthAddress = req.from.value.parse()?;
         let to_addr: EthAddress = req.to.value.parse()?;
-        let amount = parse_ether(&req.amount)?;
+        if *req.amount.asset() != Asset::ether() {
+            return Err(anyhow!("Only ETH amounts can be sent, got {}", req.amount.asset().symbol));
+        }
+        let amount = req.amount.raw();
 
         let mut tx = TransactionRequest::new()
             .from(from_addr)
@@ -71,8 +69,8 @@ impl ChainAdapter for EthereumAdapter {
         }
 
         if let Some(gas_price) = req.gas_price {
-            let gas_price_parsed = parse_ether(&gas_price)?;
-            tx = tx.gas_price(gas_price_parsed);
+            let gas_price = Amount::parse(&gas_price, Asset::new("gwei", 9))?;
+            tx = tx.gas_price(gas_price.raw());
         }
 
         if let Some(data) = req.data {
@@ -139,16 +137,31 @@ impl ChainAdapter for EthereumAdapter {
             .get_transaction_receipt(tx_hash)
             .await?;
 
+        // A receipt from a block that has since been reorged out is not final,
+        // only trust it while its block is still canonical
+        let receipt_opt = match receipt_opt {
+            Some(receipt) => match (receipt.block_number, receipt.block_hash) {
+                (Some(number), Some(block_hash)) => {
+                    let canonical = self.http_provider.get_block(number).await?.and_then(|b| b.hash);
+                    (canonical == Some(block_hash)).then_some(receipt)
+                }
+                _ => None,
+            },
+            None => None,
+        };
+
         let status = if let Some(receipt) = receipt_opt {
             let block_number = receipt.block_number.map(|n| n.as_u64());
-            let confirmations = block_number.map(|_| 1u64); // Simplified
+            let head = self.http_provider.get_block_number().await?.as_u64();
+            let confirmations = block_number.map(|n| head.saturating_sub(n) + 1);
+            let reverted = receipt.status.is_some_and(|s| s.as_u64() == 0);
 
             TxStatusInfo {
                 hash: hash.clone(),
-                status: TxStatus::Confirmed,
+                status: if reverted { TxStatus::Failed } else { TxStatus::Confirmed },
                 block_number,
                 confirmations,
-                error: None,
+                error: reverted.then(|| "execution reverted".to_string()),
             }
         } else {
             // Check if transaction exists (might be pending)
@@ -0,0 +1,192 @@
+//! In-process JSON-RPC node serving a scripted chain, so services built on
+//! `Provider<RpcPool>` can be tested without a real node
+
+use crate::chain::eth::retry::RetryPolicy;
+use crate::chain::eth::RpcPool;
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use ethers::providers::Provider;
+use ethers::types::{Address, Block, Log, Transaction, H256, U256, U64};
+use ethers::utils::keccak256;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Chain of blocks on the current branch plus the logs they contain
+#[derive(Default)]
+pub(crate) struct MockChain {
+    pub blocks: Vec<Block<Transaction>>,
+    pub logs: Vec<Log>,
+    /// Branch counter mixed into block hashes, bumped on every reorg
+    branch: u64,
+    /// Canned results or errors of methods the node doesn't simulate
+    pub responses: HashMap<String, Result<Value, (i64, String)>>,
+}
+
+impl MockChain {
+    pub fn head(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    pub fn hash_at(&self, number: u64) -> H256 {
+        self.blocks[number as usize].hash.unwrap()
+    }
+
+    /// Append `count` empty blocks to the current branch
+    pub fn mine(&mut self, count: u64) {
+        for _ in 0..count {
+            let number = self.blocks.len() as u64;
+            let parent_hash = self.blocks.last().and_then(|block| block.hash).unwrap_or_default();
+            self.blocks.push(Block {
+                hash: Some(H256::from(keccak256(format!("{}-{}", self.branch, number)))),
+                parent_hash,
+                number: Some(U64::from(number)),
+                timestamp: U256::from(1_700_000_000 + number * 12),
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Replace the last `depth` blocks by `depth` empty blocks of a new branch,
+    /// dropping the logs of the orphaned ones
+    pub fn reorg(&mut self, depth: u64) {
+        let keep = self.blocks.len() - depth as usize;
+        self.blocks.truncate(keep);
+        self.logs.retain(|log| log.block_number.is_some_and(|n| (n.as_u64() as usize) < keep));
+        self.branch += 1;
+        self.mine(depth);
+    }
+
+    /// Add an ERC20 `Transfer` log to block `number` of the current branch
+    pub fn transfer(&mut self, number: u64, token: Address, from: Address, to: Address, value: u64) -> H256 {
+        let tx_hash = H256::from(keccak256(format!("tx-{}-{}-{}", self.branch, number, self.logs.len())));
+        let mut data = [0u8; 32];
+        U256::from(value).to_big_endian(&mut data);
+        self.logs.push(Log {
+            address: token,
+            topics: vec![
+                H256::from(keccak256("Transfer(address,address,uint256)")),
+                H256::from(from),
+                H256::from(to),
+            ],
+            data: data.to_vec().into(),
+            block_hash: Some(self.hash_at(number)),
+            block_number: Some(U64::from(number)),
+            transaction_hash: Some(tx_hash),
+            transaction_index: Some(U64::zero()),
+            log_index: Some(U256::from(self.logs.len())),
+            removed: Some(false),
+            ..Default::default()
+        });
+        tx_hash
+    }
+
+    fn handle(&self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if let Some(response) = self.responses.get(method) {
+            return response.clone();
+        }
+        match method {
+            "eth_chainId" => Ok(json!("0x7a69")),
+            "eth_blockNumber" => Ok(json!(format!("{:#x}", self.head()))),
+            "eth_getBlockByNumber" => {
+                let number = match params[0].as_str() {
+                    Some("latest") => Some(self.head()),
+                    Some(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16).ok(),
+                    None => None,
+                };
+                Ok(match number.and_then(|number| self.blocks.get(number as usize)) {
+                    Some(block) if params[1].as_bool() == Some(true) => json!(block),
+                    Some(block) => {
+                        let mut header = json!(block);
+                        header["transactions"] = json!(block.transactions.iter().map(|tx| tx.hash).collect::<Vec<_>>());
+                        header
+                    }
+                    None => Value::Null,
+                })
+            }
+            "eth_getLogs" => {
+                let filter = &params[0];
+                let block = |key: &str| {
+                    filter[key]
+                        .as_str()
+                        .and_then(|number| u64::from_str_radix(number.trim_start_matches("0x"), 16).ok())
+                };
+                let (from, to) = (block("fromBlock").unwrap_or(0), block("toBlock").unwrap_or(self.head()));
+                let matches = |expected: &Value, actual: String| match expected {
+                    Value::Null => true,
+                    Value::String(value) => value.eq_ignore_ascii_case(&actual),
+                    Value::Array(values) => values.iter().any(|value| value.as_str().is_some_and(|v| v.eq_ignore_ascii_case(&actual))),
+                    _ => false,
+                };
+                let logs: Vec<&Log> = self
+                    .logs
+                    .iter()
+                    .filter(|log| log.block_number.is_some_and(|n| (from..=to).contains(&n.as_u64())))
+                    .filter(|log| matches(&filter["address"], format!("{:?}", log.address)))
+                    .filter(|log| {
+                        let topics = filter["topics"].as_array().cloned().unwrap_or_default();
+                        topics.iter().enumerate().all(|(i, expected)| {
+                            log.topics.get(i).is_some_and(|topic| matches(expected, format!("{:?}", topic)))
+                        })
+                    })
+                    .collect();
+                Ok(json!(logs))
+            }
+            _ => Err((-32601, format!("the method {} does not exist/is not available", method))),
+        }
+    }
+}
+
+pub(crate) struct MockNode {
+    chain: Arc<Mutex<MockChain>>,
+    url: String,
+}
+
+impl MockNode {
+    /// Serve a chain holding only the genesis block on a random local port
+    pub async fn start() -> Self {
+        let mut chain = MockChain::default();
+        chain.mine(1);
+        let chain = Arc::new(Mutex::new(chain));
+
+        let app = Router::new().route("/", post(rpc)).with_state(chain.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Self { chain, url }
+    }
+
+    pub fn provider(&self) -> Provider<RpcPool> {
+        let retry = RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        let pool = RpcPool::new(std::slice::from_ref(&self.url), 0, 1, retry, 0, Duration::from_secs(5)).unwrap();
+        Provider::new(pool)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn chain(&self) -> std::sync::MutexGuard<'_, MockChain> {
+        self.chain.lock().unwrap()
+    }
+}
+
+async fn rpc(State(chain): State<Arc<Mutex<MockChain>>>, Json(request): Json<Value>) -> Json<Value> {
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let method = request["method"].as_str().unwrap_or_default();
+    let response = match chain.lock().unwrap().handle(method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": { "code": code, "message": message },
+        }),
+    };
+    Json(response)
+}
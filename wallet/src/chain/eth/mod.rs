@@ -1,4 +1,10 @@
 pub mod adapter;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod pool;
+pub mod retry;
+pub mod ws;
 
 pub use adapter::EthereumAdapter;
-
+pub use pool::RpcPool;
+pub use ws::WsConnection;
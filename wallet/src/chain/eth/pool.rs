@@ -0,0 +1,416 @@
+use crate::chain::eth::retry::{classify, is_already_known, is_idempotent, ErrorClass, RateLimiter, RetryPolicy};
+use crate::metrics::METRICS;
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError};
+use ethers::types::Bytes;
+use ethers::utils::keccak256;
+use futures::future;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+/// Weight of the newest sample in the latency moving average
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+/// Consecutive transport failures after which an endpoint is taken out of rotation
+/// until the next successful health check
+const MAX_CONSECUTIVE_FAILURES: u64 = 3;
+
+#[derive(Debug, Error)]
+pub enum RpcPoolError {
+    #[error(transparent)]
+    Client(#[from] HttpClientError),
+    #[error("Deserialization Error: {0}")]
+    SerdeJson(#[from] serde_json::Error),
+    #[error("no RPC endpoint configured")]
+    NoEndpoints,
+    #[error("RPC quorum not reached: {matching} of {required} endpoints agreed")]
+    NoQuorum { matching: usize, required: usize },
+    #[error("RPC quorum must be between 1 and the number of endpoints ({endpoints}), got {quorum}")]
+    InvalidQuorum { quorum: usize, endpoints: usize },
+}
+
+impl RpcError for RpcPoolError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            RpcPoolError::Client(e) => e.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            RpcPoolError::Client(e) => e.as_serde_error(),
+            RpcPoolError::SerdeJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<RpcPoolError> for ProviderError {
+    fn from(e: RpcPoolError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointStats {
+    latency_ms: Option<f64>,
+    requests: u64,
+    errors: u64,
+    consecutive_failures: u64,
+    block_number: Option<u64>,
+    /// Behind the best endpoint by more than the allowed lag
+    lagging: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    /// Redacted URL used in logs and metric labels
+    label: String,
+    client: Http,
+    limiter: RateLimiter,
+    stats: Mutex<EndpointStats>,
+}
+
+impl Endpoint {
+    fn is_healthy(&self) -> bool {
+        let stats = self.stats.lock().unwrap();
+        stats.consecutive_failures < MAX_CONSECUTIVE_FAILURES && !stats.lagging
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        let sample = latency.as_secs_f64() * 1000.0;
+        stats.latency_ms = Some(match stats.latency_ms {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (sample - avg),
+            None => sample,
+        });
+        stats.requests += 1;
+        stats.consecutive_failures = 0;
+    }
+
+    fn record_failure(&self) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.requests += 1;
+        stats.errors += 1;
+        stats.consecutive_failures += 1;
+    }
+
+    async fn request(&self, method: &str, params: &Value) -> Result<Value, HttpClientError> {
+        self.limiter.acquire().await;
+        let started = Instant::now();
+        let result = self.client.request::<_, Value>(method, params).await;
+        METRICS.observe_rpc(method, &self.label, result.is_ok(), started.elapsed());
+        debug!(
+            method,
+            endpoint = %self.label,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            ok = result.is_ok(),
+            "RPC call"
+        );
+        match &result {
+            Ok(_) => self.record_success(started.elapsed()),
+            // a healthy node rejecting the request itself
+            Err(e) if classify(e) == ErrorClass::Fatal => self.record_success(started.elapsed()),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+}
+
+/// Health snapshot of one endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub latency_ms: Option<f64>,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub block_number: Option<u64>,
+    pub lag: Option<u64>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    max_block_lag: u64,
+    quorum: usize,
+    retry: RetryPolicy,
+}
+
+/// JSON-RPC transport spreading requests over several HTTP endpoints of one chain.
+/// Requests go to the fastest healthy endpoint, transient failures fail over to
+/// the next one and are retried with jittered backoff, fatal JSON-RPC errors are
+/// returned as is. Each endpoint has its own requests-per-second budget.
+/// Non-idempotent calls are sent once, except raw transactions whose hash is
+/// known up front so a rebroadcast can be recognised.
+#[derive(Debug, Clone)]
+pub struct RpcPool {
+    inner: Arc<PoolInner>,
+}
+
+impl RpcPool {
+    pub fn new(
+        urls: &[String],
+        max_block_lag: u64,
+        quorum: usize,
+        retry: RetryPolicy,
+        max_rps: u32,
+        timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            return Err(RpcPoolError::NoEndpoints.into());
+        }
+        if quorum == 0 || quorum > urls.len() {
+            return Err(RpcPoolError::InvalidQuorum {
+                quorum,
+                endpoints: urls.len(),
+            }
+            .into());
+        }
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                Ok(Endpoint {
+                    label: redact_url(url),
+                    client: Http::new_with_client(url::Url::parse(url)?, http.clone()),
+                    limiter: RateLimiter::new(max_rps),
+                    stats: Mutex::new(EndpointStats::default()),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                endpoints,
+                max_block_lag,
+                quorum,
+                retry,
+            }),
+        })
+    }
+
+    /// Endpoints in the order they should be tried: healthy ones first, fastest first
+    fn ranked(&self) -> Vec<&Endpoint> {
+        let mut endpoints: Vec<&Endpoint> = self.inner.endpoints.iter().collect();
+        endpoints.sort_by(|a, b| {
+            let key = |e: &Endpoint| (!e.is_healthy(), e.stats.lock().unwrap().latency_ms.unwrap_or(f64::MAX));
+            let (a, b) = (key(a), key(b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+        endpoints
+    }
+
+    /// Send a read to every healthy endpoint and only accept a result returned by
+    /// at least `quorum` of them, for critical reads such as balances before a withdrawal.
+    /// A quorum of one is a plain request, with failover and retries.
+    pub async fn request_quorum<T, R>(&self, method: &str, params: T) -> Result<R, RpcPoolError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let required = self.inner.quorum;
+        if required == 1 {
+            return self.request(method, params).await;
+        }
+        let params = serde_json::to_value(params)?;
+        let mut endpoints: Vec<&Endpoint> = self.ranked().into_iter().filter(|e| e.is_healthy()).collect();
+        if endpoints.len() < required {
+            endpoints = self.inner.endpoints.iter().collect();
+        }
+
+        let responses = future::join_all(endpoints.iter().map(|e| e.request(method, &params))).await;
+        let mut tally: Vec<(Value, usize)> = Vec::new();
+        for response in responses.into_iter().flatten() {
+            match tally.iter_mut().find(|(value, _)| *value == response) {
+                Some((_, count)) => *count += 1,
+                None => tally.push((response, 1)),
+            }
+        }
+
+        let (value, matching) = tally
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .unwrap_or((Value::Null, 0));
+        if matching < required {
+            return Err(RpcPoolError::NoQuorum { matching, required });
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Poll `eth_blockNumber` on every endpoint, refreshing latency and marking
+    /// endpoints that lag behind the best head by more than the allowed lag
+    pub async fn check_health(&self) {
+        let heads = future::join_all(self.inner.endpoints.iter().map(|endpoint| async move {
+            let head = endpoint.request("eth_blockNumber", &Value::Array(Vec::new())).await.ok()?;
+            u64::from_str_radix(head.as_str()?.trim_start_matches("0x"), 16).ok()
+        }))
+        .await;
+
+        let best = heads.iter().flatten().max().copied();
+        for (endpoint, head) in self.inner.endpoints.iter().zip(heads) {
+            let mut stats = endpoint.stats.lock().unwrap();
+            if head.is_some() {
+                stats.block_number = head;
+            }
+            stats.lagging = match (best, head) {
+                (Some(best), Some(head)) => best - head > self.inner.max_block_lag,
+                _ => false,
+            };
+        }
+    }
+
+    pub fn spawn_health_checks(&self, interval: u64) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            loop {
+                pool.check_health().await;
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        let best = self
+            .inner
+            .endpoints
+            .iter()
+            .filter_map(|e| e.stats.lock().unwrap().block_number)
+            .max();
+
+        self.inner
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let healthy = endpoint.is_healthy();
+                let stats = endpoint.stats.lock().unwrap();
+                EndpointStatus {
+                    url: endpoint.label.clone(),
+                    healthy,
+                    latency_ms: stats.latency_ms,
+                    requests: stats.requests,
+                    errors: stats.errors,
+                    error_rate: if stats.requests == 0 { 0.0 } else { stats.errors as f64 / stats.requests as f64 },
+                    block_number: stats.block_number,
+                    lag: best.zip(stats.block_number).map(|(best, head)| best - head),
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcPool {
+    type Error = RpcPoolError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let raw_tx_hash = if method == "eth_sendRawTransaction" { raw_tx_hash(&params) } else { None };
+        let retryable = is_idempotent(method) || raw_tx_hash.is_some();
+        let attempts = if retryable { self.inner.retry.max_retries + 1 } else { 1 };
+        let mut last_error = RpcPoolError::NoEndpoints;
+
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                tokio::time::sleep(self.inner.retry.backoff(attempt - 1)).await;
+            }
+
+            let mut endpoints = self.ranked();
+            if !retryable {
+                endpoints.truncate(1);
+            }
+            for endpoint in endpoints {
+                match endpoint.request(method, &params).await {
+                    Ok(value) => return Ok(serde_json::from_value(value)?),
+                    Err(e) if is_already_known(&e) => {
+                        return match &raw_tx_hash {
+                            Some(hash) => Ok(serde_json::from_value(hash.clone())?),
+                            None => Err(e.into()),
+                        };
+                    }
+                    Err(e) if classify(&e) == ErrorClass::Fatal => return Err(e.into()),
+                    Err(e) => {
+                        warn!(method, endpoint = %endpoint.label, attempt, error = %e, "RPC call failed");
+                        last_error = e.into();
+                    }
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+/// Hash of the signed transaction in `eth_sendRawTransaction` params
+fn raw_tx_hash(params: &Value) -> Option<Value> {
+    let raw: Bytes = params.get(0)?.as_str()?.parse().ok()?;
+    Some(Value::String(format!("{:?}", ethers::types::H256::from(keccak256(raw)))))
+}
+
+/// Keep scheme and host only, provider URLs often carry API keys in the path
+pub(crate) fn redact_url(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return "<invalid url>".to_string();
+    };
+    let host = parsed.host_str().unwrap_or_default();
+    match parsed.port() {
+        Some(port) => format!("{}://{}:{}", parsed.scheme(), host, port),
+        None => format!("{}://{}", parsed.scheme(), host),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use serde_json::json;
+
+    fn pool(urls: &[&str], quorum: usize) -> anyhow::Result<RpcPool> {
+        let urls: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+        let retry = RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        };
+        RpcPool::new(&urls, 0, quorum, retry, 0, Duration::from_secs(5))
+    }
+
+    #[test]
+    fn quorum_must_fit_the_endpoints() {
+        assert!(pool(&["http://localhost:8545"], 0).is_err());
+        assert!(pool(&["http://localhost:8545"], 2).is_err());
+        assert!(pool(&["http://localhost:8545", "http://localhost:8546"], 2).is_ok());
+    }
+
+    #[tokio::test]
+    async fn quorum_of_one_fails_over_like_a_plain_request() {
+        let node = MockNode::start().await;
+        let pool = pool(&["http://127.0.0.1:1", node.url()], 1).unwrap();
+        let head: String = pool.request_quorum("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(head, "0x0");
+    }
+
+    #[tokio::test]
+    async fn quorum_requires_matching_answers() {
+        let (a, b) = (MockNode::start().await, MockNode::start().await);
+        let agreeing = pool(&[a.url(), b.url()], 2).unwrap();
+        let head: String = agreeing.request_quorum("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(head, "0x0");
+
+        b.chain().responses.insert("eth_blockNumber".into(), Ok(json!("0x5")));
+        let result: Result<String, _> = agreeing.request_quorum("eth_blockNumber", ()).await;
+        assert!(matches!(result, Err(RpcPoolError::NoQuorum { matching: 1, required: 2 })));
+    }
+}
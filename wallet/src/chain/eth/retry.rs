@@ -0,0 +1,220 @@
+use ethers::providers::HttpClientError;
+use rand::Rng;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Calls with side effects that must not be blindly repeated
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "personal_sendTransaction",
+];
+
+/// JSON-RPC error codes that signal a transient condition on the node
+const RETRYABLE_RPC_CODES: &[i64] = &[
+    429,
+    -32005, // limit exceeded
+    -32603, // internal error, commonly a backend timeout
+];
+
+/// Messages of transient JSON-RPC errors reported with generic codes
+const RETRYABLE_RPC_MESSAGES: &[&str] = &[
+    "rate limit",
+    "too many requests",
+    "timeout",
+    "timed out",
+    "header not found",
+    "temporarily unavailable",
+    "try again",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Transient, the same request may succeed on retry or on another endpoint
+    Retryable,
+    /// The node rejected the request itself, e.g. reverted or nonce too low
+    Fatal,
+}
+
+pub fn classify(error: &HttpClientError) -> ErrorClass {
+    match error {
+        HttpClientError::ReqwestError(e) => {
+            let retryable_status = e
+                .status()
+                .is_some_and(|status| status.as_u16() == 429 || status.is_server_error());
+            if e.is_timeout() || e.is_connect() || e.is_request() || retryable_status {
+                ErrorClass::Retryable
+            } else {
+                ErrorClass::Fatal
+            }
+        }
+        HttpClientError::JsonRpcError(e) => {
+            let message = e.message.to_lowercase();
+            if RETRYABLE_RPC_CODES.contains(&e.code) || RETRYABLE_RPC_MESSAGES.iter().any(|m| message.contains(m)) {
+                ErrorClass::Retryable
+            } else {
+                ErrorClass::Fatal
+            }
+        }
+        // non JSON bodies are gateway error pages (502, 503, 429 ...)
+        HttpClientError::SerdeJson { .. } => ErrorClass::Retryable,
+    }
+}
+
+pub fn is_idempotent(method: &str) -> bool {
+    !NON_IDEMPOTENT_METHODS.contains(&method)
+}
+
+/// A raw transaction rebroadcast after a lost response is reported like this
+/// by nodes that already accepted the first copy
+pub fn is_already_known(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::JsonRpcError(e) => {
+            let message = e.message.to_lowercase();
+            message.contains("already known") || message.contains("known transaction") || message.contains("already imported")
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with full jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+}
+
+/// Token bucket enforcing a requests-per-second budget on one endpoint
+#[derive(Debug)]
+pub struct RateLimiter {
+    rps: f64,
+    /// (available tokens, last refill)
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    /// `rps` of 0 disables limiting
+    pub fn new(rps: u32) -> Self {
+        Self {
+            rps: rps as f64,
+            state: Mutex::new((rps as f64, Instant::now())),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        if self.rps <= 0.0 {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let (tokens, last) = *state;
+                let now = Instant::now();
+                let tokens = (tokens + now.duration_since(last).as_secs_f64() * self.rps).min(self.rps);
+                if tokens >= 1.0 {
+                    *state = (tokens - 1.0, now);
+                    return;
+                }
+                *state = (tokens, now);
+                Duration::from_secs_f64((1.0 - tokens) / self.rps)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::JsonRpcError;
+
+    fn rpc_error(code: i64, message: &str) -> HttpClientError {
+        HttpClientError::JsonRpcError(JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn transient_node_errors_are_retryable() {
+        assert_eq!(classify(&rpc_error(429, "Too Many Requests")), ErrorClass::Retryable);
+        assert_eq!(classify(&rpc_error(-32005, "limit exceeded")), ErrorClass::Retryable);
+        assert_eq!(classify(&rpc_error(-32000, "header not found")), ErrorClass::Retryable);
+        assert_eq!(classify(&rpc_error(-32000, "request timed out")), ErrorClass::Retryable);
+
+        let gateway_page = HttpClientError::SerdeJson {
+            err: serde_json::from_str::<serde_json::Value>("<html>502</html>").unwrap_err(),
+            text: "<html>502</html>".to_string(),
+        };
+        assert_eq!(classify(&gateway_page), ErrorClass::Retryable);
+    }
+
+    #[test]
+    fn rejected_requests_are_fatal() {
+        assert_eq!(classify(&rpc_error(3, "execution reverted")), ErrorClass::Fatal);
+        assert_eq!(classify(&rpc_error(-32000, "nonce too low")), ErrorClass::Fatal);
+        assert_eq!(classify(&rpc_error(-32601, "method not found")), ErrorClass::Fatal);
+    }
+
+    #[test]
+    fn only_sends_are_not_idempotent() {
+        assert!(is_idempotent("eth_call"));
+        assert!(!is_idempotent("eth_sendRawTransaction"));
+        assert!(is_already_known(&rpc_error(-32000, "already known")));
+        assert!(!is_already_known(&rpc_error(-32000, "nonce too low")));
+    }
+
+    #[test]
+    fn backoff_is_jittered_below_a_capped_exponential() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1_000),
+        };
+        for _ in 0..100 {
+            assert!(policy.backoff(0) <= Duration::from_millis(100));
+            assert!(policy.backoff(2) <= Duration::from_millis(400));
+            assert!(policy.backoff(10) <= Duration::from_millis(1_000));
+            assert!(policy.backoff(u32::MAX) <= Duration::from_millis(1_000));
+        }
+
+        let none = RetryPolicy {
+            max_delay: Duration::ZERO,
+            ..policy
+        };
+        assert_eq!(none.backoff(3), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_spends_a_burst_then_waits_for_refill() {
+        let unlimited = RateLimiter::new(0);
+        let started = Instant::now();
+        for _ in 0..1_000 {
+            unlimited.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        let limiter = RateLimiter::new(10);
+        let started = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(50));
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+}
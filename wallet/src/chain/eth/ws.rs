@@ -0,0 +1,93 @@
+use crate::chain::eth::pool::redact_url;
+use ethers::providers::{Middleware, Provider, Ws};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, RwLock};
+use tracing::{info, info_span, warn, Instrument};
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+/// Interval and timeout of the liveness probe on an established connection
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// WebSocket provider that is (re)connected in the background, moving on to the
+/// next URL after a failure. Every connect and disconnect bumps a generation
+/// counter so subscribers know to resubscribe.
+pub struct WsConnection {
+    urls: Vec<String>,
+    provider: RwLock<Option<Provider<Ws>>>,
+    generation: watch::Sender<u64>,
+}
+
+impl WsConnection {
+    pub fn new(urls: Vec<String>) -> Arc<Self> {
+        let (generation, _) = watch::channel(0);
+        Arc::new(Self {
+            urls,
+            provider: RwLock::new(None),
+            generation,
+        })
+    }
+
+    /// Current connection, `None` while disconnected
+    pub async fn provider(&self) -> Option<Provider<Ws>> {
+        self.provider.read().await.clone()
+    }
+
+    pub async fn is_connected(&self) -> bool {
+        self.provider.read().await.is_some()
+    }
+
+    /// Notified whenever the connection is established or lost
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.generation.subscribe()
+    }
+
+    pub fn spawn(self: &Arc<Self>) {
+        let connection = self.clone();
+        tokio::spawn(async move { connection.run().await }.instrument(info_span!("ws_connection")));
+    }
+
+    async fn run(&self) {
+        if self.urls.is_empty() {
+            return;
+        }
+        let mut delay = MIN_RECONNECT_DELAY;
+        for url in self.urls.iter().cycle() {
+            match Provider::<Ws>::connect(url).await {
+                Ok(provider) => {
+                    info!(url = %redact_url(url), "WebSocket provider connected");
+                    delay = MIN_RECONNECT_DELAY;
+                    self.set(Some(provider.clone())).await;
+
+                    loop {
+                        tokio::time::sleep(PING_INTERVAL).await;
+                        match tokio::time::timeout(PING_TIMEOUT, provider.get_block_number()).await {
+                            Ok(Ok(_)) => continue,
+                            Ok(Err(e)) => warn!(error = %e, "WebSocket connection lost"),
+                            Err(_) => warn!("WebSocket connection lost: ping timed out"),
+                        }
+                        break;
+                    }
+                    self.set(None).await;
+                }
+                Err(e) => {
+                    warn!(
+                        url = %redact_url(url),
+                        retry_in = ?delay,
+                        error = %e,
+                        "WebSocket provider connection failed"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+        }
+    }
+
+    async fn set(&self, provider: Option<Provider<Ws>>) {
+        *self.provider.write().await = provider;
+        self.generation.send_modify(|generation| *generation += 1);
+    }
+}
@@ -10,7 +10,7 @@ pub trait ChainAdapter: Send + Sync {
     fn chain_id(&self) -> ChainId;
 
     /// Get balance for an address
-    async fn get_balance(&self, address: &Address) -> Result<Balance>;
+    async fn get_balance(&self, address: &Address) -> Result<Amount>;
 
     /// Build an unsigned transaction from a request
     async fn build_tx(&self, req: TxRequest) -> Result<UnsignedTx>;
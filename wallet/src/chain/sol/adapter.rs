@@ -21,7 +21,7 @@ impl ChainAdapter for SolanaAdapter {
         ChainId::Solana
     }
 
-    async fn get_balance(&self, _address: &Address) -> Result<Balance> {
+    async fn get_balance(&self, _address: &Address) -> Result<Amount> {
         Err(anyhow::anyhow!(
             "Solana adapter not yet implemented. Add solana-sdk dependency."
         ))
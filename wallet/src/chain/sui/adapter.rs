@@ -21,7 +21,7 @@ impl ChainAdapter for SuiAdapter {
         ChainId::Sui
     }
 
-    async fn get_balance(&self, _address: &Address) -> Result<Balance> {
+    async fn get_balance(&self, _address: &Address) -> Result<Amount> {
         Err(anyhow::anyhow!(
             "Sui adapter not yet implemented. Add sui-sdk or similar dependency."
         ))
@@ -1,15 +1,191 @@
+use crate::chain::eth::pool::RpcPoolError;
 use axum::response::{IntoResponse, Response};
 use axum::{http::StatusCode, Json};
-use serde_json::json;
+use ethers::types::{Address, H256};
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Stable, machine-readable error codes returned in every error body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    InvalidAddress,
+    InvalidHash,
+    InvalidAmount,
+    InvalidPrivateKey,
+    InvalidMnemonic,
+    InvalidKeystore,
+    Unauthorized,
+    Forbidden,
+    RateLimited,
+    KeyNotFound,
+    NotFound,
+    InsufficientFunds,
+    FeeTooHigh,
+    Reverted,
+    NonceConflict,
+    IdempotencyKeyInUse,
+    IdempotencyKeyReused,
+    RpcUnavailable,
+    DatabaseUnavailable,
+    NotImplemented,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest
+            | ErrorCode::InvalidAddress
+            | ErrorCode::InvalidHash
+            | ErrorCode::InvalidAmount
+            | ErrorCode::InvalidPrivateKey
+            | ErrorCode::InvalidMnemonic
+            | ErrorCode::InvalidKeystore => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::KeyNotFound | ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::NonceConflict | ErrorCode::IdempotencyKeyInUse => StatusCode::CONFLICT,
+            ErrorCode::InsufficientFunds
+            | ErrorCode::FeeTooHigh
+            | ErrorCode::Reverted
+            | ErrorCode::IdempotencyKeyReused => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ErrorCode::RpcUnavailable | ErrorCode::DatabaseUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::NotImplemented => StatusCode::NOT_IMPLEMENTED,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Error with a code, raised by services for failures the client can act on.
+/// Anything else reaching a handler is classified by [`ApiError::classify`].
+#[derive(Debug, Clone, Error)]
+#[error("{message}")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    /// Map any error to a code: errors raised as `ApiError` keep theirs, database
+    /// and RPC transport errors are recognised by type, and node / contract
+    /// errors from ethers by their message
+    pub fn classify(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<ApiError>() {
+                return e.clone();
+            }
+            if let Some(e) = cause.downcast_ref::<sqlx::Error>() {
+                let code = match e {
+                    sqlx::Error::RowNotFound => ErrorCode::NotFound,
+                    sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) => {
+                        ErrorCode::DatabaseUnavailable
+                    }
+                    _ => ErrorCode::Internal,
+                };
+                return Self::new(code, e.to_string());
+            }
+            if let Some(e @ (RpcPoolError::NoEndpoints | RpcPoolError::NoQuorum { .. })) =
+                cause.downcast_ref::<RpcPoolError>()
+            {
+                return Self::new(ErrorCode::RpcUnavailable, e.to_string());
+            }
+        }
+
+        let message = format!("{:#}", error);
+        let code = classify_node_message(&message).unwrap_or(ErrorCode::Internal);
+        Self::new(code, error.to_string())
+    }
+}
+
+/// Node and contract errors only differ by message across clients, match the common wordings
+fn classify_node_message(message: &str) -> Option<ErrorCode> {
+    let message = message.to_lowercase();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+
+    if matches(&["insufficient funds", "insufficient balance"]) {
+        Some(ErrorCode::InsufficientFunds)
+    } else if matches(&[
+        "nonce too low",
+        "nonce too high",
+        "already known",
+        "known transaction",
+        "replacement transaction underpriced",
+    ]) {
+        Some(ErrorCode::NonceConflict)
+    } else if matches(&["execution reverted", "contract call reverted with data"]) {
+        Some(ErrorCode::Reverted)
+    } else if matches(&[
+        "error sending request",
+        "connection refused",
+        "timed out",
+        "rate limit",
+        "too many requests",
+        "quorum not reached",
+    ]) {
+        Some(ErrorCode::RpcUnavailable)
+    } else {
+        None
+    }
+}
+
+pub fn parse_address(value: &str) -> Result<Address, ApiError> {
+    value
+        .parse()
+        .map_err(|_| ApiError::new(ErrorCode::InvalidAddress, format!("invalid address: {}", value)))
+}
+
+pub fn parse_hash(value: &str) -> Result<H256, ApiError> {
+    value
+        .parse()
+        .map_err(|_| ApiError::new(ErrorCode::InvalidHash, format!("invalid hash: {}", value)))
+}
+
+/// Body of every error response
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// HTTP status, repeated for clients that only look at the body
+    pub status: u16,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
 pub struct AppError(pub anyhow::Error);
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let error_response = json!({
-            "status": "error",
-            "message": format!("Server Error: {}", self.0),
-        });
-        (StatusCode::OK, Json(error_response)).into_response()
+        let error = ApiError::classify(&self.0);
+        let status = error.code.status();
+        if status.is_server_error() {
+            tracing::error!(code = ?error.code, error = %format!("{:#}", self.0), "request failed");
+        }
+
+        let body = ErrorBody {
+            status: status.as_u16(),
+            code: error.code,
+            message: error.message,
+        };
+        (status, Json(body)).into_response()
     }
 }
 
@@ -21,3 +197,63 @@ where
         Self(e.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use ethers::providers::{HttpClientError, JsonRpcError};
+
+    fn node_error(code: i64, message: &str) -> anyhow::Error {
+        RpcPoolError::Client(HttpClientError::JsonRpcError(JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }))
+        .into()
+    }
+
+    fn code(error: anyhow::Error) -> ErrorCode {
+        ApiError::classify(&error).code
+    }
+
+    #[test]
+    fn api_errors_keep_their_code_through_context() {
+        let error = anyhow::Error::from(ApiError::new(ErrorCode::FeeTooHigh, "fee above cap")).context("sending");
+        let classified = ApiError::classify(&error);
+        assert_eq!(classified.code, ErrorCode::FeeTooHigh);
+        assert_eq!(classified.message, "fee above cap");
+    }
+
+    #[test]
+    fn database_and_pool_errors_are_recognised_by_type() {
+        assert_eq!(code(sqlx::Error::RowNotFound.into()), ErrorCode::NotFound);
+        assert_eq!(code(sqlx::Error::PoolTimedOut.into()), ErrorCode::DatabaseUnavailable);
+        assert_eq!(code(sqlx::Error::Protocol("bad packet".into()).into()), ErrorCode::Internal);
+        assert_eq!(code(RpcPoolError::NoQuorum { matching: 1, required: 2 }.into()), ErrorCode::RpcUnavailable);
+    }
+
+    #[test]
+    fn node_errors_are_recognised_by_message() {
+        assert_eq!(code(node_error(-32000, "insufficient funds for gas * price + value")), ErrorCode::InsufficientFunds);
+        assert_eq!(code(node_error(-32000, "nonce too low")), ErrorCode::NonceConflict);
+        assert_eq!(code(node_error(-32000, "replacement transaction underpriced")), ErrorCode::NonceConflict);
+        assert_eq!(code(node_error(3, "execution reverted: ERC20: transfer amount exceeds balance")), ErrorCode::Reverted);
+        assert_eq!(code(anyhow!("Contract call reverted with data: 0x08c379a0")), ErrorCode::Reverted);
+        assert_eq!(code(anyhow!("error sending request for url")), ErrorCode::RpcUnavailable);
+    }
+
+    #[test]
+    fn revert_must_be_an_execution_revert() {
+        assert_eq!(code(anyhow!("failed to decode irreversible migration")), ErrorCode::Internal);
+        assert_eq!(code(node_error(-32000, "reverted checkpoint not found")), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn codes_map_to_statuses() {
+        assert_eq!(ErrorCode::InvalidRequest.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(ErrorCode::RateLimited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(ErrorCode::Reverted.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(ErrorCode::RpcUnavailable.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
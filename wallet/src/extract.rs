@@ -0,0 +1,162 @@
+//! Drop-in replacements for axum's `Json`, `Query` and `Path` extractors whose
+//! rejections go through [`AppError`], so a malformed body, query or path gets
+//! the same `invalid_request` error body as every other client error instead of
+//! axum's plain-text response
+
+use crate::error::{ApiError, AppError};
+use axum::extract::{FromRequest, FromRequestParts, OptionalFromRequest, Request};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Display;
+
+fn rejected(rejection: impl Display) -> AppError {
+    AppError::from(ApiError::invalid_request(rejection.to_string()))
+}
+
+/// JSON request body, also used for JSON responses
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match <axum::Json<T> as FromRequest<S>>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(rejected(rejection)),
+        }
+    }
+}
+
+/// Optional body: `None` without a JSON content type, rejected if present but malformed
+impl<T, S> OptionalFromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        match <axum::Json<T> as OptionalFromRequest<S>>::from_request(req, state).await {
+            Ok(value) => Ok(value.map(|axum::Json(value)| Self(value))),
+            Err(rejection) => Err(rejected(rejection)),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Self(value)),
+            Err(rejection) => Err(rejected(rejection)),
+        }
+    }
+}
+
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Self(value)),
+            Err(rejection) => Err(rejected(rejection)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::header::CONTENT_TYPE;
+    use axum::http::StatusCode;
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    #[derive(Debug, Deserialize)]
+    struct Transfer {
+        #[allow(dead_code)]
+        to: String,
+        #[allow(dead_code)]
+        amount: u64,
+    }
+
+    async fn error_body(error: AppError) -> (StatusCode, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn json_request(body: &'static str) -> Request {
+        Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn malformed_bodies_get_the_error_envelope() {
+        for body in [r#"{"to": "0x1""#, r#"{"to": "0x1", "amount": "ten"}"#] {
+            let Err(error) = <Json<Transfer> as FromRequest<()>>::from_request(json_request(body), &()).await else {
+                panic!("{} was accepted", body);
+            };
+            let (status, body) = error_body(error).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], "invalid_request");
+        }
+
+        let request = Request::builder().method("POST").body(Body::from("{}")).unwrap();
+        let Err(error) = <Json<Transfer> as FromRequest<()>>::from_request(request, &()).await else {
+            panic!("body without content type was accepted");
+        };
+        assert_eq!(error_body(error).await.1["code"], "invalid_request");
+    }
+
+    #[tokio::test]
+    async fn optional_bodies_may_be_missing_but_not_malformed() {
+        let request = Request::builder().method("POST").body(Body::empty()).unwrap();
+        let missing = <Json<Transfer> as OptionalFromRequest<()>>::from_request(request, &()).await;
+        assert!(matches!(missing, Ok(None)));
+
+        let malformed = <Json<Transfer> as OptionalFromRequest<()>>::from_request(json_request("{"), &()).await;
+        assert!(malformed.is_err());
+    }
+
+    #[tokio::test]
+    async fn malformed_queries_get_the_error_envelope() {
+        let (mut parts, _) = Request::builder().uri("/?to=0x1&amount=-1").body(()).unwrap().into_parts();
+        let Err(error) = Query::<Transfer>::from_request_parts(&mut parts, &()).await else {
+            panic!("negative amount was accepted");
+        };
+        let (status, body) = error_body(error).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_request");
+    }
+}
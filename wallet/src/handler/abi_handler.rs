@@ -0,0 +1,45 @@
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::model::app_model::AppState;
+use crate::model::response::{AddressResponse, ApiResponse};
+use crate::service::abi_service::AbiService;
+use axum::extract::State;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub struct AbiHandler;
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterAbiRequest {
+    pub address: String,
+    /// JSON ABI, or an array of human-readable signatures
+    pub abi: Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AbiListResponse {
+    #[schema(value_type = Vec<String>)]
+    pub contracts: Vec<Address>,
+}
+
+impl AbiHandler {
+    pub async fn register(
+        State(app_state): State<Arc<AppState>>,
+        Json(register_req): Json<RegisterAbiRequest>,
+    ) -> Result<Json<ApiResponse<AddressResponse>>, AppError> {
+        let address = AbiService::new(&app_state.mem.abis)?
+            .register(&register_req.address, register_req.abi)
+            .await?;
+        Ok(ApiResponse::success(AddressResponse { address }))
+    }
+
+    pub async fn list(
+        State(app_state): State<Arc<AppState>>,
+    ) -> Result<Json<ApiResponse<AbiListResponse>>, AppError> {
+        let contracts = AbiService::new(&app_state.mem.abis)?.list().await?;
+        Ok(ApiResponse::success(AbiListResponse { contracts }))
+    }
+}
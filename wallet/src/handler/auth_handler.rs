@@ -0,0 +1,134 @@
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::middleware::auth::credential;
+use crate::model::api_key::{ApiKey, AuthSession, CreatedApiKey, Scope};
+use crate::model::app_model::AppState;
+use crate::model::rate_limit::RateLimitOverride;
+use crate::model::response::ApiResponse;
+use crate::service::auth_service::AuthService;
+use crate::service::siwe_service::{SiweNonce, SiweService, SiweSession};
+use axum::extract::State;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub struct AuthHandler;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Lifetime in seconds, keys don't expire by default
+    pub expires_in: Option<u64>,
+    /// Restrict the key to a tenant's assets, platform keys see every tenant
+    pub tenant_id: Option<Uuid>,
+    /// Requests per window replacing the configured budgets for this key
+    #[serde(default)]
+    pub rate_limit: RateLimitOverride,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RotateApiKeyRequest {
+    /// Seconds the old key keeps working, 0 revokes it immediately
+    #[serde(default)]
+    pub grace_period: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SiweVerifyRequest {
+    /// EIP-4361 message exactly as signed
+    pub message: String,
+    /// Hex encoded `personal_sign` signature
+    pub signature: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeysResponse {
+    pub keys: Vec<ApiKey>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SessionsResponse {
+    pub sessions: Vec<AuthSession>,
+}
+
+impl AuthHandler {
+    pub async fn create_key(
+        State(app_state): State<Arc<AppState>>,
+        Json(create_req): Json<CreateApiKeyRequest>,
+    ) -> Result<Json<ApiResponse<CreatedApiKey>>, AppError> {
+        let created = AuthService::new(&app_state.db)?
+            .create_key(&create_req.name, &create_req.scopes, create_req.expires_in, create_req.tenant_id, create_req.rate_limit)
+            .await?;
+        Ok(ApiResponse::success(created))
+    }
+
+    pub async fn list_keys(
+        State(app_state): State<Arc<AppState>>,
+    ) -> Result<Json<ApiResponse<ApiKeysResponse>>, AppError> {
+        let keys = AuthService::new(&app_state.db)?.list_keys().await?;
+        Ok(ApiResponse::success(ApiKeysResponse { keys }))
+    }
+
+    pub async fn rotate_key(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+        rotate_req: Option<Json<RotateApiKeyRequest>>,
+    ) -> Result<Json<ApiResponse<CreatedApiKey>>, AppError> {
+        let grace_period = rotate_req.map_or(0, |Json(req)| req.grace_period);
+        let created = AuthService::new(&app_state.db)?.rotate_key(&id, grace_period).await?;
+        Ok(ApiResponse::success(created))
+    }
+
+    pub async fn revoke_key(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+    ) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+        let key = AuthService::new(&app_state.db)?.revoke_key(&id).await?;
+        Ok(ApiResponse::success(key))
+    }
+
+    pub async fn siwe_nonce(State(app_state): State<Arc<AppState>>) -> Result<Json<ApiResponse<SiweNonce>>, AppError> {
+        let nonce = SiweService::new(&app_state.db, &app_state.eth, &app_state.env.auth)?
+            .issue_nonce()
+            .await?;
+        Ok(ApiResponse::success(nonce))
+    }
+
+    pub async fn siwe_verify(
+        State(app_state): State<Arc<AppState>>,
+        Json(verify_req): Json<SiweVerifyRequest>,
+    ) -> Result<Json<ApiResponse<SiweSession>>, AppError> {
+        let session = SiweService::new(&app_state.db, &app_state.eth, &app_state.env.auth)?
+            .verify(&verify_req.message, &verify_req.signature)
+            .await?;
+        Ok(ApiResponse::success(session))
+    }
+
+    /// End the session the request is authenticated with
+    pub async fn logout(
+        State(app_state): State<Arc<AppState>>,
+        headers: HeaderMap,
+    ) -> Result<Json<ApiResponse<AuthSession>>, AppError> {
+        let token = credential(&headers)?;
+        let session = AuthService::new(&app_state.db)?.logout(token).await?;
+        Ok(ApiResponse::success(session))
+    }
+
+    pub async fn list_sessions(
+        State(app_state): State<Arc<AppState>>,
+    ) -> Result<Json<ApiResponse<SessionsResponse>>, AppError> {
+        let sessions = AuthService::new(&app_state.db)?.list_sessions().await?;
+        Ok(ApiResponse::success(SessionsResponse { sessions }))
+    }
+
+    pub async fn revoke_session(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+    ) -> Result<Json<ApiResponse<AuthSession>>, AppError> {
+        let session = AuthService::new(&app_state.db)?.revoke_session(&id).await?;
+        Ok(ApiResponse::success(session))
+    }
+}
@@ -1,37 +1,102 @@
 use crate::error::AppError;
+use crate::extract::{Json, Path, Query};
 use crate::model::app_model::AppState;
-use crate::service::block_service::BlockService;
+use crate::model::block_tracker::Reorg;
+use crate::model::response::ApiResponse;
+use crate::service::block_service::{BlockDetail, BlockPage, BlockService};
 use axum::extract::State;
-use axum::{response::IntoResponse, Json};
-use serde_json::json;
+use ethers::types::{Block, H256};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 pub struct BlockHandler;
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockQuery {
+    /// Return full transactions instead of hashes
+    #[serde(default)]
+    pub full: bool,
+    /// Include the receipts of every transaction
+    #[serde(default)]
+    pub receipts: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockRangeQuery {
+    pub from: u64,
+    pub to: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockHeightResponse {
+    pub block_height: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LatestBlockResponse {
+    /// Block as returned by `eth_getBlockByNumber`
+    #[schema(value_type = Object)]
+    pub latest_block: Block<H256>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReorgsResponse {
+    pub reorgs: Vec<Reorg>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockResponse {
+    pub block: BlockDetail,
+}
+
 impl BlockHandler {
     pub async fn get_block_height(
         State(app_state): State<Arc<AppState>>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "block_height": BlockService::new(&app_state.eth)?.get_block_height().await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<BlockHeightResponse>>, AppError> {
+        let block_height = BlockService::new(&app_state.eth, &app_state.mem.blocks)?
+            .get_block_height()
+            .await?;
+        Ok(ApiResponse::success(BlockHeightResponse { block_height }))
     }
 
     pub async fn get_latest_block(
         State(app_state): State<Arc<AppState>>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "latest_block": BlockService::new(&app_state.eth)?.get_latest_block().await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<LatestBlockResponse>>, AppError> {
+        let latest_block = BlockService::new(&app_state.eth, &app_state.mem.blocks)?
+            .get_latest_block()
+            .await?;
+        Ok(ApiResponse::success(LatestBlockResponse { latest_block }))
+    }
+
+    pub async fn get_reorgs(
+        State(app_state): State<Arc<AppState>>,
+    ) -> Result<Json<ApiResponse<ReorgsResponse>>, AppError> {
+        let reorgs = BlockService::new(&app_state.eth, &app_state.mem.blocks)?.get_reorgs().await?;
+        Ok(ApiResponse::success(ReorgsResponse { reorgs }))
+    }
+
+    pub async fn get_block(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+        Query(query): Query<BlockQuery>,
+    ) -> Result<Json<ApiResponse<BlockResponse>>, AppError> {
+        let block = BlockService::new(&app_state.eth, &app_state.mem.blocks)?
+            .get_block(&id, query.full, query.receipts)
+            .await?;
+        Ok(ApiResponse::success(BlockResponse { block }))
+    }
+
+    pub async fn get_blocks(
+        State(app_state): State<Arc<AppState>>,
+        Query(query): Query<BlockRangeQuery>,
+    ) -> Result<Json<ApiResponse<BlockPage>>, AppError> {
+        let page = BlockService::new(&app_state.eth, &app_state.mem.blocks)?
+            .get_blocks(query.from, query.to, query.limit)
+            .await?;
+        Ok(ApiResponse::success(page))
     }
 }
@@ -0,0 +1,55 @@
+use crate::error::AppError;
+use crate::extract::{Json, Query};
+use crate::model::api_key::Principal;
+use crate::model::app_model::AppState;
+use crate::model::deposit::Deposit;
+use crate::model::response::{AddressResponse, ApiResponse};
+use crate::service::deposit_service::DepositService;
+use axum::extract::State;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+pub struct DepositHandler;
+
+#[derive(Deserialize, ToSchema)]
+pub struct WatchAddressRequest {
+    pub address: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DepositQuery {
+    /// Only deposits to this address
+    pub address: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DepositsResponse {
+    pub deposits: Vec<Deposit>,
+}
+
+impl DepositHandler {
+    pub async fn watch_address(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Json(watch_req): Json<WatchAddressRequest>,
+    ) -> Result<Json<ApiResponse<AddressResponse>>, AppError> {
+        let address = DepositService::new(&app_state.mem.keyring, &app_state.mem.watched, &app_state.mem.deposits)?
+            .watch_address(&principal, &watch_req.address)
+            .await?;
+        Ok(ApiResponse::success(AddressResponse { address }))
+    }
+
+    pub async fn get_deposits(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Query(query): Query<DepositQuery>,
+    ) -> Result<Json<ApiResponse<DepositsResponse>>, AppError> {
+        let deposits = DepositService::new(&app_state.mem.keyring, &app_state.mem.watched, &app_state.mem.deposits)?
+            .get_deposits(&principal, query.address.as_deref())
+            .await?;
+        Ok(ApiResponse::success(DepositsResponse { deposits }))
+    }
+}
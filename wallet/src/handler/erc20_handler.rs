@@ -1,85 +1,109 @@
 use crate::error::AppError;
+use crate::extract::{Json, Path, Query};
+use crate::model::api_key::Principal;
 use crate::model::app_model::AppState;
-use crate::service::erc20_service::ERC20Service;
-use axum::extract::{Path, Query, State};
-use axum::response::IntoResponse;
-use axum::Json;
-use serde::Deserialize;
-use serde_json::json;
+use crate::model::listener::ListenerState;
+use crate::model::response::{ApiResponse, BalanceResponse, TransactionHashResponse};
+use crate::service::erc20_service::{ERC20Service, TokenInfo};
+use crate::service::outbound_service::OutboundTxService;
+use axum::extract::State;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
 pub struct ERC20Handler;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ERC20BalanceRequest {
     pub address: String,
     pub contract_address: String,
 }
 
-#[derive(Deserialize)]
-pub struct SendTxRequest {
+#[derive(Deserialize, ToSchema)]
+pub struct ERC20SendTxRequest {
     pub from: String,
     pub to: String,
+    /// Amount in whole tokens such as `1.5`, at most the token decimals
     pub amount: String,
     pub contract: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct BackfillRequest {
+    pub contract: String,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListenResponse {
+    pub status: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BackfillResponse {
+    /// Poll `/jobs/{id}` for progress
+    pub job_id: Uuid,
+}
+
 impl ERC20Handler {
     pub async fn get_balance(
         State(app_state): State<Arc<AppState>>,
         Query(req): Query<ERC20BalanceRequest>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status": 200,
-            "message": "success",
-            "data": {
-                "balance": ERC20Service::new(&app_state.eth, app_state.eth_ws.as_ref(), &app_state.mem.keyring, &app_state.mem.listening)?
-                    .get_balance(&req.address, &req.contract_address).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<BalanceResponse>>, AppError> {
+        let balance = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .get_balance(&req.address, &req.contract_address).await?;
+        Ok(ApiResponse::success(BalanceResponse { balance }))
     }
 
     pub async fn send_transaction(
         State(app_state): State<Arc<AppState>>,
-        Json(send_tx_req): Json<SendTxRequest>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status": 200,
-            "message": "success",
-            "data": {
-                "transaction_hash": ERC20Service::new(&app_state.eth, app_state.eth_ws.as_ref(), &app_state.mem.keyring, &app_state.mem.listening)?
-                    .send_transaction(&send_tx_req.from, &send_tx_req.to, &send_tx_req.amount, &send_tx_req.contract).await?
-            }
-        });
-        Ok(Json(response))
+        Extension(principal): Extension<Principal>,
+        Json(send_tx_req): Json<ERC20SendTxRequest>,
+    ) -> Result<Json<ApiResponse<TransactionHashResponse>>, AppError> {
+        let outbound = OutboundTxService::new(&app_state.db)?;
+        let sent = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .send_transaction(&principal, &outbound, &send_tx_req.from, &send_tx_req.to, &send_tx_req.amount, &send_tx_req.contract).await?;
+        Ok(ApiResponse::success(TransactionHashResponse { id: sent.id, transaction_hash: sent.tx_hash }))
     }
 
     pub async fn get_info(
         State(app_state): State<Arc<AppState>>,
         Path(contract_address): Path<String>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status": 200,
-            "message": "success",
-            "data": ERC20Service::new(&app_state.eth, app_state.eth_ws.as_ref(), &app_state.mem.keyring, &app_state.mem.listening)?
-                .get_info(&contract_address).await?
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<TokenInfo>>, AppError> {
+        let info = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .get_info(&contract_address).await?;
+        Ok(ApiResponse::success(info))
     }
 
     pub async fn listen(
         State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
         Path(contract_address): Path<String>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status": 200,
-            "message": "success",
-            "data": {
-                "status": ERC20Service::new(&app_state.eth, app_state.eth_ws.as_ref(), &app_state.mem.keyring, &app_state.mem.listening)?
-                    .listen(&contract_address).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<ListenResponse>>, AppError> {
+        let status = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .listen(&principal, &contract_address).await?;
+        Ok(ApiResponse::success(ListenResponse { status }))
+    }
+
+    pub async fn get_listeners(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+    ) -> Result<Json<ApiResponse<Vec<ListenerState>>>, AppError> {
+        let listeners = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .get_listeners(&principal).await;
+        Ok(ApiResponse::success(listeners))
+    }
+
+    pub async fn backfill(
+        State(app_state): State<Arc<AppState>>,
+        Json(backfill_req): Json<BackfillRequest>,
+    ) -> Result<Json<ApiResponse<BackfillResponse>>, AppError> {
+        let job_id = ERC20Service::new(&app_state.eth, &app_state.eth_ws, &app_state.mem.keyring, &app_state.mem.listeners, &app_state.mem.events, &app_state.mem.jobs, &app_state.env.eth().fee)?
+            .backfill(&backfill_req.contract, backfill_req.from_block, backfill_req.to_block).await?;
+        Ok(ApiResponse::success(BackfillResponse { job_id }))
     }
 }
@@ -1,18 +1,24 @@
 use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::model::api_key::Principal;
 use crate::model::app_model::AppState;
-use crate::service::ether_service::EtherService;
-use axum::extract::{Path, State};
-use axum::{response::IntoResponse, Json};
+use crate::model::response::{ApiResponse, BalanceResponse, TransactionHashResponse};
+use crate::service::ether_service::{EtherService, TransactionDetail};
+use crate::service::outbound_service::OutboundTxService;
+use crate::service::trace_service::{TraceService, TransactionTrace};
+use axum::extract::State;
+use axum::Extension;
 use serde::Deserialize;
-use serde_json::json;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 pub struct EtherHandler;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SendTxRequest {
     pub from: String,
     pub to: String,
+    /// Amount in ETH such as `1.5`, at most 18 decimals
     pub amount: String,
 }
 
@@ -20,45 +26,37 @@ impl EtherHandler {
     pub async fn get_balance(
         State(app_state): State<Arc<AppState>>,
         Path(address): Path<String>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "balance": EtherService::new(&app_state.eth, &app_state.mem.keyring)?
-                    .get_balance(&address).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<BalanceResponse>>, AppError> {
+        let balance = EtherService::new(&app_state.eth, &app_state.mem.keyring, &app_state.mem.abis, &app_state.env.eth().fee)?
+            .get_balance(&address).await?;
+        Ok(ApiResponse::success(BalanceResponse { balance }))
     }
 
     pub async fn get_transaction(
         State(app_state): State<Arc<AppState>>,
         Path(tx_hash): Path<String>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "transaction": EtherService::new(&app_state.eth, &app_state.mem.keyring)?
-                    .get_transaction(&tx_hash).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<TransactionDetail>>, AppError> {
+        let detail = EtherService::new(&app_state.eth, &app_state.mem.keyring, &app_state.mem.abis, &app_state.env.eth().fee)?
+            .get_transaction(&tx_hash).await?;
+        Ok(ApiResponse::success(detail))
     }
 
     pub async fn send_transaction(
         State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
         Json(send_tx_req): Json<SendTxRequest>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "transaction_hash": EtherService::new(&app_state.eth, &app_state.mem.keyring)?
-                    .send_transaction(&send_tx_req.from, &send_tx_req.to, &send_tx_req.amount).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<TransactionHashResponse>>, AppError> {
+        let outbound = OutboundTxService::new(&app_state.db)?;
+        let sent = EtherService::new(&app_state.eth, &app_state.mem.keyring, &app_state.mem.abis, &app_state.env.eth().fee)?
+            .send_transaction(&principal, &outbound, &send_tx_req.from, &send_tx_req.to, &send_tx_req.amount).await?;
+        Ok(ApiResponse::success(TransactionHashResponse { id: sent.id, transaction_hash: sent.tx_hash }))
+    }
+
+    pub async fn trace_transaction(
+        State(app_state): State<Arc<AppState>>,
+        Path(tx_hash): Path<String>,
+    ) -> Result<Json<ApiResponse<TransactionTrace>>, AppError> {
+        let trace = TraceService::new(&app_state.eth)?.trace_transaction(&tx_hash).await?;
+        Ok(ApiResponse::success(trace))
     }
 }
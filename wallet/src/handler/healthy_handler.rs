@@ -1,12 +1,63 @@
 use crate::error::AppError;
-use axum::{response::IntoResponse, Json};
-use serde_json::json;
+use crate::extract::Json;
+use crate::model::app_model::AppState;
+use crate::service::health_service::{DependencyCheck, HealthService};
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
 
-pub async fn healthy() -> Result<impl IntoResponse, AppError> {
-    let response = json!({
-        "status":"success",
-        "message":"health is working"
-    });
+#[derive(Serialize, ToSchema)]
+pub struct HealthyResponse {
+    pub status: String,
+    pub message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LiveResponse {
+    pub status: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadyResponse {
+    /// `ok`, or `unavailable` while a critical dependency is down
+    pub status: String,
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
+
+pub async fn healthy() -> Result<Json<HealthyResponse>, AppError> {
+    Ok(Json(HealthyResponse {
+        status: "success".to_string(),
+        message: "health is working".to_string(),
+    }))
+}
+
+/// Liveness probe, only tells the process is serving requests
+pub async fn live() -> Result<Json<LiveResponse>, AppError> {
+    Ok(Json(LiveResponse { status: "ok".to_string() }))
+}
+
+/// Readiness probe, 503 while a critical dependency is down or a background task is stale
+pub async fn ready(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<(StatusCode, Json<ReadyResponse>), AppError> {
+    let report = HealthService::new(
+        &app_state.db,
+        &app_state.eth,
+        &app_state.eth_ws,
+        &app_state.mem.listeners,
+        &app_state.mem.heartbeats,
+        app_state.env.eth(),
+    )?
+    .ready()
+    .await;
 
-    Ok(Json(response))
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let response = ReadyResponse {
+        status: if report.ready { "ok" } else { "unavailable" }.to_string(),
+        checks: report.checks,
+    };
+    Ok((status, Json(response)))
 }
@@ -0,0 +1,49 @@
+use crate::error::AppError;
+use crate::error::parse_address;
+use crate::extract::{Json, Path, Query};
+use crate::model::api_key::Principal;
+use crate::model::app_model::AppState;
+use crate::model::response::ApiResponse;
+use crate::service::history_service::{HistoryPage, HistoryService};
+use crate::service::tenant_service::check_address;
+use axum::extract::State;
+use axum::Extension;
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+pub struct HistoryHandler;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<u64>,
+    /// `in` or `out`, self transfers match both, or `self` for self transfers only
+    pub direction: Option<String>,
+    /// `ETH` or a token contract address
+    pub asset: Option<String>,
+}
+
+impl HistoryHandler {
+    pub async fn get_history(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Path(address): Path<String>,
+        Query(query): Query<HistoryQuery>,
+    ) -> Result<Json<ApiResponse<HistoryPage>>, AppError> {
+        check_address(&principal, parse_address(&address)?, &app_state.mem.keyring, &app_state.mem.watched).await?;
+        let page = HistoryService::new(&app_state.db)?
+            .get_history(
+                principal.tenant,
+                &address,
+                query.cursor.as_deref(),
+                query.limit,
+                query.direction.as_deref(),
+                query.asset.as_deref(),
+            )
+            .await?;
+        Ok(ApiResponse::success(page))
+    }
+}
@@ -0,0 +1,27 @@
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::model::app_model::AppState;
+use crate::model::job::Job;
+use crate::model::response::ApiResponse;
+use crate::service::job_service::JobService;
+use axum::extract::State;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub struct JobHandler;
+
+#[derive(Serialize, ToSchema)]
+pub struct JobResponse {
+    pub job: Job,
+}
+
+impl JobHandler {
+    pub async fn get_job(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+    ) -> Result<Json<ApiResponse<JobResponse>>, AppError> {
+        let job = JobService::new(&app_state.mem.jobs)?.get_job(&id).await?;
+        Ok(ApiResponse::success(JobResponse { job }))
+    }
+}
@@ -0,0 +1,41 @@
+use crate::error::AppError;
+use crate::metrics::METRICS;
+use crate::model::app_model::AppState;
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use std::sync::Arc;
+
+pub struct MetricsHandler;
+
+impl MetricsHandler {
+    /// Prometheus scrape endpoint. Gauges derived from in-memory state are
+    /// refreshed here instead of on every change.
+    pub async fn metrics(State(app_state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+        METRICS
+            .keyring_keys
+            .set(app_state.mem.keyring.read().await.addresses().len() as i64);
+
+        let listeners = app_state.mem.listeners.read().await.all();
+        let head = app_state.mem.blocks.read().await.head().map(|block| block.number);
+        METRICS.listeners_active.set(listeners.len() as i64);
+        for listener in listeners {
+            if let (Some(head), Some(seen)) = (head, listener.last_seen_block) {
+                METRICS
+                    .listener_lag
+                    .with_label_values(&[&format!("{:?}", listener.contract)])
+                    .set(head.saturating_sub(seen) as i64);
+            }
+        }
+
+        let size = app_state.db.size() as i64;
+        let idle = app_state.db.num_idle() as i64;
+        METRICS.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        METRICS.db_pool_connections.with_label_values(&["active"]).set(size - idle);
+        METRICS
+            .db_pool_max_connections
+            .set(app_state.env.database.max_connections as i64);
+
+        Ok(([(CONTENT_TYPE, "text/plain; version=0.0.4")], METRICS.encode()?))
+    }
+}
@@ -3,3 +3,13 @@ pub mod block_handler;
 pub mod wallet_handler;
 pub mod erc20_handler;
 pub mod ether_handler;
+pub mod job_handler;
+pub mod deposit_handler;
+pub mod history_handler;
+pub mod abi_handler;
+pub mod rpc_handler;
+pub mod metrics_handler;
+pub mod openapi_handler;
+pub mod auth_handler;
+pub mod tenant_handler;
+pub mod tx_handler;
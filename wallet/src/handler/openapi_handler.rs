@@ -0,0 +1,12 @@
+use crate::extract::Json;
+use crate::openapi::ApiDoc;
+use utoipa::openapi::OpenApi as OpenApiSpec;
+use utoipa::OpenApi;
+
+pub struct OpenApiHandler;
+
+impl OpenApiHandler {
+    pub async fn spec() -> Json<OpenApiSpec> {
+        Json(ApiDoc::openapi())
+    }
+}
@@ -0,0 +1,26 @@
+use crate::chain::eth::pool::EndpointStatus;
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::model::app_model::AppState;
+use crate::model::response::ApiResponse;
+use crate::service::rpc_service::RpcService;
+use axum::extract::State;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub struct RpcHandler;
+
+#[derive(Serialize, ToSchema)]
+pub struct EndpointsResponse {
+    pub endpoints: Vec<EndpointStatus>,
+}
+
+impl RpcHandler {
+    pub async fn get_endpoints(
+        State(app_state): State<Arc<AppState>>,
+    ) -> Result<Json<ApiResponse<EndpointsResponse>>, AppError> {
+        let endpoints = RpcService::new(&app_state.eth)?.get_endpoints().await?;
+        Ok(ApiResponse::success(EndpointsResponse { endpoints }))
+    }
+}
@@ -0,0 +1,47 @@
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::model::app_model::AppState;
+use crate::model::response::ApiResponse;
+use crate::model::tenant::{Tenant, TenantAssets};
+use crate::service::tenant_service::TenantService;
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+pub struct TenantHandler;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTenantRequest {
+    pub name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TenantsResponse {
+    pub tenants: Vec<Tenant>,
+}
+
+impl TenantHandler {
+    pub async fn create(
+        State(app_state): State<Arc<AppState>>,
+        Json(create_req): Json<CreateTenantRequest>,
+    ) -> Result<Json<ApiResponse<Tenant>>, AppError> {
+        let tenant = TenantService::new(&app_state.db)?.create_tenant(&create_req.name).await?;
+        Ok(ApiResponse::success(tenant))
+    }
+
+    pub async fn list(State(app_state): State<Arc<AppState>>) -> Result<Json<ApiResponse<TenantsResponse>>, AppError> {
+        let tenants = TenantService::new(&app_state.db)?.list_tenants().await?;
+        Ok(ApiResponse::success(TenantsResponse { tenants }))
+    }
+
+    pub async fn get_assets(
+        State(app_state): State<Arc<AppState>>,
+        Path(id): Path<String>,
+    ) -> Result<Json<ApiResponse<TenantAssets>>, AppError> {
+        let assets = TenantService::new(&app_state.db)?
+            .get_assets(&id, &app_state.mem.keyring, &app_state.mem.watched, &app_state.mem.listeners)
+            .await?;
+        Ok(ApiResponse::success(assets))
+    }
+}
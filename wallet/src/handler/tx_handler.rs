@@ -0,0 +1,52 @@
+use crate::error::AppError;
+use crate::extract::{Json, Path, Query};
+use crate::model::api_key::Principal;
+use crate::model::app_model::AppState;
+use crate::model::outbound_tx::OutboundTx;
+use crate::model::response::ApiResponse;
+use crate::service::outbound_service::OutboundTxService;
+use axum::extract::State;
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+pub struct TxHandler;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TxQuery {
+    /// built, signed, submitted, included, confirmed, reverted, rejected, dropped or expired
+    pub status: Option<String>,
+    /// Only transactions flagged (or not) as stuck
+    pub stuck: Option<bool>,
+    /// Page size, 50 by default and at most 200
+    pub limit: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionsResponse {
+    pub transactions: Vec<OutboundTx>,
+}
+
+impl TxHandler {
+    pub async fn get_tx(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Path(id): Path<String>,
+    ) -> Result<Json<ApiResponse<OutboundTx>>, AppError> {
+        let tx = OutboundTxService::new(&app_state.db)?.get(&principal, &id).await?;
+        Ok(ApiResponse::success(tx))
+    }
+
+    pub async fn list_txs(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Query(query): Query<TxQuery>,
+    ) -> Result<Json<ApiResponse<TransactionsResponse>>, AppError> {
+        let transactions = OutboundTxService::new(&app_state.db)?
+            .list(&principal, query.status.as_deref(), query.stuck, query.limit)
+            .await?;
+        Ok(ApiResponse::success(TransactionsResponse { transactions }))
+    }
+}
@@ -1,32 +1,117 @@
 use crate::error::AppError;
+use crate::extract::Json;
+use crate::model::api_key::Principal;
 use crate::model::app_model::AppState;
-use crate::service::wallet_service::WalletService;
+use crate::model::response::{AddressResponse, ApiResponse};
+use crate::service::wallet_service::{WalletEntity, WalletService};
 use axum::extract::State;
-use axum::{response::IntoResponse, Json};
-use serde::Deserialize;
-use serde_json::json;
+use axum::Extension;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 pub struct WalletHandler;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ImportPriKeyRequest {
+    /// Hex encoded secp256k1 key, with or without `0x`
     pub private_key: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct CreateWalletRequest {
+    /// 12, 15, 18, 21 or 24, 12 by default
+    pub word_count: Option<usize>,
+    /// Optional BIP-39 passphrase, needed with the mnemonic to restore the wallet
+    pub passphrase: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportMnemonicRequest {
+    /// BIP-39 English phrase, its first account `m/44'/60'/0'/0/0` is imported
+    pub mnemonic: String,
+    pub passphrase: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportKeystoreRequest {
+    /// Web3 Secret Storage (V3) keystore, scrypt or pbkdf2
+    #[schema(value_type = Object)]
+    pub keystore: Value,
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportKeystoreRequest {
+    pub address: String,
+    /// Password the exported keystore is encrypted with
+    pub password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KeystoreResponse {
+    #[schema(value_type = String)]
+    pub address: Address,
+    /// Web3 Secret Storage (V3) keystore
+    #[schema(value_type = Object)]
+    pub keystore: Value,
+}
+
 impl WalletHandler {
     pub async fn import_private_key(
         State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
         Json(import_key_req): Json<ImportPriKeyRequest>,
-    ) -> Result<impl IntoResponse, AppError> {
-        let response = json!({
-            "status":200,
-            "message":"success",
-            "data":{
-                "address": WalletService::new(&app_state.mem.keyring)?
-                    .import_private_key(&import_key_req.private_key).await?
-            }
-        });
-        Ok(Json(response))
+    ) -> Result<Json<ApiResponse<AddressResponse>>, AppError> {
+        let address = WalletService::new(&app_state.mem.keyring, app_state.keystore.as_ref())?
+            .import_private_key(&principal, &import_key_req.private_key)
+            .await?;
+        Ok(ApiResponse::success(AddressResponse { address }))
+    }
+
+    pub async fn create_wallet(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Json(create_req): Json<CreateWalletRequest>,
+    ) -> Result<Json<ApiResponse<WalletEntity>>, AppError> {
+        let wallet = WalletService::new(&app_state.mem.keyring, app_state.keystore.as_ref())?
+            .create_wallet(&principal, create_req.word_count.unwrap_or(12), create_req.passphrase.as_deref())
+            .await?;
+        Ok(ApiResponse::success(wallet))
+    }
+
+    pub async fn import_mnemonic(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Json(import_req): Json<ImportMnemonicRequest>,
+    ) -> Result<Json<ApiResponse<AddressResponse>>, AppError> {
+        let address = WalletService::new(&app_state.mem.keyring, app_state.keystore.as_ref())?
+            .import_mnemonic(&principal, &import_req.mnemonic, import_req.passphrase.as_deref())
+            .await?;
+        Ok(ApiResponse::success(AddressResponse { address }))
+    }
+
+    pub async fn import_keystore(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Json(import_req): Json<ImportKeystoreRequest>,
+    ) -> Result<Json<ApiResponse<AddressResponse>>, AppError> {
+        let address = WalletService::new(&app_state.mem.keyring, app_state.keystore.as_ref())?
+            .import_keystore(&principal, import_req.keystore, import_req.password)
+            .await?;
+        Ok(ApiResponse::success(AddressResponse { address }))
+    }
+
+    pub async fn export_keystore(
+        State(app_state): State<Arc<AppState>>,
+        Extension(principal): Extension<Principal>,
+        Json(export_req): Json<ExportKeystoreRequest>,
+    ) -> Result<Json<ApiResponse<KeystoreResponse>>, AppError> {
+        let (address, keystore) = WalletService::new(&app_state.mem.keyring, app_state.keystore.as_ref())?
+            .export_keystore(&principal, &export_req.address, export_req.password)
+            .await?;
+        Ok(ApiResponse::success(KeystoreResponse { address, keystore }))
     }
 }
@@ -1,9 +1,14 @@
 pub mod chain;
 pub mod config;
 pub mod model;
+pub mod openapi;
 pub mod handler;
+pub mod metrics;
+pub mod middleware;
 pub mod error;
+pub mod extract;
 pub mod router;
 pub mod runtime;
 pub mod service;
+pub mod telemetry;
 pub mod types;
@@ -1,66 +1,229 @@
 use anyhow::Result;
-use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
-use axum::http::Method;
-use ethers::providers::{Http, Provider, Ws};
+use axum::http::header::{ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{HeaderName, HeaderValue, Method};
+use ethers::providers::Provider;
 use sqlx::mysql::MySqlPoolOptions;
-use std::collections::HashSet;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use tower_http::cors::CorsLayer;
+use wallet::chain::eth::retry::RetryPolicy;
+use wallet::chain::eth::{RpcPool, WsConnection};
+use wallet::model::abi_registry::AbiRegistry;
+use wallet::model::api_key::Scope;
 use wallet::model::app_model::MemoryStorage;
+use wallet::model::block_tracker::BlockTracker;
+use wallet::model::deposit::DepositStore;
+use wallet::model::event_store::EventStore;
+use wallet::model::heartbeat::Heartbeats;
+use wallet::model::job::JobRegistry;
 use wallet::model::keyring::Keyring;
+use wallet::model::keystore::KeyStore;
+use wallet::model::listener::ListenerRegistry;
+use wallet::model::rate_limit::{RateLimitOverride, RateLimitStore};
+use wallet::model::watchlist::WatchList;
+use wallet::service::auth_service::AuthService;
+use wallet::service::block_feed::BlockFeed;
+use wallet::service::block_service::BlockMonitor;
+use wallet::service::deposit_service::DepositScanner;
+use wallet::service::outbound_service::TxTracker;
+use wallet::service::history_service::HistoryIndexer;
+use wallet::service::idempotency_service;
+use wallet::middleware::auth::API_KEY_HEADER;
+use wallet::middleware::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER};
+use wallet::middleware::rate_limit::RATE_LIMIT_HEADERS;
+use wallet::middleware::request_id::REQUEST_ID_HEADER;
+use wallet::telemetry;
+use wallet::config::server_config::KeystoreConfig;
 use wallet::{config::server_config::Config, model::app_model::AppState, router::create_route};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
-    let config = Config::init();
+
+    // --config <path> selects the config file, --check-config only validates it,
+    // --create-admin-key <name> issues an admin API key to bootstrap access
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from);
+    let check_only = args.iter().any(|arg| arg == "--check-config");
+    let admin_key_name = args
+        .iter()
+        .position(|arg| arg == "--create-admin-key")
+        .map(|i| args.get(i + 1).cloned().unwrap_or_else(|| "admin".to_string()));
+
+    let config = match Config::load(config_path.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if check_only {
+        println!("✅ configuration is valid");
+        return Ok(());
+    }
+    telemetry::init(&config.logging);
 
     let pool = MySqlPoolOptions::new()
-        .max_connections(10)
-        .connect(&config.dsn)
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout))
+        .connect(&config.database.dsn)
         .await?;
+    sqlx::migrate!().run(&pool).await?;
 
-    let eth_provider = Provider::<Http>::try_from(&config.eth_url)?;
+    if let Some(name) = admin_key_name {
+        let created = AuthService::new(&pool)?.create_key(&name, &[Scope::Admin], None, None, RateLimitOverride::default()).await?;
+        println!("🔑 admin API key {} ({}), store it now, it is not shown again:", created.api_key.name, created.api_key.id);
+        println!("{}", created.key);
+        return Ok(());
+    }
 
-    // Try to initialize WebSocket Provider (for event listening)
-    // If connection fails, will use None and fallback to HTTP Provider
-    let eth_ws_provider = match Provider::<Ws>::connect(&config.eth_ws_url()).await {
-        Ok(provider) => {
-            println!("✅ WebSocket Provider connected successfully: {}", config.eth_ws_url());
-            Some(provider)
-        }
-        Err(e) => {
-            eprintln!("⚠️  WebSocket Provider connection failed: {}, will use HTTP Provider for event listening: {}", config.eth_ws_url(), e);
-            None
+    // Keys persisted by a previous run are decrypted before serving requests
+    let keystore = open_keystore(&config.keystore)?;
+    let mut keyring = Keyring::new();
+    if let Some(store) = keystore.clone() {
+        let keys = tokio::task::spawn_blocking(move || store.load()).await??;
+        let count = keys.len();
+        for (wallet, tenant) in keys {
+            keyring.add_wallet(wallet, tenant)?;
         }
+        tracing::info!(keys = count, dir = %config.keystore.dir, "keystore loaded");
+    }
+
+    let retry_policy = RetryPolicy {
+        max_retries: config.rpc.max_retries,
+        base_delay: Duration::from_millis(config.rpc.retry_base_ms),
+        max_delay: Duration::from_millis(config.rpc.retry_max_ms),
     };
+    let rpc_pool = RpcPool::new(
+        &config.eth().rpc_urls,
+        config.rpc.max_block_lag,
+        config.rpc.quorum,
+        retry_policy,
+        config.rpc.max_rps,
+        Duration::from_secs(config.rpc.timeout),
+    )?;
+    rpc_pool.spawn_health_checks(config.rpc.health_interval);
+    let eth_provider = Provider::new(rpc_pool);
 
+    // WebSocket Provider for event listening, connected and kept alive in the
+    // background; listeners poll over HTTP while it is unavailable
+    let eth_ws = WsConnection::new(config.eth().ws_urls.clone());
+    eth_ws.spawn();
+
+    let (reorg_tx, _) = broadcast::channel(16);
     let mem_store = MemoryStorage {
-        keyring: RwLock::new(Keyring::new()),
-        listening: RwLock::new(HashSet::new()),
+        keyring: Arc::new(RwLock::new(keyring)),
+        listeners: Arc::new(RwLock::new(ListenerRegistry::new())),
+        watched: Arc::new(RwLock::new(WatchList::new())),
+        deposits: Arc::new(RwLock::new(DepositStore::new())),
+        events: Arc::new(RwLock::new(EventStore::new())),
+        jobs: Arc::new(RwLock::new(JobRegistry::new())),
+        blocks: Arc::new(RwLock::new(BlockTracker::new(config.listener.block_track_depth))),
+        reorgs: reorg_tx,
+        abis: RwLock::new(AbiRegistry::new()),
+        rate_limits: RateLimitStore::new(),
+        heartbeats: Arc::new(Heartbeats::new()),
     };
 
     let app_state = Arc::new(AppState {
         db: pool,
         env: config,
         eth: eth_provider,
-        eth_ws: eth_ws_provider,
+        eth_ws,
+        keystore,
         mem: mem_store,
     });
 
+    // Deposit scanner and history indexer walk the same blocks, fetched once
+    let block_feed = Arc::new(BlockFeed::new(app_state.eth.clone(), app_state.env.listener.trace_internal_txs));
+
+    BlockMonitor::new(
+        block_feed.clone(),
+        app_state.mem.blocks.clone(),
+        app_state.mem.events.clone(),
+        app_state.mem.deposits.clone(),
+        app_state.mem.listeners.clone(),
+        app_state.mem.reorgs.clone(),
+        app_state.env.listener.block_poll_interval,
+    )
+    .spawn(app_state.mem.heartbeats.clone());
+
+    DepositScanner::new(
+        block_feed.clone(),
+        app_state.mem.keyring.clone(),
+        app_state.mem.watched.clone(),
+        app_state.mem.deposits.clone(),
+        app_state.env.eth().confirmations,
+        app_state.env.listener.deposit_poll_interval,
+    )
+    .spawn(app_state.mem.heartbeats.clone());
+
+    HistoryIndexer::new(
+        app_state.db.clone(),
+        block_feed,
+        app_state.mem.keyring.clone(),
+        app_state.mem.watched.clone(),
+        app_state.mem.reorgs.subscribe(),
+        app_state.env.listener.block_poll_interval,
+    )
+    .spawn(app_state.mem.heartbeats.clone());
+
+    TxTracker::new(
+        app_state.db.clone(),
+        app_state.eth.clone(),
+        app_state.env.eth().confirmations,
+        app_state.env.listener.tx_poll_interval,
+        app_state.env.listener.tx_stuck_after,
+        app_state.env.listener.tx_dropped_after,
+    )
+    .spawn(app_state.mem.heartbeats.clone());
+
+    idempotency_service::spawn_cleanup(app_state.db.clone());
+
     run(app_state).await?;
 
     Ok(())
 }
 
+/// Open the configured keystore, prompting for its passphrase when the configuration has none
+fn open_keystore(config: &KeystoreConfig) -> Result<Option<KeyStore>> {
+    if config.dir.is_empty() {
+        return Ok(None);
+    }
+    let passphrase = if config.passphrase.is_empty() {
+        eprint!("🔐 keystore passphrase: ");
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        config.passphrase.clone()
+    };
+    if passphrase.is_empty() {
+        anyhow::bail!("keystore.passphrase is not set and none was entered");
+    }
+    Ok(Some(KeyStore::open(&config.dir, passphrase)?))
+}
+
 async fn run(app_state: Arc<AppState>) -> Result<()> {
     // CORS configuration
-    let orgins = [
-        "http://localhost:3000".parse()?,
-        // Add other allowed origins here
-    ];
+    let orgins = app_state
+        .env
+        .server
+        .cors_origins
+        .iter()
+        .map(|origin| origin.parse())
+        .collect::<Result<Vec<HeaderValue>, _>>()?;
     let cors = CorsLayer::new()
         .allow_origin(orgins)
         .allow_methods([
@@ -71,17 +234,36 @@ async fn run(app_state: Arc<AppState>) -> Result<()> {
             Method::OPTIONS,
         ])
         .allow_credentials(true)
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE, CONTENT_DISPOSITION]);
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            CONTENT_DISPOSITION,
+            HeaderName::from_static(API_KEY_HEADER),
+            HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers(
+            [
+                HeaderName::from_static(REQUEST_ID_HEADER),
+                HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+                RETRY_AFTER,
+            ]
+                .into_iter()
+                .chain(RATE_LIMIT_HEADERS.map(HeaderName::from_static))
+                .collect::<Vec<_>>(),
+        );
 
-    let port = app_state.env.port;
+    let port = app_state.env.server.port;
     let addr = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(&addr).await?;
 
     let route = create_route(app_state).layer(cors);
 
-    println!("✅ server start at http://{}", addr);
+    tracing::info!(%addr, "server started");
 
-    axum::serve(listener, route).await?;
+    // Peer address is recorded as the last use of API keys
+    axum::serve(listener, route.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
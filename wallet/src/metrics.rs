@@ -0,0 +1,149 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Process wide metrics, recorded from handlers, services and the RPC transport
+/// and exposed in Prometheus text format on `/metrics`
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub rpc_requests: IntCounterVec,
+    pub rpc_errors: IntCounterVec,
+    pub rpc_request_duration: HistogramVec,
+    pub listeners_active: IntGauge,
+    pub listener_events: IntCounterVec,
+    pub listener_lag: IntGaugeVec,
+    pub transactions_sent: IntCounterVec,
+    pub keyring_keys: IntGauge,
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_max_connections: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("wallet".to_string()), None).expect("valid metrics prefix");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route"),
+            &["method", "route"],
+        )
+        .unwrap();
+        let rpc_requests = IntCounterVec::new(
+            Opts::new("rpc_requests_total", "JSON-RPC calls by method and endpoint"),
+            &["method", "endpoint"],
+        )
+        .unwrap();
+        let rpc_errors = IntCounterVec::new(
+            Opts::new("rpc_errors_total", "Failed JSON-RPC calls by method and endpoint"),
+            &["method", "endpoint"],
+        )
+        .unwrap();
+        let rpc_request_duration = HistogramVec::new(
+            HistogramOpts::new("rpc_request_duration_seconds", "JSON-RPC latency by method and endpoint"),
+            &["method", "endpoint"],
+        )
+        .unwrap();
+        let listeners_active = IntGauge::new("listeners_active", "Contract event listeners running").unwrap();
+        let listener_events = IntCounterVec::new(
+            Opts::new("listener_events_total", "Events processed by contract listeners"),
+            &["contract"],
+        )
+        .unwrap();
+        let listener_lag = IntGaugeVec::new(
+            Opts::new(
+                "listener_lag_blocks",
+                "Blocks between the chain head and the last block a listener recorded events for or caught up to",
+            ),
+            &["contract"],
+        )
+        .unwrap();
+        let transactions_sent = IntCounterVec::new(
+            Opts::new("transactions_sent_total", "Outgoing transactions by asset kind and status"),
+            &["kind", "status"],
+        )
+        .unwrap();
+        let keyring_keys = IntGauge::new("keyring_keys", "Keys held in the keyring").unwrap();
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .unwrap();
+        let db_pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Configured database pool size").unwrap();
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(rpc_requests.clone()),
+            Box::new(rpc_errors.clone()),
+            Box::new(rpc_request_duration.clone()),
+            Box::new(listeners_active.clone()),
+            Box::new(listener_events.clone()),
+            Box::new(listener_lag.clone()),
+            Box::new(transactions_sent.clone()),
+            Box::new(keyring_keys.clone()),
+            Box::new(db_pool_connections.clone()),
+            Box::new(db_pool_max_connections.clone()),
+        ] {
+            registry.register(collector).expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            rpc_requests,
+            rpc_errors,
+            rpc_request_duration,
+            listeners_active,
+            listener_events,
+            listener_lag,
+            transactions_sent,
+            keyring_keys,
+            db_pool_connections,
+            db_pool_max_connections,
+        }
+    }
+
+    pub fn observe_http(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_rpc(&self, method: &str, endpoint: &str, success: bool, elapsed: Duration) {
+        self.rpc_requests.with_label_values(&[method, endpoint]).inc();
+        if !success {
+            self.rpc_errors.with_label_values(&[method, endpoint]).inc();
+        }
+        self.rpc_request_duration
+            .with_label_values(&[method, endpoint])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// `status` is "submitted" when the node accepted the transaction, "failed" when it
+    /// was refused and "unknown" when the broadcast timed out. The tracker later counts
+    /// each as "confirmed", "reverted" or "dropped", and abandoned ones as "expired".
+    pub fn transaction_sent(&self, kind: &str, status: &str) {
+        self.transactions_sent.with_label_values(&[kind, status]).inc();
+    }
+
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::{ApiError, AppError, ErrorCode};
+use crate::model::api_key::Scope;
+use crate::middleware::rate_limit::{ip_client, too_many_requests};
+use crate::model::app_model::AppState;
+use crate::model::rate_limit::RateClass;
+use crate::service::auth_service::AuthService;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Span;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API key or session token sent as `Authorization: Bearer <token>` or `X-Api-Key`
+pub fn credential(headers: &HeaderMap) -> Result<&str, ApiError> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| ApiError::new(ErrorCode::Unauthorized, "missing API key or session token"))
+}
+
+/// Authenticate the caller from `Authorization: Bearer <token>` or `X-Api-Key`,
+/// with an API key or a SIWE session token, and require `scope`. The [`Principal`](crate::model::api_key::Principal)
+/// is then available to handlers as an extension. Client IPs with too many failed
+/// attempts get 429 until their `rate_limit.auth_failures` window resets.
+pub async fn authorize(
+    State((app_state, scope)): State<(Arc<AppState>, Scope)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let key = credential(request.headers())?;
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    // Failed attempts are counted per IP, so guessing keys is refused before it costs a lookup
+    let config = &app_state.env.rate_limit;
+    let failures = config.limit(RateClass::AuthFailure);
+    let window = Duration::from_secs(config.window);
+    let client = ip_client(ip);
+    if failures > 0 {
+        let status = app_state.mem.rate_limits.peek(&client, RateClass::AuthFailure, failures, window);
+        if !status.allowed {
+            let message = format!("too many failed authentications, retry in {}s", status.reset);
+            return Ok(too_many_requests(&status, config.window, message));
+        }
+    }
+
+    let principal = match AuthService::new(&app_state.db)?.authenticate(key, ip, &app_state.env.auth).await {
+        Ok(principal) => principal,
+        Err(e) => {
+            if failures > 0 && e.downcast_ref::<ApiError>().is_some_and(|e| e.code == ErrorCode::Unauthorized) {
+                app_state.mem.rate_limits.check(&client, RateClass::AuthFailure, failures, window);
+            }
+            return Err(e.into());
+        }
+    };
+    Span::current().record("principal", principal.subject.to_string());
+
+    if !principal.has_scope(scope) {
+        return Err(ApiError::new(ErrorCode::Forbidden, format!("credentials lack the {} scope", scope.as_str())).into());
+    }
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,95 @@
+use crate::error::{ApiError, AppError};
+use crate::model::api_key::Principal;
+use crate::model::app_model::AppState;
+use crate::service::idempotency_service::{Claim, IdempotencyService, StoredResponse};
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+use tracing::warn;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses replayed from a previous request with the same key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+/// Request bodies are buffered to be fingerprinted, matching axum's default body limit
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Honor `Idempotency-Key` on mutating requests: the first request with a key
+/// runs and its response is stored, a retry with the same key and body gets
+/// that response back, and the same key with another body is rejected. Keys
+/// are scoped to the caller, so this runs inside [`authorize`](crate::middleware::auth::authorize).
+pub async fn idempotency(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if request.method().is_safe() {
+        return Ok(next.run(request).await);
+    }
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| {
+            ApiError::invalid_request(format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LEN))
+        })?
+        .to_string();
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .map(|principal| principal.subject.to_string())
+        .ok_or_else(|| ApiError::invalid_request("Idempotency-Key requires an authenticated request"))?;
+
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_BODY_SIZE)
+        .await
+        .map_err(|_| ApiError::invalid_request("request body is too large"))?;
+    let method = parts.method.to_string();
+    let path = parts.uri.path_and_query().map_or(parts.uri.path(), |p| p.as_str()).to_string();
+
+    let service = IdempotencyService::new(&app_state.db)?;
+    let claimed_at = match service.claim(&principal, &key, &method, &path, &body).await? {
+        Claim::New { claimed_at } => claimed_at,
+        Claim::Replay(stored) => {
+            let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let mut response = (status, stored.body).into_response();
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return Ok(response);
+        }
+    };
+
+    // A send may outlast the lease across RPC retries and failovers, the claim is
+    // renewed meanwhile so a retry can't take it over and sign a second time
+    let handler = next.run(Request::from_parts(parts, Body::from(body)));
+    tokio::pin!(handler);
+    let response = tokio::select! {
+        response = &mut handler => response,
+        () = service.keep_alive(&principal, &key, claimed_at) => handler.await,
+    };
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.map_err(anyhow::Error::from)?;
+
+    // Every outcome is stored, errors included: a failed send may still have been
+    // broadcast, so a retry must not sign again under the same key
+    let stored = StoredResponse {
+        status_code: parts.status.as_u16(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+    let tx_hash = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value["data"]["transaction_hash"].as_str().map(str::to_string));
+    if let Err(e) = service.complete(&principal, &key, &stored, tx_hash.as_deref()).await {
+        // The key stays claimed, retries are rejected as in progress until its lease runs out
+        warn!(key, error = %e, "failed to store idempotent response");
+    }
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
@@ -0,0 +1,21 @@
+use crate::metrics::METRICS;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+/// Count and time every routed request, labelled with the route template
+/// (`/block/{id}`) rather than the raw path to keep label cardinality bounded
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    METRICS.observe_http(&method, &route, response.status().as_u16(), started.elapsed());
+    response
+}
@@ -0,0 +1,5 @@
+pub mod auth;
+pub mod metrics;
+pub mod request_id;
+pub mod rate_limit;
+pub mod idempotency;
@@ -0,0 +1,127 @@
+use crate::error::{ApiError, AppError, ErrorCode};
+use crate::model::api_key::Principal;
+use crate::model::app_model::AppState;
+use crate::model::rate_limit::{RateClass, RateLimitStatus};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const RATE_LIMIT_HEADERS: [&str; 4] = ["ratelimit-limit", "ratelimit-remaining", "ratelimit-reset", "ratelimit-policy"];
+
+/// Count the request against the `class` budget of its caller: the API key or
+/// session set by [`authorize`](crate::middleware::auth::authorize), or the client
+/// IP on public routes. Layered inside `authorize` so the principal is known.
+pub async fn rate_limit(
+    State((app_state, class)): State<(Arc<AppState>, RateClass)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = &app_state.env.rate_limit;
+    let principal = request.extensions().get::<Principal>();
+    let limit = principal
+        .and_then(|principal| principal.rate_limit.get(class))
+        .unwrap_or_else(|| config.limit(class));
+    if limit == 0 {
+        return next.run(request).await;
+    }
+
+    let client = match principal {
+        Some(principal) => principal.subject.to_string(),
+        None => ip_client(
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip()),
+        ),
+    };
+    let window = config.window;
+    let status = app_state
+        .mem
+        .rate_limits
+        .check(&client, class, limit, Duration::from_secs(window));
+    if !status.allowed {
+        let message = format!("rate limit of {} requests per {}s exceeded, retry in {}s", limit, window, status.reset);
+        return too_many_requests(&status, window, message);
+    }
+
+    let mut response = next.run(request).await;
+    insert_headers(response.headers_mut(), &status, window);
+    response
+}
+
+/// Rate limit key of a caller known by its IP only
+pub(crate) fn ip_client(ip: Option<IpAddr>) -> String {
+    ip.map_or_else(|| "ip:unknown".to_string(), |ip| format!("ip:{}", ip))
+}
+
+/// 429 for a spent budget, with `Retry-After` and the `RateLimit-*` headers
+pub(crate) fn too_many_requests(status: &RateLimitStatus, window: u64, message: String) -> Response {
+    let mut response = AppError::from(ApiError::new(ErrorCode::RateLimited, message)).into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(status.reset));
+    insert_headers(response.headers_mut(), status, window);
+    response
+}
+
+/// `RateLimit-*` headers of draft-ietf-httpapi-ratelimit-headers
+fn insert_headers(headers: &mut HeaderMap, status: &RateLimitStatus, window: u64) {
+    let [limit, remaining, reset, policy] = RATE_LIMIT_HEADERS.map(HeaderName::from_static);
+    headers.insert(limit, HeaderValue::from(status.limit));
+    headers.insert(remaining, HeaderValue::from(status.remaining));
+    headers.insert(reset, HeaderValue::from(status.reset));
+    if let Ok(value) = HeaderValue::from_str(&format!("{};w={}", status.limit, window)) {
+        headers.insert(policy, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::rate_limit::RateLimitStore;
+    use axum::body::to_bytes;
+    use axum::http::StatusCode;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn spent_budgets_get_429_with_retry_after_and_ratelimit_headers() {
+        let store = RateLimitStore::new();
+        let window = Duration::from_secs(60);
+        store.check("key:a", RateClass::Expensive, 1, window);
+        let status = store.check("key:a", RateClass::Expensive, 1, window);
+        assert!(!status.allowed);
+
+        let response = too_many_requests(&status, 60, "rate limit exceeded".to_string());
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let headers = response.headers();
+        let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+        let reset: u64 = header("ratelimit-reset").parse().unwrap();
+        assert!(reset > 0 && reset <= 60);
+        assert_eq!(header(RETRY_AFTER.as_str()), reset.to_string());
+        assert_eq!(header("ratelimit-limit"), "1");
+        assert_eq!(header("ratelimit-remaining"), "0");
+        assert_eq!(header("ratelimit-policy"), "1;w=60");
+
+        let body: Value = serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+        assert_eq!(body["code"], "rate_limited");
+    }
+
+    #[test]
+    fn allowed_requests_get_ratelimit_headers_without_retry_after() {
+        let status = RateLimitStore::new().check("ip:10.0.0.1", RateClass::Read, 10, Duration::from_secs(30));
+        let mut headers = HeaderMap::new();
+        insert_headers(&mut headers, &status, 30);
+        assert_eq!(headers["ratelimit-remaining"], "9");
+        assert_eq!(headers["ratelimit-policy"], "10;w=30");
+        assert!(!headers.contains_key(RETRY_AFTER));
+    }
+
+    #[test]
+    fn callers_without_credentials_are_keyed_by_ip() {
+        assert_eq!(ip_client(Some("10.0.0.1".parse().unwrap())), "ip:10.0.0.1");
+        assert_eq!(ip_client(None), "ip:unknown");
+    }
+}
@@ -0,0 +1,323 @@
+use ethers::abi::{decode, parse_abi, Abi, Event, Param, ParamType, Token};
+use ethers::types::{Address, Bytes, Log};
+use ethers::utils::hex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+const ERC20_ABI: &[&str] = &[
+    "function transfer(address to, uint256 amount) returns (bool)",
+    "function transferFrom(address from, address to, uint256 amount) returns (bool)",
+    "function approve(address spender, uint256 amount) returns (bool)",
+    "function balanceOf(address owner) view returns (uint256)",
+    "function allowance(address owner, address spender) view returns (uint256)",
+    "event Transfer(address indexed from, address indexed to, uint256 value)",
+    "event Approval(address indexed owner, address indexed spender, uint256 value)",
+];
+
+const ERC721_ABI: &[&str] = &[
+    "function safeTransferFrom(address from, address to, uint256 tokenId)",
+    "function safeTransferFrom(address from, address to, uint256 tokenId, bytes data)",
+    "function transferFrom(address from, address to, uint256 tokenId)",
+    "function approve(address to, uint256 tokenId)",
+    "function setApprovalForAll(address operator, bool approved)",
+    "event Transfer(address indexed from, address indexed to, uint256 indexed tokenId)",
+    "event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId)",
+    "event ApprovalForAll(address indexed owner, address indexed operator, bool approved)",
+];
+
+const ERC1155_ABI: &[&str] = &[
+    "function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data)",
+    "function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] amounts, bytes data)",
+    "function setApprovalForAll(address operator, bool approved)",
+    "event TransferSingle(address indexed operator, address indexed from, address indexed to, uint256 id, uint256 value)",
+    "event TransferBatch(address indexed operator, address indexed from, address indexed to, uint256[] ids, uint256[] values)",
+    "event ApprovalForAll(address indexed account, address indexed operator, bool approved)",
+    "event URI(string value, uint256 indexed id)",
+];
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DecodedParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+/// Calldata matched against a known function
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DecodedCall {
+    pub selector: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// `registered` for contract specific ABIs, otherwise the matching standard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub params: Vec<DecodedParam>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DecodedLog {
+    #[schema(value_type = String)]
+    pub address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_index: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub params: Vec<DecodedParam>,
+    /// Raw topics and data, kept only when the log couldn't be decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub raw: Option<Log>,
+}
+
+/// Contract ABIs registered by users plus the built-in token standards,
+/// used to decode calldata and logs
+pub struct AbiRegistry {
+    contracts: HashMap<Address, Abi>,
+    builtins: Vec<(&'static str, Abi)>,
+}
+
+impl AbiRegistry {
+    pub fn new() -> Self {
+        let builtins = [("erc20", ERC20_ABI), ("erc721", ERC721_ABI), ("erc1155", ERC1155_ABI)]
+            .into_iter()
+            .map(|(name, abi)| (name, parse_abi(abi).expect("built-in ABI must parse")))
+            .collect();
+        Self {
+            contracts: HashMap::new(),
+            builtins,
+        }
+    }
+
+    pub fn register(&mut self, address: Address, abi: Abi) {
+        self.contracts.insert(address, abi);
+    }
+
+    pub fn registered(&self) -> Vec<Address> {
+        self.contracts.keys().copied().collect()
+    }
+
+    /// Candidate ABIs for a contract, its registered ABI first
+    fn candidates(&self, address: Option<Address>) -> impl Iterator<Item = (&str, &Abi)> {
+        address
+            .and_then(|address| self.contracts.get(&address))
+            .map(|abi| ("registered", abi))
+            .into_iter()
+            .chain(self.builtins.iter().map(|(name, abi)| (*name, abi)))
+    }
+
+    pub fn decode_call(&self, to: Option<Address>, input: &Bytes) -> Option<DecodedCall> {
+        if input.len() < 4 {
+            return None;
+        }
+        let (selector, data) = input.split_at(4);
+
+        for (source, abi) in self.candidates(to) {
+            for function in abi.functions().filter(|f| f.short_signature() == selector) {
+                if let Ok(tokens) = function.decode_input(data) {
+                    return Some(DecodedCall {
+                        selector: hex::encode_prefixed(selector),
+                        signature: Some(function.signature()),
+                        source: Some(source.to_string()),
+                        params: decode_params(&function.inputs, tokens),
+                    });
+                }
+            }
+        }
+
+        Some(DecodedCall {
+            selector: hex::encode_prefixed(selector),
+            signature: None,
+            source: None,
+            params: Vec::new(),
+        })
+    }
+
+    pub fn decode_log(&self, log: &Log) -> DecodedLog {
+        let log_index = log.log_index.map(|index| index.as_u64());
+        if let Some(topic0) = log.topics.first() {
+            for (source, abi) in self.candidates(Some(log.address)) {
+                for event in abi.events().filter(|e| e.signature() == *topic0) {
+                    // same topic0 with a different indexed layout fails here, e.g. ERC20 vs ERC721 Transfer
+                    if let Some(params) = decode_event_params(event, log) {
+                        return DecodedLog {
+                            address: log.address,
+                            log_index,
+                            event: Some(event_signature(event)),
+                            source: Some(source.to_string()),
+                            params,
+                            raw: None,
+                        };
+                    }
+                }
+            }
+        }
+
+        DecodedLog {
+            address: log.address,
+            log_index,
+            event: None,
+            source: None,
+            params: Vec::new(),
+            raw: Some(log.clone()),
+        }
+    }
+}
+
+impl Default for AbiRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn decode_params(inputs: &[Param], tokens: Vec<Token>) -> Vec<DecodedParam> {
+    inputs
+        .iter()
+        .zip(tokens)
+        .enumerate()
+        .map(|(index, (input, token))| DecodedParam {
+            name: if input.name.is_empty() { format!("arg{}", index) } else { input.name.clone() },
+            kind: input.kind.to_string(),
+            value: token_to_json(&token),
+        })
+        .collect()
+}
+
+/// Decode the parameters of a log by position. `Event::parse_log` keys values by
+/// name, so unnamed parameters would overwrite each other.
+fn decode_event_params(event: &Event, log: &Log) -> Option<Vec<DecodedParam>> {
+    let topics = log.topics.get(1..)?;
+    if topics.len() != event.inputs.iter().filter(|input| input.indexed).count() {
+        return None;
+    }
+    let data_types: Vec<ParamType> = event
+        .inputs
+        .iter()
+        .filter(|input| !input.indexed)
+        .map(|input| input.kind.clone())
+        .collect();
+    let mut data = decode(&data_types, &log.data).ok()?.into_iter();
+    let mut topics = topics.iter();
+
+    event
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let value = if input.indexed {
+                // dynamic values are indexed by their hash
+                let kind = match input.kind {
+                    ParamType::String | ParamType::Bytes | ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_) => {
+                        ParamType::FixedBytes(32)
+                    }
+                    ref kind => kind.clone(),
+                };
+                decode(&[kind], topics.next()?.as_bytes()).ok()?.pop()?
+            } else {
+                data.next()?
+            };
+            Some(DecodedParam {
+                name: if input.name.is_empty() { format!("arg{}", index) } else { input.name.clone() },
+                kind: input.kind.to_string(),
+                value: token_to_json(&value),
+            })
+        })
+        .collect()
+}
+
+fn event_signature(event: &Event) -> String {
+    let types: Vec<String> = event.inputs.iter().map(|input| input.kind.to_string()).collect();
+    format!("{}({})", event.name, types.join(","))
+}
+
+/// Integers are rendered as decimal strings so large values survive JSON clients
+pub(crate) fn token_to_json(token: &Token) -> Value {
+    match token {
+        Token::Address(address) => json!(format!("{:?}", address)),
+        Token::Uint(value) => json!(value.to_string()),
+        Token::Int(value) => json!(ethers::types::I256::from_raw(*value).to_string()),
+        Token::Bool(value) => json!(value),
+        Token::String(value) => json!(value),
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => json!(hex::encode_prefixed(bytes)),
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.iter().map(token_to_json).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{encode, Token};
+    use ethers::types::{H256, U256};
+    use ethers::utils::keccak256;
+
+    fn log(address: Address, signature: &str, topics: &[H256], data: &[Token]) -> Log {
+        Log {
+            address,
+            topics: std::iter::once(H256::from(keccak256(signature))).chain(topics.iter().copied()).collect(),
+            data: encode(data).into(),
+            ..Default::default()
+        }
+    }
+
+    fn params(decoded: &DecodedLog) -> Vec<(&str, &str, Value)> {
+        decoded
+            .params
+            .iter()
+            .map(|param| (param.name.as_str(), param.kind.as_str(), param.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn unnamed_event_params_are_decoded_by_position() {
+        let contract = Address::repeat_byte(9);
+        let mut registry = AbiRegistry::new();
+        registry.register(contract, parse_abi(&["event Moved(address indexed, uint256, bool, uint8 indexed)"]).unwrap());
+
+        let owner = Address::repeat_byte(1);
+        let decoded = registry.decode_log(&log(
+            contract,
+            "Moved(address,uint256,bool,uint8)",
+            &[H256::from(owner), H256::from_low_u64_be(7)],
+            &[Token::Uint(U256::from(1_000)), Token::Bool(true)],
+        ));
+
+        assert_eq!(decoded.source.as_deref(), Some("registered"));
+        assert_eq!(
+            params(&decoded),
+            [
+                ("arg0", "address", json!(format!("{:?}", owner))),
+                ("arg1", "uint256", json!("1000")),
+                ("arg2", "bool", json!(true)),
+                ("arg3", "uint8", json!("7")),
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_logs_are_told_apart_by_their_indexed_layout() {
+        let registry = AbiRegistry::new();
+        let (from, to) = (H256::from(Address::repeat_byte(1)), H256::from(Address::repeat_byte(2)));
+        let signature = "Transfer(address,address,uint256)";
+
+        let erc20 = registry.decode_log(&log(Address::zero(), signature, &[from, to], &[Token::Uint(5.into())]));
+        assert_eq!(erc20.source.as_deref(), Some("erc20"));
+        assert_eq!(params(&erc20)[2], ("value", "uint256", json!("5")));
+
+        let erc721 = registry.decode_log(&log(Address::zero(), signature, &[from, to, H256::from_low_u64_be(42)], &[]));
+        assert_eq!(erc721.source.as_deref(), Some("erc721"));
+        assert_eq!(params(&erc721)[2], ("tokenId", "uint256", json!("42")));
+    }
+
+    #[test]
+    fn undecodable_logs_keep_their_raw_form() {
+        let decoded = AbiRegistry::new().decode_log(&log(Address::zero(), "Unknown()", &[], &[]));
+        assert!(decoded.event.is_none());
+        assert!(decoded.raw.is_some());
+    }
+}
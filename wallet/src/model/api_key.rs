@@ -0,0 +1,143 @@
+use crate::error::ApiError;
+use crate::model::rate_limit::RateLimitOverride;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Permission granted to an API key or session, `admin` implies every other scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Query balances, blocks, transactions, deposits and history
+    Read,
+    /// Import keys into the keyring
+    Sign,
+    /// Send ETH and tokens from keyring addresses
+    Send,
+    /// Watch addresses and listen to token contracts
+    Watch,
+    /// Manage tenants, API keys, backfills and ABIs, and export keys. Only granted to platform credentials.
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Sign => "sign",
+            Scope::Send => "send",
+            Scope::Watch => "watch",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = ApiError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "read" => Ok(Scope::Read),
+            "sign" => Ok(Scope::Sign),
+            "send" => Ok(Scope::Send),
+            "watch" => Ok(Scope::Watch),
+            "admin" => Ok(Scope::Admin),
+            other => Err(ApiError::invalid_request(format!("unknown scope {}", other))),
+        }
+    }
+}
+
+/// Metadata of an API key, the key itself is only stored as a SHA-256 hash
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub prefix: String,
+    /// Tenant the key acts for, `None` for platform keys
+    pub tenant_id: Option<Uuid>,
+    pub scopes: Vec<Scope>,
+    pub rate_limit: RateLimitOverride,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub revoked_at: Option<u64>,
+    /// Key replacing this one after a rotation
+    pub rotated_to: Option<Uuid>,
+    pub last_used_at: Option<u64>,
+    pub last_used_ip: Option<String>,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// Newly created or rotated key, the only time the key is returned
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+/// SIWE session of an operator, the token is only stored as a SHA-256 hash
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuthSession {
+    pub id: Uuid,
+    /// Checksummed address of the operator
+    pub address: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    /// Set by a logout or by an admin
+    pub revoked_at: Option<u64>,
+    pub last_used_at: Option<u64>,
+    pub last_used_ip: Option<String>,
+}
+
+impl AuthSession {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// Credential a request was authenticated with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    ApiKey(Uuid),
+    /// Session of an operator signed in with Ethereum
+    Session { id: Uuid, address: Address },
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subject::ApiKey(id) => write!(f, "key:{}", id),
+            Subject::Session { address, .. } => write!(f, "siwe:{:?}", address),
+        }
+    }
+}
+
+/// Caller authenticated by the auth middleware, available to handlers as an extension
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: Subject,
+    /// `None` for platform credentials, which see the assets of every tenant
+    pub tenant: Option<Uuid>,
+    pub scopes: Vec<Scope>,
+    pub rate_limit: RateLimitOverride,
+}
+
+impl Principal {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.iter().any(|granted| *granted == scope || *granted == Scope::Admin)
+    }
+
+    /// Whether the caller may use an asset owned by `owner`
+    pub fn can_access(&self, owner: Option<Uuid>) -> bool {
+        self.tenant.is_none() || self.tenant == owner
+    }
+}
@@ -1,20 +1,41 @@
+use crate::chain::eth::{RpcPool, WsConnection};
 use crate::config::server_config::Config;
+use crate::model::abi_registry::AbiRegistry;
+use crate::model::block_tracker::{BlockTracker, Reorg};
+use crate::model::deposit::DepositStore;
+use crate::model::event_store::EventStore;
+use crate::model::heartbeat::Heartbeats;
+use crate::model::job::JobRegistry;
 use crate::model::keyring::Keyring;
-use ethers::providers::{Http, Provider, Ws};
-use ethers::types::Address;
+use crate::model::keystore::KeyStore;
+use crate::model::listener::ListenerRegistry;
+use crate::model::rate_limit::RateLimitStore;
+use crate::model::watchlist::WatchList;
+use ethers::providers::Provider;
 use sqlx::{MySql, Pool};
-use std::collections::HashSet;
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
 
 pub struct AppState {
     pub db: Pool<MySql>,
     pub env: Config,
-    pub eth: Provider<Http>,
-    pub eth_ws: Option<Provider<Ws>>,
+    pub eth: Provider<RpcPool>,
+    pub eth_ws: Arc<WsConnection>,
+    /// Encrypted copy of the keyring, `None` when keys only live in memory
+    pub keystore: Option<KeyStore>,
     pub mem: MemoryStorage,
 }
 
 pub struct MemoryStorage {
-    pub keyring: RwLock<Keyring>,
-    pub listening: RwLock<HashSet<Address>>,
+    pub keyring: Arc<RwLock<Keyring>>,
+    pub listeners: Arc<RwLock<ListenerRegistry>>,
+    pub watched: Arc<RwLock<WatchList>>,
+    pub deposits: Arc<RwLock<DepositStore>>,
+    pub events: Arc<RwLock<EventStore>>,
+    pub jobs: Arc<RwLock<JobRegistry>>,
+    pub blocks: Arc<RwLock<BlockTracker>>,
+    pub reorgs: broadcast::Sender<Reorg>,
+    pub abis: RwLock<AbiRegistry>,
+    pub rate_limits: RateLimitStore,
+    pub heartbeats: Arc<Heartbeats>,
 }
@@ -0,0 +1,99 @@
+use ethers::types::H256;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Number of detected reorgs kept for inspection
+const MAX_RECENT_REORGS: usize = 32;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TrackedBlock {
+    pub number: u64,
+    pub hash: H256,
+    pub parent_hash: H256,
+}
+
+/// Chain reorganization, `from_block..=to_block` were replaced by another branch
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Reorg {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Hashes of the orphaned blocks still in the tracked window
+    #[schema(value_type = Vec<String>)]
+    pub orphaned: Vec<H256>,
+    /// Deeper than the tracked window, rolled back as far as stored events and
+    /// deposits of orphaned blocks reach
+    pub deep: bool,
+    pub detected_at: u64,
+}
+
+impl Reorg {
+    pub fn depth(&self) -> u64 {
+        self.to_block - self.from_block + 1
+    }
+}
+
+/// Sliding window of the last `depth` canonical block hashes
+pub struct BlockTracker {
+    depth: usize,
+    blocks: BTreeMap<u64, TrackedBlock>,
+    reorgs: VecDeque<Reorg>,
+}
+
+impl BlockTracker {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth: depth.max(1),
+            blocks: BTreeMap::new(),
+            reorgs: VecDeque::new(),
+        }
+    }
+
+    pub fn head(&self) -> Option<TrackedBlock> {
+        self.blocks.values().next_back().copied()
+    }
+
+    pub fn tail(&self) -> Option<TrackedBlock> {
+        self.blocks.values().next().copied()
+    }
+
+    pub fn hash_at(&self, number: u64) -> Option<H256> {
+        self.blocks.get(&number).map(|block| block.hash)
+    }
+
+    /// Append a new canonical block, dropping the oldest ones beyond the window
+    pub fn push(&mut self, block: TrackedBlock) {
+        self.blocks.insert(block.number, block);
+        while self.blocks.len() > self.depth {
+            self.blocks.pop_first();
+        }
+    }
+
+    /// Drop every block above `fork_point`, the last block still canonical, and
+    /// record the orphaned range. `deep` marks a fork point below the window.
+    pub fn rewind(&mut self, fork_point: u64, deep: bool) -> Option<Reorg> {
+        let orphaned = self.blocks.split_off(&(fork_point + 1));
+        let to_block = *orphaned.keys().next_back()?;
+
+        let reorg = Reorg {
+            from_block: fork_point + 1,
+            to_block,
+            orphaned: orphaned.values().map(|block| block.hash).collect(),
+            deep,
+            detected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        self.reorgs.push_back(reorg.clone());
+        if self.reorgs.len() > MAX_RECENT_REORGS {
+            self.reorgs.pop_front();
+        }
+        Some(reorg)
+    }
+
+    pub fn recent_reorgs(&self) -> Vec<Reorg> {
+        self.reorgs.iter().cloned().collect()
+    }
+}
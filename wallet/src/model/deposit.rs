@@ -0,0 +1,239 @@
+use crate::types::Amount;
+use ethers::types::{Address, H256};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DepositStatus {
+    Pending,
+    Credited,
+}
+
+/// Incoming native or ERC20 transfer to a managed or watched address
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Deposit {
+    #[schema(value_type = String)]
+    pub address: Address,
+    #[schema(value_type = String)]
+    pub from: Address,
+    /// Token contract, `None` for native ETH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub token: Option<Address>,
+    pub amount: Amount,
+    pub block_number: u64,
+    #[schema(value_type = String)]
+    pub block_hash: H256,
+    #[schema(value_type = String)]
+    pub tx_hash: H256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_index: Option<u64>,
+    /// Position among the internal transfers of the transaction, for ETH sent by contracts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_index: Option<u64>,
+    pub confirmations: u64,
+    pub status: DepositStatus,
+}
+
+/// Position of a deposit: (block number, tx hash, log index, trace index)
+type DepositKey = (u64, H256, Option<u64>, Option<u64>);
+
+/// Deposits ordered by position plus the scanner cursor. Confirmation counts are
+/// derived from the head of the last confirmation pass when deposits are read.
+pub struct DepositStore {
+    deposits: BTreeMap<DepositKey, Deposit>,
+    /// Deposits not credited yet, ordered by block so a pass only visits the ones
+    /// that can have reached the required confirmations
+    pending: BTreeSet<DepositKey>,
+    head: Option<u64>,
+    last_scanned: Option<u64>,
+}
+
+impl DepositStore {
+    pub fn new() -> Self {
+        Self {
+            deposits: BTreeMap::new(),
+            pending: BTreeSet::new(),
+            head: None,
+            last_scanned: None,
+        }
+    }
+
+    /// Insert a deposit, returns false if it was already recorded
+    pub fn insert(&mut self, deposit: Deposit) -> bool {
+        let key = (deposit.block_number, deposit.tx_hash, deposit.log_index, deposit.trace_index);
+        if self.deposits.contains_key(&key) {
+            return false;
+        }
+        if deposit.status == DepositStatus::Pending {
+            self.pending.insert(key);
+        }
+        self.deposits.insert(key, deposit);
+        true
+    }
+
+    /// Credit pending deposits that reached `required` confirmations at `head`,
+    /// returns the newly credited ones
+    pub fn confirm(&mut self, head: u64, required: u64) -> Vec<Deposit> {
+        self.head = Some(head);
+        // a deposit in block b has head - b + 1 confirmations
+        let Some(last_block) = head.saturating_add(1).checked_sub(required.max(1)) else {
+            return Vec::new();
+        };
+        let still_pending = self.pending.split_off(&(last_block.saturating_add(1), H256::zero(), None, None));
+        let ready = std::mem::replace(&mut self.pending, still_pending);
+
+        let mut credited = Vec::new();
+        for key in ready {
+            if let Some(deposit) = self.deposits.get_mut(&key) {
+                deposit.status = DepositStatus::Credited;
+                credited.push(with_confirmations(deposit.clone(), self.head));
+            }
+        }
+        credited
+    }
+
+    /// Remove deposits at or above `from_block` and rewind the scanner so the
+    /// range is scanned again on the new branch
+    pub fn rollback(&mut self, from_block: u64) -> usize {
+        let from = (from_block, H256::zero(), None, None);
+        self.pending.split_off(&from);
+        let removed = self.deposits.split_off(&from).len();
+        let rewound = from_block.saturating_sub(1);
+        if self.last_scanned.is_some_and(|last| last > rewound) {
+            self.last_scanned = Some(rewound);
+        }
+        removed
+    }
+
+    /// Hashes of the blocks holding deposits below `number`, newest first
+    pub fn blocks_below(&self, number: u64) -> Vec<(u64, H256)> {
+        let mut blocks: Vec<(u64, H256)> = self
+            .deposits
+            .range(..(number, H256::zero(), None, None))
+            .rev()
+            .map(|(_, deposit)| (deposit.block_number, deposit.block_hash))
+            .collect();
+        blocks.dedup();
+        blocks
+    }
+
+    pub fn by_address(&self, address: Address) -> Vec<Deposit> {
+        self.deposits
+            .values()
+            .filter(|deposit| deposit.address == address)
+            .map(|deposit| with_confirmations(deposit.clone(), self.head))
+            .collect()
+    }
+
+    pub fn all(&self) -> Vec<Deposit> {
+        self.deposits
+            .values()
+            .map(|deposit| with_confirmations(deposit.clone(), self.head))
+            .collect()
+    }
+
+    pub fn last_scanned(&self) -> Option<u64> {
+        self.last_scanned
+    }
+
+    pub fn set_last_scanned(&mut self, block_number: u64) {
+        self.last_scanned = Some(block_number);
+    }
+}
+
+fn with_confirmations(mut deposit: Deposit, head: Option<u64>) -> Deposit {
+    deposit.confirmations = head.map_or(0, |head| head.saturating_sub(deposit.block_number) + 1);
+    deposit
+}
+
+impl Default for DepositStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Asset;
+    use ethers::types::U256;
+
+    fn deposit(block_number: u64, tx: u8) -> Deposit {
+        Deposit {
+            address: Address::repeat_byte(1),
+            from: Address::repeat_byte(2),
+            token: None,
+            amount: Amount::new(U256::one(), Asset::ether()),
+            block_number,
+            block_hash: H256::repeat_byte(block_number as u8),
+            tx_hash: H256::repeat_byte(tx),
+            log_index: None,
+            trace_index: None,
+            confirmations: 0,
+            status: DepositStatus::Pending,
+        }
+    }
+
+    fn statuses(store: &DepositStore) -> Vec<(u64, DepositStatus, u64)> {
+        store
+            .all()
+            .into_iter()
+            .map(|deposit| (deposit.block_number, deposit.status, deposit.confirmations))
+            .collect()
+    }
+
+    #[test]
+    fn deposits_are_credited_at_the_required_confirmations() {
+        let mut store = DepositStore::new();
+        store.insert(deposit(100, 1));
+        store.insert(deposit(101, 2));
+
+        assert!(store.confirm(101, 3).is_empty());
+        assert_eq!(statuses(&store), [(100, DepositStatus::Pending, 2), (101, DepositStatus::Pending, 1)]);
+
+        let credited = store.confirm(102, 3);
+        assert_eq!(credited.iter().map(|d| (d.block_number, d.confirmations)).collect::<Vec<_>>(), [(100, 3)]);
+        assert_eq!(statuses(&store), [(100, DepositStatus::Credited, 3), (101, DepositStatus::Pending, 2)]);
+
+        // credited once only, confirmations keep counting
+        assert_eq!(store.confirm(110, 3).len(), 1);
+        assert!(store.confirm(111, 3).is_empty());
+        assert_eq!(statuses(&store), [(100, DepositStatus::Credited, 12), (101, DepositStatus::Credited, 11)]);
+    }
+
+    #[test]
+    fn zero_required_confirmations_credits_included_deposits() {
+        let mut store = DepositStore::new();
+        store.insert(deposit(5, 1));
+        assert_eq!(store.confirm(5, 0).len(), 1);
+    }
+
+    #[test]
+    fn deposits_above_the_head_are_not_credited() {
+        let mut store = DepositStore::new();
+        store.insert(deposit(3, 1));
+        assert!(store.confirm(1, 2).is_empty());
+        assert!(store.confirm(0, 1).is_empty());
+    }
+
+    #[test]
+    fn rollback_drops_pending_and_credited_deposits_of_orphaned_blocks() {
+        let mut store = DepositStore::new();
+        store.insert(deposit(10, 1));
+        store.insert(deposit(11, 2));
+        store.insert(deposit(12, 3));
+        store.set_last_scanned(12);
+        store.confirm(11, 2);
+
+        assert_eq!(store.rollback(11), 2);
+        assert_eq!(store.last_scanned(), Some(10));
+        assert_eq!(statuses(&store), [(10, DepositStatus::Credited, 2)]);
+
+        // a deposit mined again on the new branch is credited normally
+        store.insert(deposit(11, 2));
+        assert_eq!(store.confirm(12, 2).len(), 1);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::types::Amount;
+use ethers::types::{Address, H256};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Decoded ERC20 `Transfer` log
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferEvent {
+    pub contract: Address,
+    pub from: Address,
+    pub to: Address,
+    pub value: Amount,
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub tx_hash: H256,
+    pub log_index: u64,
+}
+
+/// In-memory store of indexed events, ordered by (block number, log index)
+pub struct EventStore {
+    transfers: BTreeMap<(u64, u64), TransferEvent>,
+    /// Contracts whose events are indexed, by listeners or backfills, even
+    /// when none was found yet. Their logs are queried again after a reorg.
+    contracts: BTreeSet<Address>,
+}
+
+impl EventStore {
+    pub fn new() -> Self {
+        Self {
+            transfers: BTreeMap::new(),
+            contracts: BTreeSet::new(),
+        }
+    }
+
+    pub fn track(&mut self, contract: Address) {
+        self.contracts.insert(contract);
+    }
+
+    pub fn contracts(&self) -> Vec<Address> {
+        self.contracts.iter().copied().collect()
+    }
+
+    /// Insert a transfer, returns false if the same log was already stored
+    pub fn insert_transfer(&mut self, event: TransferEvent) -> bool {
+        self.contracts.insert(event.contract);
+        self.transfers
+            .insert((event.block_number, event.log_index), event)
+            .is_none()
+    }
+
+    pub fn transfers_by_contract(&self, contract: Address, from_block: u64, to_block: u64) -> Vec<TransferEvent> {
+        self.transfers
+            .range((from_block, 0)..=(to_block, u64::MAX))
+            .map(|(_, event)| event)
+            .filter(|event| event.contract == contract)
+            .cloned()
+            .collect()
+    }
+
+    /// Remove every transfer at or above `from_block`, returns how many were dropped
+    pub fn rollback(&mut self, from_block: u64) -> usize {
+        self.transfers.split_off(&(from_block, 0)).len()
+    }
+
+    /// Hashes of the blocks holding events below `number`, newest first
+    pub fn blocks_below(&self, number: u64) -> Vec<(u64, H256)> {
+        let mut blocks: Vec<(u64, H256)> = self
+            .transfers
+            .range(..(number, 0))
+            .rev()
+            .map(|(_, event)| (event.block_number, event.block_hash))
+            .collect();
+        blocks.dedup();
+        blocks
+    }
+
+    pub fn len(&self) -> usize {
+        self.transfers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+}
+
+impl Default for EventStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
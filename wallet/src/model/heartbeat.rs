@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Poll intervals a task may go without a successful tick before it is stale
+const STALE_AFTER_TICKS: u32 = 3;
+/// Slack on top of the intervals for ticks that legitimately run long, e.g. catching up
+const STALE_GRACE: Duration = Duration::from_secs(30);
+
+struct Beat {
+    interval: Duration,
+    registered: Instant,
+    last_tick: Option<Instant>,
+    last_success: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Liveness of one background task as seen by the readiness probe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskHealth {
+    pub name: &'static str,
+    /// No successful tick within the allowed number of intervals
+    pub stale: bool,
+    /// Seconds since the last successful tick, `None` if it never succeeded
+    pub since_success: Option<u64>,
+    /// Error of the last tick if it failed
+    pub last_error: Option<String>,
+}
+
+/// Last ticks of the block monitor, deposit scanner, history indexer and tx
+/// tracker, so readiness can tell a task that died or keeps failing from one
+/// that is merely idle
+pub struct Heartbeats {
+    tasks: Mutex<BTreeMap<&'static str, Beat>>,
+}
+
+impl Heartbeats {
+    pub fn new() -> Self {
+        Self {
+            tasks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Start watching a task ticking every `interval`
+    pub fn register(&self, name: &'static str, interval: Duration) {
+        self.tasks.lock().unwrap().insert(
+            name,
+            Beat {
+                interval,
+                registered: Instant::now(),
+                last_tick: None,
+                last_success: None,
+                last_error: None,
+            },
+        );
+    }
+
+    /// Record the outcome of a tick of `name`
+    pub fn record<T>(&self, name: &'static str, result: &anyhow::Result<T>) {
+        let now = Instant::now();
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some(beat) = tasks.get_mut(name) else {
+            return;
+        };
+        beat.last_tick = Some(now);
+        match result {
+            Ok(_) => {
+                beat.last_success = Some(now);
+                beat.last_error = None;
+            }
+            Err(e) => beat.last_error = Some(e.to_string()),
+        }
+    }
+
+    /// Health of every registered task as of `now`
+    pub fn tasks(&self, now: Instant) -> Vec<TaskHealth> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, beat)| {
+                let reference = beat.last_success.unwrap_or(beat.registered);
+                let stale_after = beat.interval.saturating_mul(STALE_AFTER_TICKS).saturating_add(STALE_GRACE);
+                TaskHealth {
+                    name,
+                    stale: now.saturating_duration_since(reference) > stale_after,
+                    since_success: beat.last_success.map(|at| now.saturating_duration_since(at).as_secs()),
+                    last_error: beat.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Heartbeats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn task(heartbeats: &Heartbeats, now: Instant) -> TaskHealth {
+        heartbeats.tasks(now).remove(0)
+    }
+
+    #[test]
+    fn tasks_go_stale_without_successful_ticks() {
+        let heartbeats = Heartbeats::new();
+        heartbeats.register("block_monitor", Duration::from_secs(2));
+        let now = Instant::now();
+        assert!(!task(&heartbeats, now).stale);
+        assert!(task(&heartbeats, now + Duration::from_secs(37)).stale);
+
+        heartbeats.record("block_monitor", &Ok(()));
+        let now = Instant::now();
+        let healthy = task(&heartbeats, now + Duration::from_secs(5));
+        assert!(!healthy.stale);
+        assert_eq!(healthy.since_success, Some(5));
+
+        // failing ticks keep the task alive but don't count as progress
+        heartbeats.record("block_monitor", &Err::<(), _>(anyhow!("connection refused")));
+        let failing = task(&heartbeats, now + Duration::from_secs(40));
+        assert!(failing.stale);
+        assert_eq!(failing.last_error.as_deref(), Some("connection refused"));
+
+        heartbeats.record("block_monitor", &Ok(()));
+        assert_eq!(task(&heartbeats, Instant::now()).last_error, None);
+    }
+
+    #[test]
+    fn unregistered_tasks_are_ignored() {
+        let heartbeats = Heartbeats::new();
+        heartbeats.record("tx_tracker", &Ok(()));
+        assert!(heartbeats.tasks(Instant::now()).is_empty());
+    }
+}
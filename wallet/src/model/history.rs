@@ -0,0 +1,89 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+    /// Sender and recipient are the same address
+    SelfTransfer,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+            Direction::SelfTransfer => "self",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryKind {
+    /// Plain ETH transfer without calldata
+    Native,
+    /// Contract call, possibly carrying ETH value
+    Call,
+    /// ERC20 `Transfer` log
+    Token,
+    /// ETH moved by a nested call, found by tracing
+    Internal,
+}
+
+impl HistoryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryKind::Native => "native",
+            HistoryKind::Call => "call",
+            HistoryKind::Token => "token",
+            HistoryKind::Internal => "internal",
+        }
+    }
+}
+
+/// Asset column value for native ETH entries, tokens store their contract address
+pub const NATIVE_ASSET: &str = "ETH";
+
+/// One row of the `tx_history` table, seen from the point of view of `address`
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub address: String,
+    pub counterparty: Option<String>,
+    pub direction: String,
+    pub kind: String,
+    pub asset: String,
+    pub amount: String,
+    pub amount_formatted: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: u64,
+    pub block_hash: String,
+    pub block_timestamp: u64,
+}
+
+/// Entry to be inserted, the id is assigned by the database
+#[derive(Debug, Clone)]
+pub struct NewHistoryEntry {
+    /// Tenant the copy belongs to, `None` for the platform's copy
+    pub tenant_id: Option<Uuid>,
+    pub address: String,
+    pub counterparty: Option<String>,
+    pub direction: Direction,
+    pub kind: HistoryKind,
+    pub asset: String,
+    pub amount: String,
+    pub amount_formatted: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: u64,
+    pub block_hash: String,
+    pub block_timestamp: u64,
+}
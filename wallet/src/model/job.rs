@@ -0,0 +1,108 @@
+use crate::error::ApiError;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of a long running background job
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub status: JobStatus,
+    pub total: u64,
+    pub processed: u64,
+    pub found: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+pub struct JobRegistry {
+    jobs: HashMap<Uuid, Job>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self { jobs: HashMap::new() }
+    }
+
+    /// Register a new pending job with `total` units of work
+    pub fn create(&mut self, kind: &str, total: u64) -> Uuid {
+        let id = Uuid::new_v4();
+        let now = unix_now();
+        self.jobs.insert(id, Job {
+            id,
+            kind: kind.to_string(),
+            status: JobStatus::Pending,
+            total,
+            processed: 0,
+            found: 0,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        });
+        id
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<Job> {
+        self.jobs
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| ApiError::not_found(format!("job {} not found", id)).into())
+    }
+
+    pub fn start(&mut self, id: Uuid) {
+        self.update(id, |job| job.status = JobStatus::Running);
+    }
+
+    pub fn progress(&mut self, id: Uuid, processed: u64, found: u64) {
+        self.update(id, |job| {
+            job.processed += processed;
+            job.found += found;
+        });
+    }
+
+    pub fn complete(&mut self, id: Uuid) {
+        self.update(id, |job| job.status = JobStatus::Completed);
+    }
+
+    pub fn fail(&mut self, id: Uuid, error: String) {
+        self.update(id, |job| {
+            job.status = JobStatus::Failed;
+            job.error = Some(error);
+        });
+    }
+
+    fn update(&mut self, id: Uuid, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            f(job);
+            job.updated_at = unix_now();
+        }
+    }
+}
+
+impl Default for JobRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,10 +1,19 @@
-use anyhow::{anyhow, Result};
+use crate::error::{ApiError, ErrorCode};
+use crate::model::api_key::Principal;
+use anyhow::Result;
 use ethers::signers::{LocalWallet, Signer};
 use ethers::types::Address;
 use std::collections::HashMap;
+use uuid::Uuid;
+
+struct KeyEntry {
+    wallet: LocalWallet,
+    /// `None` for keys imported with platform credentials
+    tenant: Option<Uuid>,
+}
 
 pub struct Keyring {
-    data_mapping: HashMap<Address, LocalWallet>,
+    data_mapping: HashMap<Address, KeyEntry>,
 }
 
 impl Keyring {
@@ -12,17 +21,58 @@ impl Keyring {
         Self { data_mapping: HashMap::new() }
     }
 
-    pub fn add_from_private_key(&mut self, private_key: &str) -> Result<Address> {
-        let wallet: LocalWallet = private_key.parse()?;
+    /// Import a key for `tenant`. Importing it again is a no-op for its owner,
+    /// a key can't be moved to another tenant.
+    pub fn add_from_private_key(&mut self, private_key: &str, tenant: Option<Uuid>) -> Result<Address> {
+        let wallet: LocalWallet = private_key
+            .parse()
+            .map_err(|_| ApiError::new(ErrorCode::InvalidPrivateKey, "invalid private key"))?;
+        self.add_wallet(wallet, tenant)
+    }
+
+    /// Same as [`Keyring::add_from_private_key`] for an already decoded key
+    pub fn add_wallet(&mut self, wallet: LocalWallet, tenant: Option<Uuid>) -> Result<Address> {
         let addr = wallet.address();
-        self.data_mapping.insert(addr, wallet);
+        if let Some(entry) = self.data_mapping.get(&addr)
+            && entry.tenant != tenant
+        {
+            return Err(ApiError::new(ErrorCode::Forbidden, format!("key for {:?} belongs to another tenant", addr)).into());
+        }
+        self.data_mapping.insert(addr, KeyEntry { wallet, tenant });
         Ok(addr)
     }
 
-    pub fn get_by_address(&self, address: Address) -> Result<LocalWallet> {
-        let wallet = self.data_mapping
+    /// Signer for `address`, only if the caller's tenant owns the key. Keys of
+    /// other tenants are reported as missing so they can't be probed.
+    pub fn get_by_address(&self, address: Address, principal: &Principal) -> Result<LocalWallet> {
+        let entry = self
+            .data_mapping
             .get(&address)
-            .ok_or_else(|| anyhow!("key not found"))?;
-        Ok(wallet.clone())
+            .filter(|entry| principal.can_access(entry.tenant))
+            .ok_or_else(|| ApiError::new(ErrorCode::KeyNotFound, format!("no key for address {:?}", address)))?;
+        Ok(entry.wallet.clone())
+    }
+
+    /// Owner of the key for `address`, `None` if there is no such key
+    pub fn owner(&self, address: Address) -> Option<Option<Uuid>> {
+        self.data_mapping.get(&address).map(|entry| entry.tenant)
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.data_mapping.keys().copied().collect()
+    }
+
+    pub fn addresses_of(&self, tenant: Uuid) -> Vec<Address> {
+        self.data_mapping
+            .iter()
+            .filter(|(_, entry)| entry.tenant == Some(tenant))
+            .map(|(address, _)| *address)
+            .collect()
+    }
+}
+
+impl Default for Keyring {
+    fn default() -> Self {
+        Self::new()
     }
 }
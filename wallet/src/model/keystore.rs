@@ -0,0 +1,383 @@
+use crate::error::{ApiError, ErrorCode};
+use aes::cipher::{KeyIvInit, StreamCipher};
+use anyhow::{anyhow, Context, Result};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::Address;
+use ethers::utils::{hex, keccak256, to_checksum};
+use hmac::Hmac;
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Largest scrypt cost accepted from imported keystores (geth's "standard" setting),
+/// above it a single import could take minutes and gigabytes of memory
+const MAX_SCRYPT_N: u64 = 1 << 18;
+const MAX_SCRYPT_R: u64 = 8;
+const MAX_SCRYPT_P: u64 = 4;
+const MAX_PBKDF2_ROUNDS: u64 = 1 << 20;
+/// scrypt cost of the keystores written here, geth's "light" setting
+const SCRYPT_LOG_N: u8 = 13;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// Keyring persisted as Web3 Secret Storage (V3) files encrypted with one
+/// passphrase. Platform keys are stored as `<dir>/<address>.json`, keys of a
+/// tenant as `<dir>/<tenant id>/<address>.json`.
+///
+/// Encryption and decryption run scrypt, callers on the runtime use `spawn_blocking`.
+#[derive(Clone)]
+pub struct KeyStore {
+    dir: PathBuf,
+    passphrase: String,
+}
+
+impl KeyStore {
+    pub fn open(dir: impl Into<PathBuf>, passphrase: impl Into<String>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("keystore directory {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            passphrase: passphrase.into(),
+        })
+    }
+
+    /// Decrypt every stored key with its owner. Fails on the first file that
+    /// doesn't decrypt, usually a wrong passphrase.
+    pub fn load(&self) -> Result<Vec<(LocalWallet, Option<Uuid>)>> {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let Some(tenant) = path.file_name().and_then(|name| name.to_str()?.parse::<Uuid>().ok()) else {
+                    continue;
+                };
+                for entry in fs::read_dir(&path)? {
+                    let path = entry?.path();
+                    if is_keystore_file(&path) {
+                        keys.push((self.decrypt_file(&path)?, Some(tenant)));
+                    }
+                }
+            } else if is_keystore_file(&path) {
+                keys.push((self.decrypt_file(&path)?, None));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Persist `wallet` for `tenant`, a key already stored is left untouched.
+    /// Returns whether a file was written.
+    pub fn save(&self, wallet: &LocalWallet, tenant: Option<Uuid>) -> Result<bool> {
+        let (dir, name) = self.location(wallet.address(), tenant);
+        if dir.join(&name).exists() {
+            return Ok(false);
+        }
+        fs::create_dir_all(&dir)?;
+        let content = encrypt_key(wallet, &self.passphrase)?.to_string();
+
+        // Written under a temporary name and renamed, a crash never leaves a partial keystore behind
+        let partial = dir.join(format!(".{}.partial", name));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&partial)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&partial, dir.join(&name))?;
+        Ok(true)
+    }
+
+    /// Delete the stored key of `address` for `tenant`
+    pub fn remove(&self, address: Address, tenant: Option<Uuid>) -> Result<()> {
+        let (dir, name) = self.location(address, tenant);
+        fs::remove_file(dir.join(name))?;
+        Ok(())
+    }
+
+    fn location(&self, address: Address, tenant: Option<Uuid>) -> (PathBuf, String) {
+        let dir = match tenant {
+            Some(tenant) => self.dir.join(tenant.to_string()),
+            None => self.dir.clone(),
+        };
+        (dir, format!("{}.json", to_checksum(&address, None)))
+    }
+
+    fn decrypt_file(&self, path: &Path) -> Result<LocalWallet> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(anyhow::Error::from)
+            .and_then(|keystore| decrypt(&keystore, &self.passphrase))
+            .with_context(|| format!("failed to decrypt keystore {}", path.display()))
+    }
+}
+
+/// Encrypt `wallet` into a V3 keystore protected by `password`
+pub fn encrypt(wallet: &LocalWallet, password: &str) -> Result<Value> {
+    if password.is_empty() {
+        return Err(ApiError::invalid_request("password must not be empty").into());
+    }
+    encrypt_key(wallet, password)
+}
+
+/// Decrypt a V3 keystore. Its KDF cost is checked first so a crafted keystore
+/// can't exhaust the server.
+pub fn decrypt(keystore: &Value, password: &str) -> Result<LocalWallet> {
+    check_kdf(keystore)?;
+    let invalid = |message: String| ApiError::new(ErrorCode::InvalidKeystore, message);
+    let bytes = |pointer: &str| {
+        keystore
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .and_then(|value| hex::decode(value).ok())
+            .ok_or_else(|| invalid(format!("keystore has no valid {}", pointer.trim_start_matches('/'))))
+    };
+    let param = |name: &str| keystore.pointer(&format!("/crypto/kdfparams/{}", name)).and_then(Value::as_u64);
+
+    if keystore.pointer("/crypto/cipher").and_then(Value::as_str) != Some("aes-128-ctr") {
+        return Err(invalid("unsupported cipher, expected aes-128-ctr".to_string()).into());
+    }
+    let (salt, iv) = (bytes("/crypto/kdfparams/salt")?, bytes("/crypto/cipherparams/iv")?);
+    let (ciphertext, mac) = (bytes("/crypto/ciphertext")?, bytes("/crypto/mac")?);
+    if iv.len() != 16 || param("dklen") != Some(32) {
+        return Err(invalid("keystore has an invalid iv or key length".to_string()).into());
+    }
+
+    let mut key = [0u8; 32];
+    match keystore.pointer("/crypto/kdf").and_then(Value::as_str) {
+        Some("scrypt") => {
+            let n = param("n").filter(|n| n.is_power_of_two() && *n > 1);
+            let params = match (n, param("r"), param("p")) {
+                (Some(n), Some(r), Some(p)) => scrypt::Params::new(n.trailing_zeros() as u8, r as u32, p as u32).ok(),
+                _ => None,
+            }
+            .ok_or_else(|| invalid("keystore has invalid scrypt parameters".to_string()))?;
+            scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key).map_err(|e| anyhow!("{}", e))?;
+        }
+        // pbkdf2, the only other function check_kdf lets through
+        _ => {
+            if keystore.pointer("/crypto/kdfparams/prf").and_then(Value::as_str) != Some("hmac-sha256") {
+                return Err(invalid("unsupported pbkdf2 function, expected hmac-sha256".to_string()).into());
+            }
+            let rounds = param("c").unwrap_or_default() as u32;
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), &salt, rounds, &mut key);
+        }
+    }
+
+    if keccak256([&key[16..], &ciphertext[..]].concat()).as_slice() != mac.as_slice() {
+        return Err(invalid("failed to decrypt keystore: wrong password".to_string()).into());
+    }
+    let mut secret = ciphertext;
+    Aes128Ctr::new(key[..16].into(), iv.as_slice().into()).apply_keystream(&mut secret);
+    LocalWallet::from_bytes(&secret).map_err(|e| invalid(format!("failed to decrypt keystore: {}", e)).into())
+}
+
+/// V3 keystore of `wallet`, encrypted in memory with scrypt and AES-128-CTR
+fn encrypt_key(wallet: &LocalWallet, password: &str) -> Result<Value> {
+    let mut rng = rand::thread_rng();
+    let (mut salt, mut iv) = ([0u8; 32], [0u8; 16]);
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+
+    let mut key = [0u8; 32];
+    let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P).map_err(|e| anyhow!("{}", e))?;
+    scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key).map_err(|e| anyhow!("{}", e))?;
+
+    let mut ciphertext = wallet.signer().to_bytes().to_vec();
+    Aes128Ctr::new(key[..16].into(), (&iv).into()).apply_keystream(&mut ciphertext);
+    let mac = keccak256([&key[16..], &ciphertext[..]].concat());
+
+    Ok(json!({
+        "version": 3,
+        "id": Uuid::new_v4(),
+        // Optional in the format but expected by most wallets
+        "address": hex::encode(wallet.address()),
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": hex::encode(iv) },
+            "ciphertext": hex::encode(ciphertext),
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": 1u64 << SCRYPT_LOG_N,
+                "r": SCRYPT_R,
+                "p": SCRYPT_P,
+                "salt": hex::encode(salt),
+            },
+            "mac": hex::encode(mac),
+        },
+    }))
+}
+
+fn check_kdf(keystore: &Value) -> Result<(), ApiError> {
+    let invalid = |message: &str| ApiError::new(ErrorCode::InvalidKeystore, message);
+    if keystore.get("version").and_then(Value::as_u64) != Some(3) {
+        return Err(invalid("only version 3 keystores are supported"));
+    }
+    let crypto = keystore.get("crypto").ok_or_else(|| invalid("keystore has no crypto section"))?;
+    let param = |name: &str| crypto.pointer(&format!("/kdfparams/{}", name)).and_then(Value::as_u64);
+
+    let within_limits = match crypto.get("kdf").and_then(Value::as_str) {
+        Some("scrypt") => matches!(
+            (param("n"), param("r"), param("p")),
+            (Some(n), Some(r), Some(p)) if n <= MAX_SCRYPT_N && r <= MAX_SCRYPT_R && p <= MAX_SCRYPT_P
+        ),
+        Some("pbkdf2") => param("c").is_some_and(|c| c <= MAX_PBKDF2_ROUNDS),
+        _ => return Err(invalid("unsupported key derivation function, expected scrypt or pbkdf2")),
+    };
+    if !within_limits {
+        return Err(invalid("key derivation parameters are missing or too expensive"));
+    }
+    Ok(())
+}
+
+fn is_keystore_file(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|ext| ext == "json")
+        && !path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("wallet-keystore-test-{}", Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn wallet() -> LocalWallet {
+        LocalWallet::new(&mut rand::thread_rng())
+    }
+
+    fn error_code(error: anyhow::Error) -> ErrorCode {
+        error.downcast_ref::<ApiError>().expect("an ApiError").code
+    }
+
+    #[test]
+    fn saved_keys_load_with_their_tenant() {
+        let dir = TempDir::new();
+        let store = KeyStore::open(&dir.0, "passphrase").unwrap();
+        let (platform, owned, tenant) = (wallet(), wallet(), Uuid::new_v4());
+        assert!(store.save(&platform, None).unwrap());
+        assert!(store.save(&owned, Some(tenant)).unwrap());
+        // saving again leaves the stored file alone
+        assert!(!store.save(&owned, Some(tenant)).unwrap());
+
+        let mut loaded: Vec<_> = store
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|(wallet, tenant)| (wallet.address(), tenant))
+            .collect();
+        loaded.sort();
+        let mut expected = vec![(platform.address(), None), (owned.address(), Some(tenant))];
+        expected.sort();
+        assert_eq!(loaded, expected);
+
+        let file = dir.0.join(tenant.to_string()).join(format!("{}.json", to_checksum(&owned.address(), None)));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        assert!(fs::read_dir(&dir.0).unwrap().all(|entry| !entry.unwrap().file_name().to_string_lossy().ends_with(".partial")));
+
+        let wrong = KeyStore::open(&dir.0, "another passphrase").unwrap();
+        assert!(wrong.load().is_err());
+
+        store.remove(owned.address(), Some(tenant)).unwrap();
+        assert!(!file.exists());
+        assert_eq!(store.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn exported_keys_decrypt_with_their_password() {
+        let wallet = wallet();
+        let exported = encrypt(&wallet, "password").unwrap();
+        assert_eq!(exported["address"], hex::encode(wallet.address()));
+        assert_eq!(decrypt(&exported, "password").unwrap().address(), wallet.address());
+        assert_eq!(error_code(decrypt(&exported, "wrong").unwrap_err()), ErrorCode::InvalidKeystore);
+        assert!(encrypt(&wallet, "").is_err());
+    }
+
+    #[test]
+    fn keystores_written_by_other_tools_decrypt() {
+        let dir = TempDir::new();
+        fs::create_dir_all(&dir.0).unwrap();
+        let wallet = wallet();
+        LocalWallet::encrypt_keystore(&dir.0, &mut rand::thread_rng(), wallet.signer().to_bytes(), "password", Some("key.json"))
+            .unwrap();
+        let keystore: Value = serde_json::from_str(&fs::read_to_string(dir.0.join("key.json")).unwrap()).unwrap();
+        assert_eq!(decrypt(&keystore, "password").unwrap().address(), wallet.address());
+    }
+
+    #[test]
+    fn pbkdf2_keystores_decrypt() {
+        let wallet = wallet();
+        let mut keystore = encrypt(&wallet, "password").unwrap();
+        let salt = keystore["crypto"]["kdfparams"]["salt"].clone();
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2::<Hmac<Sha256>>(b"password", &hex::decode(salt.as_str().unwrap()).unwrap(), 1_000, &mut key);
+        let iv = hex::decode(keystore["crypto"]["cipherparams"]["iv"].as_str().unwrap()).unwrap();
+        let mut ciphertext = wallet.signer().to_bytes().to_vec();
+        Aes128Ctr::new(key[..16].into(), iv.as_slice().into()).apply_keystream(&mut ciphertext);
+
+        keystore["crypto"]["kdf"] = "pbkdf2".into();
+        keystore["crypto"]["kdfparams"] = json!({ "c": 1_000, "dklen": 32, "prf": "hmac-sha256", "salt": salt });
+        keystore["crypto"]["ciphertext"] = hex::encode(&ciphertext).into();
+        keystore["crypto"]["mac"] = hex::encode(keccak256([&key[16..], &ciphertext[..]].concat())).into();
+        assert_eq!(decrypt(&keystore, "password").unwrap().address(), wallet.address());
+    }
+
+    #[test]
+    fn expensive_or_unknown_kdfs_are_refused_before_deriving() {
+        let keystore = encrypt(&wallet(), "password").unwrap();
+        let with = |pointer: &str, value: Value| {
+            let mut keystore = keystore.clone();
+            *keystore.pointer_mut(pointer).unwrap() = value;
+            keystore
+        };
+
+        for crafted in [
+            with("/crypto/kdfparams/n", json!(MAX_SCRYPT_N * 2)),
+            with("/crypto/kdfparams/r", json!(MAX_SCRYPT_R + 1)),
+            with("/crypto/kdfparams/p", json!(MAX_SCRYPT_P + 1)),
+            with("/crypto/kdfparams/n", json!(null)),
+            with("/crypto/kdf", json!("argon2")),
+            with("/version", json!(2)),
+        ] {
+            // a key derived from the wrong password fails the mac check, any other error means nothing was derived
+            let error = decrypt(&crafted, "wrong").unwrap_err();
+            assert!(!error.to_string().contains("wrong password"), "{}", error);
+            assert_eq!(error_code(error), ErrorCode::InvalidKeystore);
+        }
+        let error = decrypt(&with("/crypto/kdfparams/n", json!(MAX_SCRYPT_N * 2)), "wrong").unwrap_err();
+        assert!(error.to_string().contains("too expensive"));
+
+        let mut pbkdf2 = with("/crypto/kdf", json!("pbkdf2"));
+        pbkdf2["crypto"]["kdfparams"] = json!({ "c": MAX_PBKDF2_ROUNDS + 1, "dklen": 32, "prf": "hmac-sha256", "salt": "00" });
+        assert!(decrypt(&pbkdf2, "password").unwrap_err().to_string().contains("too expensive"));
+
+        // scrypt costs that aren't a power of two can't be derived at all
+        let error = decrypt(&with("/crypto/kdfparams/n", json!(1000)), "password").unwrap_err();
+        assert!(error.to_string().contains("invalid scrypt parameters"));
+    }
+}
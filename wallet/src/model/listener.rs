@@ -0,0 +1,103 @@
+use ethers::types::Address;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerMode {
+    /// Subscribed over the WebSocket connection
+    WebSocket,
+    /// Polling logs over HTTP until WebSocket is available
+    Http,
+    /// Between two subscriptions, catching up on missed logs
+    Reconnecting,
+}
+
+/// Supervision state of one contract event listener
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ListenerState {
+    #[schema(value_type = String)]
+    pub contract: Address,
+    pub mode: ListenerMode,
+    pub last_seen_block: Option<u64>,
+    pub events: u64,
+    pub restarts: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+pub struct ListenerRegistry {
+    listeners: HashMap<Address, ListenerState>,
+    /// Tenants that asked for each listener, one task serves all of them
+    owners: HashMap<Address, BTreeSet<Uuid>>,
+}
+
+impl ListenerRegistry {
+    pub fn new() -> Self {
+        Self {
+            listeners: HashMap::new(),
+            owners: HashMap::new(),
+        }
+    }
+
+    /// Register a listener for `tenant`, returns false if the contract is already listened to
+    pub fn register(&mut self, contract: Address, tenant: Option<Uuid>) -> bool {
+        if let Some(tenant) = tenant {
+            self.owners.entry(contract).or_default().insert(tenant);
+        }
+        if self.listeners.contains_key(&contract) {
+            return false;
+        }
+        self.listeners.insert(contract, ListenerState {
+            contract,
+            mode: ListenerMode::Reconnecting,
+            last_seen_block: None,
+            events: 0,
+            restarts: 0,
+            last_error: None,
+        });
+        true
+    }
+
+    pub fn contains(&self, contract: Address) -> bool {
+        self.listeners.contains_key(&contract)
+    }
+
+    pub fn contracts(&self) -> Vec<Address> {
+        self.listeners.keys().copied().collect()
+    }
+
+    pub fn all(&self) -> Vec<ListenerState> {
+        self.listeners.values().cloned().collect()
+    }
+
+    pub fn all_of(&self, tenant: Uuid) -> Vec<ListenerState> {
+        self.listeners
+            .values()
+            .filter(|state| self.owners.get(&state.contract).is_some_and(|owners| owners.contains(&tenant)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn contracts_of(&self, tenant: Uuid) -> Vec<Address> {
+        self.owners
+            .iter()
+            .filter(|(_, owners)| owners.contains(&tenant))
+            .map(|(contract, _)| *contract)
+            .collect()
+    }
+
+    pub fn update(&mut self, contract: Address, f: impl FnOnce(&mut ListenerState)) {
+        if let Some(state) = self.listeners.get_mut(&contract) {
+            f(state);
+        }
+    }
+}
+
+impl Default for ListenerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
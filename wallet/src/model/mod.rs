@@ -1,2 +1,18 @@
+pub mod abi_registry;
+pub mod api_key;
 pub mod app_model;
+pub mod block_tracker;
+pub mod deposit;
+pub mod event_store;
+pub mod heartbeat;
+pub mod history;
+pub mod job;
 pub mod keyring;
+pub mod keystore;
+pub mod listener;
+pub mod outbound_tx;
+pub mod rate_limit;
+pub mod response;
+pub mod siwe;
+pub mod tenant;
+pub mod watchlist;
@@ -0,0 +1,122 @@
+use crate::error::ApiError;
+use crate::types::Amount;
+use serde::Serialize;
+use std::str::FromStr;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Lifecycle of a transaction sent by the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboundTxStatus {
+    /// Nonce, gas and fees resolved
+    Built,
+    /// Signed, broadcast not acknowledged yet, e.g. after an RPC timeout
+    Signed,
+    /// Accepted by the node, waiting to be mined
+    Submitted,
+    /// Mined in a canonical block, waiting for confirmations
+    Included,
+    /// Final, executed successfully
+    Confirmed,
+    /// Final, execution reverted
+    Reverted,
+    /// Refused by the node at broadcast
+    Rejected,
+    /// Left the mempool without being mined, or its nonce was used by another transaction
+    Dropped,
+    /// Left built by a request that failed before signing, never broadcast
+    Expired,
+}
+
+impl OutboundTxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboundTxStatus::Built => "built",
+            OutboundTxStatus::Signed => "signed",
+            OutboundTxStatus::Submitted => "submitted",
+            OutboundTxStatus::Included => "included",
+            OutboundTxStatus::Confirmed => "confirmed",
+            OutboundTxStatus::Reverted => "reverted",
+            OutboundTxStatus::Rejected => "rejected",
+            OutboundTxStatus::Dropped => "dropped",
+            OutboundTxStatus::Expired => "expired",
+        }
+    }
+
+    /// Statuses the tracker keeps polling
+    pub const IN_FLIGHT: [OutboundTxStatus; 3] =
+        [OutboundTxStatus::Signed, OutboundTxStatus::Submitted, OutboundTxStatus::Included];
+}
+
+impl FromStr for OutboundTxStatus {
+    type Err = ApiError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "built" => Ok(OutboundTxStatus::Built),
+            "signed" => Ok(OutboundTxStatus::Signed),
+            "submitted" => Ok(OutboundTxStatus::Submitted),
+            "included" => Ok(OutboundTxStatus::Included),
+            "confirmed" => Ok(OutboundTxStatus::Confirmed),
+            "reverted" => Ok(OutboundTxStatus::Reverted),
+            "rejected" => Ok(OutboundTxStatus::Rejected),
+            "dropped" => Ok(OutboundTxStatus::Dropped),
+            "expired" => Ok(OutboundTxStatus::Expired),
+            other => Err(ApiError::invalid_request(format!("unknown transaction status {}", other))),
+        }
+    }
+}
+
+/// Transaction built and broadcast by the wallet, stored in `outbound_txs`.
+/// Fees are in wei.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OutboundTx {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<Uuid>,
+    /// `eth` or `erc20`
+    pub kind: String,
+    pub from: String,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub amount: Amount,
+    /// API request the transaction was built from
+    #[schema(value_type = Object)]
+    pub request: serde_json::Value,
+    pub nonce: Option<u64>,
+    pub gas_limit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+    /// Hex encoded signed transaction, can be rebroadcast as is
+    pub signed_payload: Option<String>,
+    pub tx_hash: Option<String>,
+    pub status: OutboundTxStatus,
+    /// Flagged when not mined long after submission, cleared once mined
+    pub stuck: bool,
+    pub block_number: Option<u64>,
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub submitted_at: Option<u64>,
+    pub finalized_at: Option<u64>,
+    /// Status changes, oldest first. Only returned for a single transaction.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<TxTransition>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TxTransition {
+    pub from: Option<OutboundTxStatus>,
+    pub to: OutboundTxStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub at: u64,
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Counters are swept once this many clients are tracked
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Budget a route draws from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    /// Queries and other cheap requests
+    Read,
+    /// Signing, sending and backfills
+    Expensive,
+    /// Failed authentications of a client IP, counted before credentials are looked up
+    AuthFailure,
+}
+
+/// Per-key overrides of the configured budgets, in requests per window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RateLimitOverride {
+    pub read: Option<u32>,
+    pub expensive: Option<u32>,
+}
+
+impl RateLimitOverride {
+    pub fn get(&self, class: RateClass) -> Option<u32> {
+        match class {
+            RateClass::Read => self.read,
+            RateClass::Expensive => self.expensive,
+            // Counted per client IP, before the key is known
+            RateClass::AuthFailure => None,
+        }
+    }
+}
+
+/// Outcome of counting a request, rendered as `RateLimit-*` headers
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the window resets
+    pub reset: u64,
+    pub allowed: bool,
+}
+
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Fixed window request counters per client and class, kept in process: each
+/// node of a multi-node deployment enforces the budgets on its own
+pub struct RateLimitStore {
+    windows: Mutex<HashMap<(String, RateClass), Window>>,
+}
+
+impl RateLimitStore {
+    pub fn new() -> Self {
+        Self {
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request of `client` against `limit` requests per `window`
+    pub fn check(&self, client: &str, class: RateClass, limit: u32, window: Duration) -> RateLimitStatus {
+        self.update(client, class, limit, window, true)
+    }
+
+    /// Whether `client` may still make a request, without counting one
+    pub fn peek(&self, client: &str, class: RateClass, limit: u32, window: Duration) -> RateLimitStatus {
+        self.update(client, class, limit, window, false)
+    }
+
+    fn update(&self, client: &str, class: RateClass, limit: u32, window: Duration, count: bool) -> RateLimitStatus {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= SWEEP_THRESHOLD {
+            windows.retain(|_, w| now.duration_since(w.started) < window);
+        }
+
+        let entry = windows.entry((client.to_string(), class)).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(entry.started) >= window {
+            *entry = Window {
+                started: now,
+                count: 0,
+            };
+        }
+        let allowed = entry.count < limit;
+        if allowed && count {
+            entry.count += 1;
+        }
+
+        let elapsed = now.duration_since(entry.started);
+        RateLimitStatus {
+            limit,
+            remaining: limit - entry.count,
+            reset: window.saturating_sub(elapsed).as_secs_f64().ceil() as u64,
+            allowed,
+        }
+    }
+}
+
+impl Default for RateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn requests_are_counted_until_the_limit() {
+        let store = RateLimitStore::new();
+        let statuses: Vec<_> = (0..4).map(|_| store.check("key:a", RateClass::Read, 3, WINDOW)).collect();
+        assert_eq!(statuses.iter().map(|s| s.allowed).collect::<Vec<_>>(), [true, true, true, false]);
+        assert_eq!(statuses.iter().map(|s| s.remaining).collect::<Vec<_>>(), [2, 1, 0, 0]);
+        assert!(statuses.iter().all(|s| s.limit == 3 && s.reset > 0 && s.reset <= 60));
+    }
+
+    #[test]
+    fn clients_and_classes_have_their_own_budget() {
+        let store = RateLimitStore::new();
+        assert!(store.check("key:a", RateClass::Expensive, 1, WINDOW).allowed);
+        assert!(!store.check("key:a", RateClass::Expensive, 1, WINDOW).allowed);
+        assert!(store.check("key:a", RateClass::Read, 1, WINDOW).allowed);
+        assert!(store.check("key:b", RateClass::Expensive, 1, WINDOW).allowed);
+    }
+
+    #[test]
+    fn window_resets_the_count() {
+        let store = RateLimitStore::new();
+        let window = Duration::from_millis(50);
+        assert!(store.check("ip:10.0.0.1", RateClass::Read, 1, window).allowed);
+        assert!(!store.check("ip:10.0.0.1", RateClass::Read, 1, window).allowed);
+        std::thread::sleep(window);
+        let status = store.check("ip:10.0.0.1", RateClass::Read, 1, window);
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 0);
+    }
+
+    #[test]
+    fn peeking_does_not_count() {
+        let store = RateLimitStore::new();
+        for _ in 0..3 {
+            assert!(store.peek("ip:10.0.0.1", RateClass::AuthFailure, 2, WINDOW).allowed);
+        }
+        store.check("ip:10.0.0.1", RateClass::AuthFailure, 2, WINDOW);
+        store.check("ip:10.0.0.1", RateClass::AuthFailure, 2, WINDOW);
+        let status = store.peek("ip:10.0.0.1", RateClass::AuthFailure, 2, WINDOW);
+        assert!(!status.allowed);
+        assert_eq!(status.remaining, 0);
+    }
+
+    #[test]
+    fn keys_cannot_override_the_auth_failure_budget() {
+        let overrides = RateLimitOverride {
+            read: Some(5),
+            expensive: Some(1),
+        };
+        assert_eq!(overrides.get(RateClass::Read), Some(5));
+        assert_eq!(overrides.get(RateClass::AuthFailure), None);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::extract::Json;
+use crate::types::Amount;
+use ethers::types::{Address, H256};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Envelope of every successful JSON response
+#[derive(Serialize, ToSchema)]
+pub struct ApiResponse<T> {
+    pub status: u16,
+    pub message: String,
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn success(data: T) -> Json<Self> {
+        Json(Self {
+            status: 200,
+            message: "success".to_string(),
+            data,
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressResponse {
+    #[schema(value_type = String)]
+    pub address: Address,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BalanceResponse {
+    pub balance: Amount,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionHashResponse {
+    /// Outbound transaction id, its lifecycle is at `/tx/{id}`
+    pub id: Uuid,
+    #[schema(value_type = String)]
+    pub transaction_hash: H256,
+}
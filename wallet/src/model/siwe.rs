@@ -0,0 +1,267 @@
+use crate::error::{ApiError, ErrorCode};
+use chrono::{DateTime, FixedOffset, Utc};
+use ethers::types::Address;
+use ethers::utils::to_checksum;
+use std::str::FromStr;
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+/// Tolerated clock difference between the signer and this service, in seconds
+const CLOCK_SKEW: i64 = 60;
+
+/// Sign-In with Ethereum message (EIP-4361)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiweMessage {
+    pub scheme: Option<String>,
+    pub domain: String,
+    pub address: Address,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<FixedOffset>,
+    pub expiration_time: Option<DateTime<FixedOffset>>,
+    pub not_before: Option<DateTime<FixedOffset>>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl SiweMessage {
+    /// Check the message was issued for this service and is valid at `now`.
+    /// The nonce and signature are checked by the caller.
+    pub fn validate(&self, domain: &str, chain_id: u64, now: DateTime<Utc>) -> Result<(), ApiError> {
+        let unauthorized = |message: String| Err(ApiError::new(ErrorCode::Unauthorized, message));
+
+        if self.domain != domain {
+            return unauthorized(format!("message is for domain {}, expected {}", self.domain, domain));
+        }
+        if self.chain_id != chain_id {
+            return unauthorized(format!("message is for chain {}, expected {}", self.chain_id, chain_id));
+        }
+        if self.issued_at.timestamp() > now.timestamp() + CLOCK_SKEW {
+            return unauthorized("message is issued in the future".to_string());
+        }
+        if let Some(expiration_time) = self.expiration_time
+            && expiration_time.timestamp() <= now.timestamp()
+        {
+            return unauthorized("message has expired".to_string());
+        }
+        if let Some(not_before) = self.not_before
+            && not_before.timestamp() > now.timestamp() + CLOCK_SKEW
+        {
+            return unauthorized("message is not valid yet".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SiweMessage {
+    type Err = ApiError;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| ApiError::invalid_request(format!("invalid SIWE message: {}", reason));
+        let mut lines = message.lines().peekable();
+
+        let authority = lines
+            .next()
+            .and_then(|line| line.strip_suffix(HEADER_SUFFIX))
+            .ok_or_else(|| invalid("missing header"))?;
+        let (scheme, domain) = match authority.split_once("://") {
+            Some((scheme, domain)) => (Some(scheme.to_string()), domain),
+            None => (None, authority),
+        };
+        if domain.is_empty() {
+            return Err(invalid("empty domain"));
+        }
+
+        let address_line = lines.next().ok_or_else(|| invalid("missing address"))?;
+        let address: Address = address_line.parse().map_err(|_| invalid("invalid address"))?;
+        if to_checksum(&address, None) != address_line {
+            return Err(invalid("address is not EIP-55 checksummed"));
+        }
+
+        // Blank line, optional statement, blank line
+        if lines.next() != Some("") {
+            return Err(invalid("expected a blank line after the address"));
+        }
+        let mut statement = None;
+        if lines.peek().is_some_and(|line| !line.is_empty() && !line.starts_with("URI: ")) {
+            statement = lines.next().map(str::to_string);
+        }
+        while lines.peek() == Some(&"") {
+            lines.next();
+        }
+
+        let mut field = |tag: &str| -> Option<String> {
+            let value = lines.peek()?.strip_prefix(tag)?.to_string();
+            lines.next();
+            Some(value)
+        };
+        let uri = field("URI: ").ok_or_else(|| invalid("missing URI"))?;
+        let version = field("Version: ").ok_or_else(|| invalid("missing Version"))?;
+        if version != "1" {
+            return Err(invalid("unsupported version"));
+        }
+        let chain_id = field("Chain ID: ")
+            .ok_or_else(|| invalid("missing Chain ID"))?
+            .parse()
+            .map_err(|_| invalid("invalid Chain ID"))?;
+        let nonce = field("Nonce: ").ok_or_else(|| invalid("missing Nonce"))?;
+        if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid("nonce must be at least 8 alphanumeric characters"));
+        }
+        let timestamp = |value: String, name: &str| {
+            DateTime::parse_from_rfc3339(&value).map_err(|_| invalid(&format!("invalid {}", name)))
+        };
+        let issued_at = timestamp(field("Issued At: ").ok_or_else(|| invalid("missing Issued At"))?, "Issued At")?;
+        let expiration_time = field("Expiration Time: ")
+            .map(|value| timestamp(value, "Expiration Time"))
+            .transpose()?;
+        let not_before = field("Not Before: ").map(|value| timestamp(value, "Not Before")).transpose()?;
+        let request_id = field("Request ID: ");
+
+        let mut resources = Vec::new();
+        if field("Resources:").is_some() {
+            while let Some(resource) = field("- ") {
+                resources.push(resource);
+            }
+        }
+        if lines.any(|line| !line.is_empty()) {
+            return Err(invalid("unexpected content after the fields"));
+        }
+
+        Ok(Self {
+            scheme,
+            domain: domain.to_string(),
+            address,
+            statement,
+            uri,
+            version,
+            chain_id,
+            nonce,
+            issued_at,
+            expiration_time,
+            not_before,
+            request_id,
+            resources,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example message from EIP-4361
+    const MESSAGE: &str = "service.invalid wants you to sign in with your Ethereum account:
+0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2
+
+I accept the ServiceOrg Terms of Service: https://service.invalid/tos
+
+URI: https://service.invalid/login
+Version: 1
+Chain ID: 1
+Nonce: 32891756
+Issued At: 2021-09-30T16:25:24Z
+Resources:
+- ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/
+- https://example.com/my-web2-claim.json";
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap().to_utc()
+    }
+
+    #[test]
+    fn parses_the_eip_example() {
+        let siwe: SiweMessage = MESSAGE.parse().unwrap();
+        assert_eq!(siwe.scheme, None);
+        assert_eq!(siwe.domain, "service.invalid");
+        assert_eq!(siwe.address, "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap());
+        assert_eq!(
+            siwe.statement.as_deref(),
+            Some("I accept the ServiceOrg Terms of Service: https://service.invalid/tos")
+        );
+        assert_eq!(siwe.uri, "https://service.invalid/login");
+        assert_eq!(siwe.chain_id, 1);
+        assert_eq!(siwe.nonce, "32891756");
+        assert_eq!(siwe.issued_at, DateTime::parse_from_rfc3339("2021-09-30T16:25:24Z").unwrap());
+        assert_eq!(siwe.expiration_time, None);
+        assert_eq!(
+            siwe.resources,
+            [
+                "ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/",
+                "https://example.com/my-web2-claim.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn statement_and_optional_fields_may_be_omitted() {
+        let message = "https://example.com wants you to sign in with your Ethereum account:
+0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2
+
+
+URI: https://example.com/login
+Version: 1
+Chain ID: 5
+Nonce: abcdef12
+Issued At: 2021-09-30T16:25:24.000Z
+Expiration Time: 2021-09-30T17:25:24Z
+Not Before: 2021-09-30T16:20:00Z
+Request ID: 42";
+        let siwe: SiweMessage = message.parse().unwrap();
+        assert_eq!(siwe.scheme.as_deref(), Some("https"));
+        assert_eq!(siwe.domain, "example.com");
+        assert_eq!(siwe.statement, None);
+        assert_eq!(siwe.chain_id, 5);
+        assert!(siwe.expiration_time.is_some());
+        assert!(siwe.not_before.is_some());
+        assert_eq!(siwe.request_id.as_deref(), Some("42"));
+        assert!(siwe.resources.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let cases = [
+            (MESSAGE.replace("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"), "checksummed"),
+            (MESSAGE.replace("Version: 1", "Version: 2"), "unsupported version"),
+            (MESSAGE.replace("Nonce: 32891756", "Nonce: 1234"), "nonce"),
+            (MESSAGE.replace("Chain ID: 1", "Chain ID: one"), "invalid Chain ID"),
+            (MESSAGE.replace("2021-09-30T16:25:24Z", "yesterday"), "invalid Issued At"),
+            (MESSAGE.replace(" wants you to sign in", " would like you to sign in"), "missing header"),
+            (format!("{}\nExtra: field", MESSAGE), "unexpected content"),
+        ];
+        for (message, reason) in cases {
+            let error = message.parse::<SiweMessage>().unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidRequest);
+            assert!(error.message.contains(reason), "{} does not mention {}", error.message, reason);
+        }
+    }
+
+    #[test]
+    fn validates_domain_chain_and_validity_window() {
+        let siwe: SiweMessage = MESSAGE.parse().unwrap();
+        let now = at("2021-09-30T16:30:00Z");
+        assert!(siwe.validate("service.invalid", 1, now).is_ok());
+
+        let error = siwe.validate("evil.invalid", 1, now).unwrap_err();
+        assert_eq!(error.code, ErrorCode::Unauthorized);
+        assert!(error.message.contains("domain"));
+        assert!(siwe.validate("service.invalid", 5, now).unwrap_err().message.contains("chain"));
+        assert!(
+            siwe.validate("service.invalid", 1, at("2021-09-30T16:00:00Z"))
+                .unwrap_err()
+                .message
+                .contains("future")
+        );
+
+        let expiring = SiweMessage {
+            expiration_time: Some(DateTime::parse_from_rfc3339("2021-09-30T17:00:00Z").unwrap()),
+            not_before: Some(DateTime::parse_from_rfc3339("2021-09-30T16:40:00Z").unwrap()),
+            ..siwe
+        };
+        assert!(expiring.validate("service.invalid", 1, at("2021-09-30T16:30:00Z")).unwrap_err().message.contains("not valid yet"));
+        assert!(expiring.validate("service.invalid", 1, at("2021-09-30T16:45:00Z")).is_ok());
+        assert!(expiring.validate("service.invalid", 1, at("2021-09-30T17:00:00Z")).unwrap_err().message.contains("expired"));
+    }
+}
@@ -0,0 +1,26 @@
+use ethers::types::Address;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Account owning keys, watched addresses and listeners. API keys bound to a
+/// tenant only see and sign with its assets.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Tenant {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TenantAssets {
+    pub tenant: Tenant,
+    /// Addresses of the keyring keys owned by the tenant
+    #[schema(value_type = Vec<String>)]
+    pub keys: Vec<Address>,
+    #[schema(value_type = Vec<String>)]
+    pub watched: Vec<Address>,
+    /// Token contracts listened to for the tenant
+    #[schema(value_type = Vec<String>)]
+    pub listeners: Vec<Address>,
+}
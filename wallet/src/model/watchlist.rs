@@ -0,0 +1,56 @@
+use ethers::types::Address;
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+/// Watch-only addresses and the tenants watching them. An address watched with
+/// platform credentials only has no tenant.
+pub struct WatchList {
+    watched: HashMap<Address, BTreeSet<Uuid>>,
+}
+
+impl WatchList {
+    pub fn new() -> Self {
+        Self { watched: HashMap::new() }
+    }
+
+    pub fn watch(&mut self, address: Address, tenant: Option<Uuid>) {
+        let tenants = self.watched.entry(address).or_default();
+        if let Some(tenant) = tenant {
+            tenants.insert(tenant);
+        }
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        self.watched.contains_key(&address)
+    }
+
+    pub fn is_watched_by(&self, address: Address, tenant: Uuid) -> bool {
+        self.watched.get(&address).is_some_and(|tenants| tenants.contains(&tenant))
+    }
+
+    /// Tenants watching `address`
+    pub fn tenants(&self, address: Address) -> Vec<Uuid> {
+        self.watched
+            .get(&address)
+            .map(|tenants| tenants.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.watched.keys().copied().collect()
+    }
+
+    pub fn addresses_of(&self, tenant: Uuid) -> Vec<Address> {
+        self.watched
+            .iter()
+            .filter(|(_, tenants)| tenants.contains(&tenant))
+            .map(|(address, _)| *address)
+            .collect()
+    }
+}
+
+impl Default for WatchList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,806 @@
+//! OpenAPI 3 description of the HTTP API. Handlers live in `impl` blocks, where
+//! `#[utoipa::path]` can't generate its marker types, so each route is declared
+//! here by a stub mirroring the one in `router.rs`. Request and response schemas
+//! are derived from the structs the handlers actually use, and `tests/openapi.rs`
+//! checks each stub's body, query and response types against its handler.
+#![allow(dead_code)]
+
+use crate::error::ErrorBody;
+use crate::handler::abi_handler::{AbiListResponse, RegisterAbiRequest};
+use crate::handler::auth_handler::{
+    ApiKeysResponse, CreateApiKeyRequest, RotateApiKeyRequest, SessionsResponse, SiweVerifyRequest,
+};
+use crate::handler::block_handler::{
+    BlockHeightResponse, BlockQuery, BlockRangeQuery, BlockResponse, LatestBlockResponse, ReorgsResponse,
+};
+use crate::handler::deposit_handler::{DepositQuery, DepositsResponse, WatchAddressRequest};
+use crate::handler::erc20_handler::{
+    BackfillRequest, BackfillResponse, ERC20BalanceRequest, ERC20SendTxRequest, ListenResponse,
+};
+use crate::handler::ether_handler::SendTxRequest;
+use crate::handler::healthy_handler::{HealthyResponse, LiveResponse, ReadyResponse};
+use crate::handler::history_handler::HistoryQuery;
+use crate::handler::job_handler::JobResponse;
+use crate::handler::rpc_handler::EndpointsResponse;
+use crate::handler::tenant_handler::{CreateTenantRequest, TenantsResponse};
+use crate::handler::tx_handler::{TransactionsResponse, TxQuery};
+use crate::handler::wallet_handler::{
+    CreateWalletRequest, ExportKeystoreRequest, ImportKeystoreRequest, ImportMnemonicRequest, ImportPriKeyRequest,
+    KeystoreResponse,
+};
+use crate::model::api_key::{ApiKey, AuthSession, CreatedApiKey};
+use crate::model::listener::ListenerState;
+use crate::model::outbound_tx::OutboundTx;
+use crate::model::response::{AddressResponse, ApiResponse, BalanceResponse, TransactionHashResponse};
+use crate::model::tenant::{Tenant, TenantAssets};
+use crate::service::block_service::BlockPage;
+use crate::service::erc20_service::TokenInfo;
+use crate::service::ether_service::TransactionDetail;
+use crate::service::history_service::HistoryPage;
+use crate::service::siwe_service::{SiweNonce, SiweSession};
+use crate::service::trace_service::TransactionTrace;
+use crate::service::wallet_service::WalletEntity;
+use utoipa::openapi::path::{ParameterBuilder, ParameterIn};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Ref, Required, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Wallet API",
+        description = "Custodial Ethereum wallet: keys, balances, transfers, ERC20 tokens, deposits and chain data. \
+            Successful responses are wrapped in `{status, message, data}`, errors are `{status, code, message}`."
+    ),
+    paths(
+        healthy,
+        live,
+        ready,
+        get_block_height,
+        get_latest_block,
+        get_reorgs,
+        get_block,
+        get_blocks,
+        create_wallet,
+        import_private_key,
+        import_mnemonic,
+        import_keystore,
+        export_keystore,
+        get_balance,
+        get_transaction,
+        trace_transaction,
+        send_transaction,
+        watch_address,
+        get_history,
+        get_deposits,
+        erc20_get_balance,
+        erc20_send_transaction,
+        erc20_get_info,
+        erc20_listen,
+        erc20_get_listeners,
+        erc20_backfill,
+        get_job,
+        list_txs,
+        get_tx,
+        get_endpoints,
+        list_abis,
+        register_abi,
+        list_api_keys,
+        create_api_key,
+        revoke_api_key,
+        rotate_api_key,
+        list_sessions,
+        revoke_session,
+        list_tenants,
+        create_tenant,
+        get_tenant_assets,
+        siwe_nonce,
+        siwe_verify,
+        logout,
+        metrics,
+    ),
+    components(schemas(ErrorBody)),
+    modifiers(&BearerAuth, &IdempotencyKey),
+    tags(
+        (name = "health", description = "Liveness and readiness probes"),
+        (name = "block", description = "Blocks and reorgs"),
+        (name = "wallet", description = "Managed keys, ETH transfers and transaction history"),
+        (name = "deposit", description = "Incoming transfers to managed and watched addresses"),
+        (name = "erc20", description = "ERC20 balances, transfers and event listeners"),
+        (name = "job", description = "Background jobs"),
+        (name = "tx", description = "Lifecycle of the transactions sent by the wallet"),
+        (name = "rpc", description = "RPC endpoint pool"),
+        (name = "abi", description = "Contract ABIs used for decoding"),
+        (
+            name = "auth",
+            description = "API keys and Sign-In with Ethereum sessions, both sent as `Authorization: Bearer <token>` or `X-Api-Key`"
+        ),
+        (name = "tenant", description = "Accounts owning keys, watched addresses and listeners"),
+        (name = "metrics", description = "Prometheus metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the bearer scheme, accepting API keys and session tokens, and the
+/// 401 / 403 / 429 responses shared by every operation requiring one. The required
+/// scope is listed in the operation's `security`.
+struct BearerAuth;
+
+/// Authenticated routes and sign-in count against a request budget
+const RATE_LIMITED: &str = "Rate limit exceeded, see the `RateLimit-*` and `Retry-After` headers";
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+
+        let error = |description: &str| {
+            ResponseBuilder::new()
+                .description(description)
+                .content(
+                    "application/json",
+                    ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorBody"))).build(),
+                )
+                .build()
+        };
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [&mut item.get, &mut item.post, &mut item.delete].into_iter().flatten() {
+                if operation.security.is_some() {
+                    let responses = &mut operation.responses.responses;
+                    responses.insert("401".to_string(), error("Missing, invalid, revoked or expired API key or session token").into());
+                    // Operations refusing other tenants' assets document their own 403
+                    responses
+                        .entry("403".to_string())
+                        .or_insert_with(|| error("Credentials lack the required scope").into());
+                    responses.insert("429".to_string(), error(RATE_LIMITED).into());
+                }
+            }
+        }
+    }
+}
+
+/// Documents the optional `Idempotency-Key` header of every authenticated
+/// `POST` and `DELETE`, honored by the idempotency middleware
+struct IdempotencyKey;
+
+/// Routes outside the idempotency middleware: their responses hold secrets, or
+/// like logout they are only rate limited per client IP
+const NOT_IDEMPOTENT: &[&str] = &[
+    "/wallet/create",
+    "/wallet/keystore/export",
+    "/auth/keys",
+    "/auth/keys/{id}/rotate",
+    "/auth/logout",
+];
+
+impl Modify for IdempotencyKey {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let header = ParameterBuilder::new()
+            .name("Idempotency-Key")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Unique key making retries safe for 24 hours. A retry with the same key and body returns the \
+                 stored response with `Idempotent-Replayed: true`; 409 `idempotency_key_in_use` while the first \
+                 request runs, or for 2 minutes if it was interrupted, 422 `idempotency_key_reused` if the key was used for another request.",
+            ))
+            .schema(Some(ObjectBuilder::new().schema_type(Type::String).max_length(Some(255))))
+            .build();
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if NOT_IDEMPOTENT.contains(&path.as_str()) {
+                continue;
+            }
+            for operation in [&mut item.post, &mut item.delete].into_iter().flatten() {
+                if operation.security.is_some() {
+                    operation.parameters.get_or_insert_with(Vec::new).push(header.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Legacy health check
+#[utoipa::path(post, path = "/health", tag = "health", responses((status = 200, body = HealthyResponse)))]
+fn healthy() {}
+
+/// Liveness probe, only tells the process is serving requests
+#[utoipa::path(get, path = "/live", tag = "health", responses((status = 200, body = LiveResponse)))]
+fn live() {}
+
+/// Readiness probe with the state of every dependency
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All critical dependencies are up", body = ReadyResponse),
+        (status = 503, description = "A critical dependency is down or a background task is stale", body = ReadyResponse),
+    )
+)]
+fn ready() {}
+
+/// Current block number
+#[utoipa::path(
+    get,
+    path = "/block/height",
+    tag = "block",
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<BlockHeightResponse>),
+        (status = 503, description = "No RPC endpoint available", body = ErrorBody),
+    )
+)]
+fn get_block_height() {}
+
+/// Latest block with transaction hashes
+#[utoipa::path(
+    get,
+    path = "/block/latest",
+    tag = "block",
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<LatestBlockResponse>),
+        (status = 503, description = "No RPC endpoint available", body = ErrorBody),
+    )
+)]
+fn get_latest_block() {}
+
+/// Reorgs detected by the block monitor, most recent last
+#[utoipa::path(
+    get,
+    path = "/block/reorgs",
+    tag = "block",
+    security(("bearer" = ["read"])),
+    responses((status = 200, body = ApiResponse<ReorgsResponse>))
+)]
+fn get_reorgs() {}
+
+/// Block by number, hash or tag
+#[utoipa::path(
+    get,
+    path = "/block/{id}",
+    tag = "block",
+    params(("id" = String, Path, description = "Block number, hash, or `latest` / `pending` / `earliest`"), BlockQuery),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<BlockResponse>),
+        (status = 400, description = "Invalid block id", body = ErrorBody),
+        (status = 404, description = "Unknown block", body = ErrorBody),
+    )
+)]
+fn get_block() {}
+
+/// Page of block summaries starting at `from`
+#[utoipa::path(
+    get,
+    path = "/blocks",
+    tag = "block",
+    params(BlockRangeQuery),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<BlockPage>),
+        (status = 400, description = "Invalid range", body = ErrorBody),
+    )
+)]
+fn get_blocks() {}
+
+/// Create a wallet from a new BIP-39 mnemonic and add its first account to the keyring.
+/// The mnemonic is only returned once and retries are not deduplicated.
+#[utoipa::path(
+    post,
+    path = "/wallet/create",
+    tag = "wallet",
+    request_body = CreateWalletRequest,
+    security(("bearer" = ["sign"])),
+    responses(
+        (status = 200, body = ApiResponse<WalletEntity>),
+        (status = 400, description = "Unsupported word count", body = ErrorBody),
+    )
+)]
+fn create_wallet() {}
+
+/// Import a private key into the keyring
+#[utoipa::path(
+    post,
+    path = "/wallet/import",
+    tag = "wallet",
+    request_body = ImportPriKeyRequest,
+    security(("bearer" = ["sign"])),
+    responses(
+        (status = 200, body = ApiResponse<AddressResponse>),
+        (status = 400, description = "Invalid private key", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the key belongs to another tenant", body = ErrorBody),
+    )
+)]
+fn import_private_key() {}
+
+/// Restore the first account of a BIP-39 mnemonic into the keyring
+#[utoipa::path(
+    post,
+    path = "/wallet/import/mnemonic",
+    tag = "wallet",
+    request_body = ImportMnemonicRequest,
+    security(("bearer" = ["sign"])),
+    responses(
+        (status = 200, body = ApiResponse<AddressResponse>),
+        (status = 400, description = "Invalid mnemonic", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the key belongs to another tenant", body = ErrorBody),
+    )
+)]
+fn import_mnemonic() {}
+
+/// Import a key from an encrypted V3 keystore
+#[utoipa::path(
+    post,
+    path = "/wallet/keystore/import",
+    tag = "wallet",
+    request_body = ImportKeystoreRequest,
+    security(("bearer" = ["sign"])),
+    responses(
+        (status = 200, body = ApiResponse<AddressResponse>),
+        (status = 400, description = "Unsupported keystore, too expensive key derivation or wrong password", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the key belongs to another tenant", body = ErrorBody),
+    )
+)]
+fn import_keystore() {}
+
+/// Export a key as a V3 keystore encrypted with the given password
+///
+/// Hands out the key itself, so it takes the admin scope only platform credentials hold.
+#[utoipa::path(
+    post,
+    path = "/wallet/keystore/export",
+    tag = "wallet",
+    request_body = ExportKeystoreRequest,
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<KeystoreResponse>),
+        (status = 400, description = "Invalid address or empty password", body = ErrorBody),
+        (status = 404, description = "No key for the address", body = ErrorBody),
+    )
+)]
+fn export_keystore() {}
+
+/// ETH balance of an address
+#[utoipa::path(
+    get,
+    path = "/wallet/balance/{address}",
+    tag = "wallet",
+    params(("address" = String, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<BalanceResponse>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+    )
+)]
+fn get_balance() {}
+
+/// Transaction with its receipt, fee and decoded calldata and logs
+#[utoipa::path(
+    get,
+    path = "/wallet/transaction/{tx_hash}",
+    tag = "wallet",
+    params(("tx_hash" = String, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<TransactionDetail>),
+        (status = 400, description = "Invalid hash", body = ErrorBody),
+        (status = 404, description = "Unknown transaction", body = ErrorBody),
+    )
+)]
+fn get_transaction() {}
+
+/// Internal ETH transfers of a transaction, traced with `callTracer`
+#[utoipa::path(
+    get,
+    path = "/wallet/transaction/{tx_hash}/trace",
+    tag = "wallet",
+    params(("tx_hash" = String, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<TransactionTrace>),
+        (status = 400, description = "Invalid hash", body = ErrorBody),
+    )
+)]
+fn trace_transaction() {}
+
+/// Send ETH from a managed key
+#[utoipa::path(
+    post,
+    path = "/wallet/send",
+    tag = "wallet",
+    request_body = SendTxRequest,
+    security(("bearer" = ["send"])),
+    responses(
+        (status = 200, body = ApiResponse<TransactionHashResponse>),
+        (status = 400, description = "Invalid address or amount", body = ErrorBody),
+        (status = 404, description = "Sender is not a key of the caller's tenant", body = ErrorBody),
+        (status = 409, description = "Nonce conflict", body = ErrorBody),
+        (status = 422, description = "Insufficient funds, fee too high or reverted", body = ErrorBody),
+        (status = 503, description = "Broadcast not acknowledged, the transaction is followed at `/tx/{id}`", body = ErrorBody),
+    )
+)]
+fn send_transaction() {}
+
+/// Watch an address for deposits
+#[utoipa::path(
+    post,
+    path = "/wallet/watch",
+    tag = "deposit",
+    request_body = WatchAddressRequest,
+    security(("bearer" = ["watch"])),
+    responses(
+        (status = 200, body = ApiResponse<AddressResponse>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the address holds a key of another tenant", body = ErrorBody),
+    )
+)]
+fn watch_address() {}
+
+/// Indexed ETH, token and internal transfers of an address
+#[utoipa::path(
+    get,
+    path = "/wallet/{address}/history",
+    tag = "wallet",
+    params(("address" = String, Path), HistoryQuery),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<HistoryPage>),
+        (status = 400, description = "Invalid address, cursor or filter", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the address does not belong to the caller's tenant", body = ErrorBody),
+        (status = 503, description = "Database unavailable", body = ErrorBody),
+    )
+)]
+fn get_history() {}
+
+/// Deposits seen by the scanner to the addresses of the caller's tenant, optionally for a single address
+#[utoipa::path(
+    get,
+    path = "/deposits",
+    tag = "deposit",
+    params(DepositQuery),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<DepositsResponse>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+        (status = 403, description = "Missing scope, or the address does not belong to the caller's tenant", body = ErrorBody),
+    )
+)]
+fn get_deposits() {}
+
+/// Token balance of an address
+#[utoipa::path(
+    get,
+    path = "/erc20/balance",
+    tag = "erc20",
+    params(ERC20BalanceRequest),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<BalanceResponse>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+    )
+)]
+fn erc20_get_balance() {}
+
+/// Transfer tokens from a managed key
+#[utoipa::path(
+    post,
+    path = "/erc20/send",
+    tag = "erc20",
+    request_body = ERC20SendTxRequest,
+    security(("bearer" = ["send"])),
+    responses(
+        (status = 200, body = ApiResponse<TransactionHashResponse>),
+        (status = 400, description = "Invalid address or amount", body = ErrorBody),
+        (status = 404, description = "Sender is not a key of the caller's tenant", body = ErrorBody),
+        (status = 409, description = "Nonce conflict", body = ErrorBody),
+        (status = 422, description = "Insufficient funds, fee too high or reverted", body = ErrorBody),
+        (status = 503, description = "Broadcast not acknowledged, the transaction is followed at `/tx/{id}`", body = ErrorBody),
+    )
+)]
+fn erc20_send_transaction() {}
+
+/// Token name, symbol, decimals and total supply
+#[utoipa::path(
+    get,
+    path = "/erc20/info/{contract_address}",
+    tag = "erc20",
+    params(("contract_address" = String, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<TokenInfo>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+    )
+)]
+fn erc20_get_info() {}
+
+/// Start indexing the `Transfer` events of a token
+#[utoipa::path(
+    get,
+    path = "/erc20/listen/{contract_address}",
+    tag = "erc20",
+    params(("contract_address" = String, Path)),
+    security(("bearer" = ["watch"])),
+    responses(
+        (status = 200, body = ApiResponse<ListenResponse>),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+    )
+)]
+fn erc20_listen() {}
+
+/// State of the event listeners of the caller's tenant
+#[utoipa::path(
+    get,
+    path = "/erc20/listeners",
+    tag = "erc20",
+    security(("bearer" = ["read"])),
+    responses((status = 200, body = ApiResponse<Vec<ListenerState>>))
+)]
+fn erc20_get_listeners() {}
+
+/// Index past `Transfer` events of a token in a background job
+#[utoipa::path(
+    post,
+    path = "/erc20/backfill",
+    tag = "erc20",
+    request_body = BackfillRequest,
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<BackfillResponse>),
+        (status = 400, description = "Invalid address or block range", body = ErrorBody),
+    )
+)]
+fn erc20_backfill() {}
+
+/// Progress of a background job
+#[utoipa::path(
+    get,
+    path = "/jobs/{id}",
+    tag = "job",
+    params(("id" = uuid::Uuid, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<JobResponse>),
+        (status = 404, description = "Unknown job", body = ErrorBody),
+    )
+)]
+fn get_job() {}
+
+/// Transactions sent by the caller's tenant, newest first
+#[utoipa::path(
+    get,
+    path = "/tx",
+    tag = "tx",
+    params(TxQuery),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<TransactionsResponse>),
+        (status = 400, description = "Unknown status", body = ErrorBody),
+    )
+)]
+fn list_txs() {}
+
+/// Request, signed payload, fees and status transitions of a sent transaction
+#[utoipa::path(
+    get,
+    path = "/tx/{id}",
+    tag = "tx",
+    params(("id" = uuid::Uuid, Path)),
+    security(("bearer" = ["read"])),
+    responses(
+        (status = 200, body = ApiResponse<OutboundTx>),
+        (status = 400, description = "Invalid id", body = ErrorBody),
+        (status = 404, description = "Unknown transaction, or sent by another tenant", body = ErrorBody),
+    )
+)]
+fn get_tx() {}
+
+/// Health and statistics of every RPC endpoint
+#[utoipa::path(
+    get,
+    path = "/rpc/endpoints",
+    tag = "rpc",
+    security(("bearer" = ["read"])),
+    responses((status = 200, body = ApiResponse<EndpointsResponse>))
+)]
+fn get_endpoints() {}
+
+/// Contracts with a registered ABI
+#[utoipa::path(
+    get,
+    path = "/abi",
+    tag = "abi",
+    security(("bearer" = ["read"])),
+    responses((status = 200, body = ApiResponse<AbiListResponse>))
+)]
+fn list_abis() {}
+
+/// Register the ABI of a contract
+#[utoipa::path(
+    post,
+    path = "/abi",
+    tag = "abi",
+    request_body = RegisterAbiRequest,
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<AddressResponse>),
+        (status = 400, description = "Invalid address or ABI", body = ErrorBody),
+    )
+)]
+fn register_abi() {}
+
+/// Every API key with its scopes and last use
+#[utoipa::path(
+    get,
+    path = "/auth/keys",
+    tag = "auth",
+    security(("bearer" = ["admin"])),
+    responses((status = 200, body = ApiResponse<ApiKeysResponse>))
+)]
+fn list_api_keys() {}
+
+/// Create an API key, the key is only returned in this response
+#[utoipa::path(
+    post,
+    path = "/auth/keys",
+    tag = "auth",
+    request_body = CreateApiKeyRequest,
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<CreatedApiKey>),
+        (status = 400, description = "Invalid name or scopes", body = ErrorBody),
+        (status = 404, description = "Unknown tenant", body = ErrorBody),
+    )
+)]
+fn create_api_key() {}
+
+/// Revoke an API key immediately
+#[utoipa::path(
+    delete,
+    path = "/auth/keys/{id}",
+    tag = "auth",
+    params(("id" = uuid::Uuid, Path)),
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<ApiKey>),
+        (status = 404, description = "Unknown key", body = ErrorBody),
+    )
+)]
+fn revoke_api_key() {}
+
+/// Replace an API key by a new one with the same name and scopes
+#[utoipa::path(
+    post,
+    path = "/auth/keys/{id}/rotate",
+    tag = "auth",
+    params(("id" = uuid::Uuid, Path)),
+    request_body(content = Option<RotateApiKeyRequest>, description = "Optional, the old key is revoked at once by default"),
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<CreatedApiKey>),
+        (status = 400, description = "Key already revoked or expired", body = ErrorBody),
+        (status = 404, description = "Unknown key", body = ErrorBody),
+    )
+)]
+fn rotate_api_key() {}
+
+/// Sessions that haven't expired or been revoked, with their last use
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    tag = "auth",
+    security(("bearer" = ["admin"])),
+    responses((status = 200, body = ApiResponse<SessionsResponse>))
+)]
+fn list_sessions() {}
+
+/// Revoke an operator's session immediately
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    tag = "auth",
+    params(("id" = uuid::Uuid, Path)),
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<AuthSession>),
+        (status = 404, description = "Unknown session", body = ErrorBody),
+    )
+)]
+fn revoke_session() {}
+
+/// Every tenant
+#[utoipa::path(
+    get,
+    path = "/tenants",
+    tag = "tenant",
+    security(("bearer" = ["admin"])),
+    responses((status = 200, body = ApiResponse<TenantsResponse>))
+)]
+fn list_tenants() {}
+
+/// Create a tenant, then bind API keys to it with `tenant_id`
+#[utoipa::path(
+    post,
+    path = "/tenants",
+    tag = "tenant",
+    request_body = CreateTenantRequest,
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<Tenant>),
+        (status = 400, description = "Invalid or taken name", body = ErrorBody),
+    )
+)]
+fn create_tenant() {}
+
+/// Keys, watched addresses and listeners owned by a tenant
+#[utoipa::path(
+    get,
+    path = "/tenants/{id}/assets",
+    tag = "tenant",
+    params(("id" = uuid::Uuid, Path)),
+    security(("bearer" = ["admin"])),
+    responses(
+        (status = 200, body = ApiResponse<TenantAssets>),
+        (status = 404, description = "Unknown tenant", body = ErrorBody),
+    )
+)]
+fn get_tenant_assets() {}
+
+/// Single use nonce for the next Sign-In with Ethereum message
+#[utoipa::path(
+    get,
+    path = "/auth/siwe/nonce",
+    tag = "auth",
+    responses(
+        (status = 200, body = ApiResponse<SiweNonce>),
+        (status = 429, description = RATE_LIMITED, body = ErrorBody),
+        (status = 501, description = "Sign-In with Ethereum is not configured", body = ErrorBody),
+    )
+)]
+fn siwe_nonce() {}
+
+/// Sign in with a signed EIP-4361 message, externally owned or EIP-1271 contract
+/// wallets listed as operators get a session token
+#[utoipa::path(
+    post,
+    path = "/auth/siwe/verify",
+    tag = "auth",
+    request_body = SiweVerifyRequest,
+    responses(
+        (status = 200, body = ApiResponse<SiweSession>),
+        (status = 400, description = "Malformed message or signature", body = ErrorBody),
+        (status = 401, description = "Wrong domain, chain id, nonce or signature, or expired message", body = ErrorBody),
+        (status = 403, description = "Address is not an operator", body = ErrorBody),
+        (status = 429, description = RATE_LIMITED, body = ErrorBody),
+        (status = 501, description = "Sign-In with Ethereum is not configured", body = ErrorBody),
+    )
+)]
+fn siwe_verify() {}
+
+/// End the session whose token authenticates the request. Works with any
+/// scope, API keys are rejected.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, body = ApiResponse<AuthSession>),
+        (status = 400, description = "Credential is an API key", body = ErrorBody),
+    )
+)]
+fn logout() {}
+
+/// Metrics in Prometheus text format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses((status = 200, body = String, content_type = "text/plain"))
+)]
+fn metrics() {}
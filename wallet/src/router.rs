@@ -1,25 +1,146 @@
+use crate::handler::abi_handler::AbiHandler;
+use crate::handler::auth_handler::AuthHandler;
 use crate::handler::block_handler::BlockHandler;
+use crate::handler::deposit_handler::DepositHandler;
 use crate::handler::erc20_handler::ERC20Handler;
 use crate::handler::ether_handler::EtherHandler;
-use crate::handler::healthy_handler::healthy;
+use crate::handler::healthy_handler::{healthy, live, ready};
+use crate::handler::history_handler::HistoryHandler;
+use crate::handler::job_handler::JobHandler;
+use crate::handler::metrics_handler::MetricsHandler;
+use crate::handler::openapi_handler::OpenApiHandler;
+use crate::handler::rpc_handler::RpcHandler;
+use crate::handler::tenant_handler::TenantHandler;
+use crate::handler::tx_handler::TxHandler;
 use crate::handler::wallet_handler::WalletHandler;
+use crate::middleware::auth::authorize;
+use crate::middleware::idempotency::idempotency;
+use crate::middleware::metrics::track_http;
+use crate::middleware::rate_limit::rate_limit;
+use crate::middleware::request_id::request_id;
+use crate::model::api_key::Scope;
 use crate::model::app_model::AppState;
-use axum::{routing::get, routing::post, Router};
+use crate::model::rate_limit::RateClass;
+use crate::openapi::ApiDoc;
+use axum::{middleware, routing::delete, routing::get, routing::post, Router};
 use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_scalar::{Scalar, Servable};
 
 pub fn create_route(app_state: Arc<AppState>) -> Router {
-    let router = Router::new()
+    // Probes, metrics scraping, the API docs and sign-in stay reachable without a key.
+    // Only sign-in is rate limited, per client IP, so probes never see a 429. Logout
+    // checks the session token itself, so a session lacking the read scope can end too.
+    let public = Router::new()
         .route("/health", post(healthy))
+        .route("/live", get(live))
+        .route("/ready", get(ready))
+        .route("/metrics", get(MetricsHandler::metrics))
+        .route("/openapi.json", get(OpenApiHandler::spec))
+        .merge(Scalar::with_url("/docs", ApiDoc::openapi()));
+
+    let sign_in = Router::new()
+        .route("/auth/siwe/nonce", get(AuthHandler::siwe_nonce))
+        .route("/auth/siwe/verify", post(AuthHandler::siwe_verify))
+        .route("/auth/logout", post(AuthHandler::logout))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Read), rate_limit));
+
+    // Each group is authorized first, then counted against the caller's budget. Mutating
+    // groups honor Idempotency-Key, keyed by the caller.
+    let read = Router::new()
         .route("/block/height", get(BlockHandler::get_block_height))
         .route("/block/latest", get(BlockHandler::get_latest_block))
-        .route("/wallet/import", post(WalletHandler::import_private_key))
+        .route("/block/reorgs", get(BlockHandler::get_reorgs))
+        .route("/block/{id}", get(BlockHandler::get_block))
+        .route("/blocks", get(BlockHandler::get_blocks))
         .route("/wallet/balance/{address}", get(EtherHandler::get_balance))
         .route("/wallet/transaction/{tx_hash}", get(EtherHandler::get_transaction))
-        .route("/wallet/send", post(EtherHandler::send_transaction))
+        .route("/wallet/transaction/{tx_hash}/trace", get(EtherHandler::trace_transaction))
+        .route("/wallet/{address}/history", get(HistoryHandler::get_history))
+        .route("/deposits", get(DepositHandler::get_deposits))
         .route("/erc20/balance", get(ERC20Handler::get_balance))
-        .route("/erc20/send", post(ERC20Handler::send_transaction))
         .route("/erc20/info/{contract_address}", get(ERC20Handler::get_info))
+        .route("/erc20/listeners", get(ERC20Handler::get_listeners))
+        .route("/jobs/{id}", get(JobHandler::get_job))
+        .route("/tx", get(TxHandler::list_txs))
+        .route("/tx/{id}", get(TxHandler::get_tx))
+        .route("/rpc/endpoints", get(RpcHandler::get_endpoints))
+        .route("/abi", get(AbiHandler::list))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Read), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Read), authorize));
+
+    let sign = Router::new()
+        .route("/wallet/import", post(WalletHandler::import_private_key))
+        .route("/wallet/import/mnemonic", post(WalletHandler::import_mnemonic))
+        .route("/wallet/keystore/import", post(WalletHandler::import_keystore))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Expensive), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Sign), authorize));
+
+    // The response carries the mnemonic, it must not be kept by the idempotency middleware
+    let create_wallet = Router::new()
+        .route("/wallet/create", post(WalletHandler::create_wallet))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Expensive), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Sign), authorize));
+
+    // Exporting hands out the key itself, only admins may and the response must not be kept either
+    let export_keystore = Router::new()
+        .route("/wallet/keystore/export", post(WalletHandler::export_keystore))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Expensive), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Admin), authorize));
+
+    let send = Router::new()
+        .route("/wallet/send", post(EtherHandler::send_transaction))
+        .route("/erc20/send", post(ERC20Handler::send_transaction))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Expensive), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Send), authorize));
+
+    let watch = Router::new()
+        .route("/wallet/watch", post(DepositHandler::watch_address))
         .route("/erc20/listen/{contract_address}", get(ERC20Handler::listen))
-        .with_state(app_state.clone());
-    router
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Read), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Watch), authorize));
+
+    let backfill = Router::new()
+        .route("/erc20/backfill", post(ERC20Handler::backfill))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Expensive), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Admin), authorize));
+
+    let admin = Router::new()
+        .route("/abi", post(AbiHandler::register))
+        .route("/auth/keys", get(AuthHandler::list_keys))
+        .route("/auth/keys/{id}", delete(AuthHandler::revoke_key))
+        .route("/auth/sessions", get(AuthHandler::list_sessions))
+        .route("/auth/sessions/{id}", delete(AuthHandler::revoke_session))
+        .route("/tenants", get(TenantHandler::list).post(TenantHandler::create))
+        .route("/tenants/{id}/assets", get(TenantHandler::get_assets))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), idempotency))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Read), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Admin), authorize));
+
+    // The responses carry the new API key in plaintext, they must not be kept by the idempotency middleware
+    let issue_keys = Router::new()
+        .route("/auth/keys", post(AuthHandler::create_key))
+        .route("/auth/keys/{id}/rotate", post(AuthHandler::rotate_key))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), RateClass::Read), rate_limit))
+        .route_layer(middleware::from_fn_with_state((app_state.clone(), Scope::Admin), authorize));
+
+    Router::new()
+        .merge(public)
+        .merge(sign_in)
+        .merge(read)
+        .merge(sign)
+        .merge(create_wallet)
+        .merge(export_keystore)
+        .merge(send)
+        .merge(watch)
+        .merge(backfill)
+        .merge(admin)
+        .merge(issue_keys)
+        .route_layer(middleware::from_fn(track_http))
+        .layer(middleware::from_fn(request_id))
+        .with_state(app_state.clone())
 }
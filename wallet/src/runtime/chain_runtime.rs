@@ -33,7 +33,7 @@ impl ChainRuntime {
     }
 
     /// Get balance for an address
-    pub async fn get_balance(&self, address: &Address) -> Result<Balance> {
+    pub async fn get_balance(&self, address: &Address) -> Result<Amount> {
         let adapter = self.get_adapter(address.chain_id)?;
         adapter.get_balance(address).await
     }
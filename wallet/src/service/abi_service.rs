@@ -0,0 +1,37 @@
+use crate::error::parse_address;
+use crate::model::abi_registry::AbiRegistry;
+use anyhow::Result;
+use ethers::abi::{parse_abi, Abi};
+use ethers::types::Address;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+pub struct AbiService<'a> {
+    abis: &'a RwLock<AbiRegistry>,
+}
+
+impl<'a> AbiService<'a> {
+    pub fn new(abis: &'a RwLock<AbiRegistry>) -> Result<Self> {
+        Ok(Self { abis })
+    }
+
+    /// Register the ABI of a contract, given as JSON ABI or as a list of
+    /// human readable signatures like `function transfer(address to, uint256 amount)`
+    pub async fn register(&self, address: &str, abi: Value) -> Result<Address> {
+        let address = parse_address(address)?;
+        let abi = match abi {
+            Value::Array(items) if items.iter().all(Value::is_string) => {
+                let signatures: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+                parse_abi(&signatures)?
+            }
+            Value::String(json) => serde_json::from_str::<Abi>(&json)?,
+            other => serde_json::from_value::<Abi>(other)?,
+        };
+        self.abis.write().await.register(address, abi);
+        Ok(address)
+    }
+
+    pub async fn list(&self) -> Result<Vec<Address>> {
+        Ok(self.abis.read().await.registered())
+    }
+}
@@ -0,0 +1,539 @@
+use crate::config::server_config::AuthConfig;
+use crate::error::{ApiError, ErrorCode};
+use crate::model::api_key::{ApiKey, AuthSession, CreatedApiKey, Principal, Scope, Subject};
+use crate::model::rate_limit::RateLimitOverride;
+use crate::service::tenant_service::TenantService;
+use anyhow::Result;
+use ethers::types::Address;
+use ethers::utils::{hex, to_checksum};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, MySql, Pool};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Marks wallet API keys in logs and secret scanners
+const KEY_PREFIX: &str = "wk_";
+/// Marks SIWE session tokens, which are looked up in `auth_sessions`
+const SESSION_PREFIX: &str = "ws_";
+/// Characters of the key stored in clear, prefix included
+const DISPLAY_PREFIX_LEN: usize = 11;
+/// `last_used_at` is only written once per interval instead of on every request
+const TOUCH_INTERVAL: u64 = 60;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(FromRow)]
+struct ApiKeyRow {
+    id: String,
+    name: String,
+    prefix: String,
+    tenant_id: Option<String>,
+    scopes: String,
+    read_rate_limit: Option<u32>,
+    expensive_rate_limit: Option<u32>,
+    created_at: u64,
+    expires_at: Option<u64>,
+    revoked_at: Option<u64>,
+    rotated_to: Option<String>,
+    last_used_at: Option<u64>,
+    last_used_ip: Option<String>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: ApiKeyRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id.parse()?,
+            name: row.name,
+            prefix: row.prefix,
+            tenant_id: row.tenant_id.map(|id| id.parse()).transpose()?,
+            scopes: parse_scopes(&row.scopes)?,
+            rate_limit: RateLimitOverride {
+                read: row.read_rate_limit,
+                expensive: row.expensive_rate_limit,
+            },
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            revoked_at: row.revoked_at,
+            rotated_to: row.rotated_to.map(|id| id.parse()).transpose()?,
+            last_used_at: row.last_used_at,
+            last_used_ip: row.last_used_ip,
+        })
+    }
+}
+
+const SELECT_KEY: &str = "SELECT id, name, prefix, tenant_id, scopes, read_rate_limit, expensive_rate_limit, created_at, expires_at, revoked_at, rotated_to, \
+    last_used_at, last_used_ip FROM api_keys";
+
+#[derive(FromRow)]
+struct SessionRow {
+    id: String,
+    address: String,
+    scopes: String,
+    created_at: u64,
+    expires_at: u64,
+    revoked_at: Option<u64>,
+    last_used_at: Option<u64>,
+    last_used_ip: Option<String>,
+}
+
+impl TryFrom<SessionRow> for AuthSession {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SessionRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id.parse()?,
+            address: row.address,
+            scopes: parse_scopes(&row.scopes)?,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            revoked_at: row.revoked_at,
+            last_used_at: row.last_used_at,
+            last_used_ip: row.last_used_ip,
+        })
+    }
+}
+
+const SELECT_SESSION: &str =
+    "SELECT id, address, scopes, created_at, expires_at, revoked_at, last_used_at, last_used_ip FROM auth_sessions";
+
+/// API keys stored in `api_keys` and SIWE sessions in `auth_sessions`. Both are
+/// 32 random bytes, so a plain SHA-256 is enough to store them: unlike
+/// passwords they can't be brute forced.
+pub struct AuthService<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl<'a> AuthService<'a> {
+    pub fn new(db: &'a Pool<MySql>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Create a key, bound to `tenant_id` if given. Tenant keys can't hold the
+    /// admin scope, which manages every tenant.
+    pub async fn create_key(
+        &self,
+        name: &str,
+        scopes: &[Scope],
+        expires_in: Option<u64>,
+        tenant_id: Option<Uuid>,
+        rate_limit: RateLimitOverride,
+    ) -> Result<CreatedApiKey> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(ApiError::invalid_request(format!("name must be 1 to {} characters", MAX_NAME_LEN)).into());
+        }
+        if scopes.is_empty() {
+            return Err(ApiError::invalid_request("at least one scope is required").into());
+        }
+        if let Some(tenant_id) = tenant_id {
+            if scopes.contains(&Scope::Admin) {
+                return Err(ApiError::invalid_request("tenant keys can't have the admin scope").into());
+            }
+            TenantService::new(self.db)?.get_tenant(tenant_id).await?;
+        }
+
+        let mut tx = self.db.begin().await?;
+        let expires_at = expires_in.map(|secs| unix_now() + secs);
+        let created = insert_key(&mut tx, name, tenant_id, scopes, rate_limit, expires_at).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    pub async fn list_keys(&self) -> Result<Vec<ApiKey>> {
+        let rows: Vec<ApiKeyRow> = sqlx::query_as(&format!("{} ORDER BY created_at, id", SELECT_KEY))
+            .fetch_all(self.db)
+            .await?;
+        rows.into_iter().map(ApiKey::try_from).collect()
+    }
+
+    /// Issue a new key with the same name and scopes. The old key keeps working
+    /// for `grace_period` seconds so clients can switch over, 0 revokes it at once.
+    pub async fn rotate_key(&self, id: &str, grace_period: u64) -> Result<CreatedApiKey> {
+        let old = self.get_key(id).await?;
+        let now = unix_now();
+        if !old.is_active(now) {
+            return Err(ApiError::invalid_request(format!("API key {} is revoked or expired", old.id)).into());
+        }
+
+        let mut tx = self.db.begin().await?;
+        let created = insert_key(&mut tx, &old.name, old.tenant_id, &old.scopes, old.rate_limit, old.expires_at).await?;
+        if let Some(expires_at) = grace_expiry(old.expires_at, now, grace_period) {
+            sqlx::query("UPDATE api_keys SET expires_at = ?, rotated_to = ? WHERE id = ?")
+                .bind(expires_at)
+                .bind(created.api_key.id.to_string())
+                .bind(old.id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        } else {
+            sqlx::query("UPDATE api_keys SET revoked_at = ?, rotated_to = ? WHERE id = ?")
+                .bind(now)
+                .bind(created.api_key.id.to_string())
+                .bind(old.id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    pub async fn revoke_key(&self, id: &str) -> Result<ApiKey> {
+        let key = self.get_key(id).await?;
+        sqlx::query("UPDATE api_keys SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ?")
+            .bind(unix_now())
+            .bind(key.id.to_string())
+            .execute(self.db)
+            .await?;
+        self.get_key(id).await
+    }
+
+    /// Resolve the caller of a request from its API key or session token and record the use.
+    /// Sessions are checked against the current `auth.operators`.
+    pub async fn authenticate(&self, token: &str, ip: Option<IpAddr>, config: &AuthConfig) -> Result<Principal> {
+        if credential(token) == Credential::Session {
+            return self.authenticate_session(token, ip, config).await;
+        }
+
+        let row: Option<ApiKeyRow> = sqlx::query_as(&format!("{} WHERE key_hash = ?", SELECT_KEY))
+            .bind(hash_token(token))
+            .fetch_optional(self.db)
+            .await?;
+        let key = match row {
+            Some(row) => ApiKey::try_from(row)?,
+            None => return Err(ApiError::new(ErrorCode::Unauthorized, "invalid API key").into()),
+        };
+
+        let now = unix_now();
+        if !key.is_active(now) {
+            return Err(ApiError::new(ErrorCode::Unauthorized, "API key is revoked or expired").into());
+        }
+        if key.last_used_at.is_none_or(|at| at + TOUCH_INTERVAL <= now) {
+            self.touch("api_keys", &key.id.to_string(), now, ip).await;
+        }
+
+        Ok(Principal {
+            subject: Subject::ApiKey(key.id),
+            tenant: key.tenant_id,
+            scopes: key.scopes,
+            rate_limit: key.rate_limit,
+        })
+    }
+
+    /// Open a session for an operator signed in with Ethereum, returns the token
+    pub async fn create_session(&self, address: Address, scopes: &[Scope], expires_at: u64) -> Result<String> {
+        let token = generate_token(SESSION_PREFIX);
+        sqlx::query(
+            "INSERT INTO auth_sessions (id, token_hash, address, scopes, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(hash_token(&token))
+        .bind(to_checksum(&address, None))
+        .bind(format_scopes(scopes))
+        .bind(unix_now())
+        .bind(expires_at)
+        .execute(self.db)
+        .await?;
+        Ok(token)
+    }
+
+    /// Sessions that haven't expired or been revoked, most recent first
+    pub async fn list_sessions(&self) -> Result<Vec<AuthSession>> {
+        let rows: Vec<SessionRow> =
+            sqlx::query_as(&format!("{} WHERE revoked_at IS NULL AND expires_at > ? ORDER BY created_at DESC", SELECT_SESSION))
+                .bind(unix_now())
+                .fetch_all(self.db)
+                .await?;
+        rows.into_iter().map(AuthSession::try_from).collect()
+    }
+
+    pub async fn revoke_session(&self, id: &str) -> Result<AuthSession> {
+        let id = id
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::invalid_request(format!("invalid session id {}", id)))?;
+        sqlx::query("UPDATE auth_sessions SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ?")
+            .bind(unix_now())
+            .bind(id.to_string())
+            .execute(self.db)
+            .await?;
+        let row: Option<SessionRow> = sqlx::query_as(&format!("{} WHERE id = ?", SELECT_SESSION))
+            .bind(id.to_string())
+            .fetch_optional(self.db)
+            .await?;
+        match row {
+            Some(row) => row.try_into(),
+            None => Err(ApiError::not_found(format!("session {} not found", id)).into()),
+        }
+    }
+
+    /// Revoke the session a token belongs to. API keys are revoked by an admin instead.
+    pub async fn logout(&self, token: &str) -> Result<AuthSession> {
+        if credential(token) != Credential::Session {
+            return Err(ApiError::invalid_request("only session tokens can log out, API keys are revoked by an admin").into());
+        }
+        let session = self.find_session(token).await?;
+        if !session.is_active(unix_now()) {
+            return Err(ApiError::new(ErrorCode::Unauthorized, "session is revoked or expired").into());
+        }
+        self.revoke_session(&session.id.to_string()).await
+    }
+
+    async fn authenticate_session(&self, token: &str, ip: Option<IpAddr>, config: &AuthConfig) -> Result<Principal> {
+        let session = self.find_session(token).await?;
+        let now = unix_now();
+        if !session.is_active(now) {
+            return Err(ApiError::new(ErrorCode::Unauthorized, "session is revoked or expired").into());
+        }
+
+        // Removing an operator or narrowing its scopes applies to the sessions it already holds
+        let address: Address = session.address.parse()?;
+        let granted = config
+            .operator_scopes(address)
+            .ok_or_else(|| ApiError::new(ErrorCode::Unauthorized, format!("{} is no longer an operator", session.address)))?;
+        let scopes = session_scopes(&session.scopes, granted);
+        if scopes.is_empty() {
+            return Err(ApiError::new(ErrorCode::Unauthorized, "operator no longer holds the scopes of this session").into());
+        }
+
+        if session.last_used_at.is_none_or(|at| at + TOUCH_INTERVAL <= now) {
+            self.touch("auth_sessions", &session.id.to_string(), now, ip).await;
+        }
+
+        Ok(Principal {
+            subject: Subject::Session { id: session.id, address },
+            // Operators act for the platform
+            tenant: None,
+            scopes,
+            rate_limit: RateLimitOverride::default(),
+        })
+    }
+
+    async fn find_session(&self, token: &str) -> Result<AuthSession> {
+        let row: Option<SessionRow> = sqlx::query_as(&format!("{} WHERE token_hash = ?", SELECT_SESSION))
+            .bind(hash_token(token))
+            .fetch_optional(self.db)
+            .await?;
+        match row {
+            Some(row) => row.try_into(),
+            None => Err(ApiError::new(ErrorCode::Unauthorized, "invalid session token").into()),
+        }
+    }
+
+    /// Record the last use of a key or session. Bookkeeping only, a failed
+    /// write must not reject the request.
+    async fn touch(&self, table: &str, id: &str, now: u64, ip: Option<IpAddr>) {
+        let result = sqlx::query(&format!("UPDATE {} SET last_used_at = ?, last_used_ip = ? WHERE id = ?", table))
+            .bind(now)
+            .bind(ip.map(|ip| ip.to_string()))
+            .bind(id)
+            .execute(self.db)
+            .await;
+        if let Err(e) = result {
+            warn!(table, id, error = %e, "failed to record credential use");
+        }
+    }
+
+    async fn get_key(&self, id: &str) -> Result<ApiKey> {
+        let id = id
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::invalid_request(format!("invalid API key id {}", id)))?;
+        let row: Option<ApiKeyRow> = sqlx::query_as(&format!("{} WHERE id = ?", SELECT_KEY))
+            .bind(id.to_string())
+            .fetch_optional(self.db)
+            .await?;
+        match row {
+            Some(row) => row.try_into(),
+            None => Err(ApiError::not_found(format!("API key {} not found", id)).into()),
+        }
+    }
+}
+
+async fn insert_key(
+    tx: &mut sqlx::Transaction<'_, MySql>,
+    name: &str,
+    tenant_id: Option<Uuid>,
+    scopes: &[Scope],
+    rate_limit: RateLimitOverride,
+    expires_at: Option<u64>,
+) -> Result<CreatedApiKey> {
+    let key = generate_token(KEY_PREFIX);
+
+    let mut scopes = scopes.to_vec();
+    scopes.sort_by_key(|scope| *scope as u8);
+    scopes.dedup();
+
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+        tenant_id,
+        scopes,
+        rate_limit,
+        created_at: unix_now(),
+        expires_at,
+        revoked_at: None,
+        rotated_to: None,
+        last_used_at: None,
+        last_used_ip: None,
+    };
+    sqlx::query(
+        "INSERT INTO api_keys (id, name, prefix, tenant_id, key_hash, scopes, read_rate_limit, expensive_rate_limit, \
+         created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(api_key.id.to_string())
+    .bind(&api_key.name)
+    .bind(&api_key.prefix)
+    .bind(api_key.tenant_id.map(|id| id.to_string()))
+    .bind(hash_token(&key))
+    .bind(format_scopes(&api_key.scopes))
+    .bind(api_key.rate_limit.read)
+    .bind(api_key.rate_limit.expensive)
+    .bind(api_key.created_at)
+    .bind(api_key.expires_at)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(CreatedApiKey { key, api_key })
+}
+
+/// Kind of credential a bearer token is, told apart by its prefix
+#[derive(Debug, PartialEq, Eq)]
+enum Credential {
+    ApiKey,
+    Session,
+}
+
+/// Tokens without the session prefix are looked up as API keys, unknown ones simply don't match
+fn credential(token: &str) -> Credential {
+    if token.starts_with(SESSION_PREFIX) {
+        Credential::Session
+    } else {
+        Credential::ApiKey
+    }
+}
+
+/// Expiry of a key rotated at `now`: it keeps working for `grace_period` seconds
+/// but never past its own expiry. `None` when it must be revoked at once.
+fn grace_expiry(expires_at: Option<u64>, now: u64, grace_period: u64) -> Option<u64> {
+    if grace_period == 0 {
+        return None;
+    }
+    let end = now.saturating_add(grace_period);
+    Some(expires_at.map_or(end, |at| at.min(end)))
+}
+
+/// Scopes of a session its operator still holds, admin holding every scope
+fn session_scopes(session: &[Scope], granted: &[Scope]) -> Vec<Scope> {
+    session
+        .iter()
+        .copied()
+        .filter(|scope| granted.iter().any(|held| held == scope || *held == Scope::Admin))
+        .collect()
+}
+
+fn generate_token(prefix: &str) -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    format!("{}{}", prefix, hex::encode(secret))
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn format_scopes(scopes: &[Scope]) -> String {
+    scopes.iter().map(Scope::as_str).collect::<Vec<_>>().join(",")
+}
+
+fn parse_scopes(scopes: &str) -> Result<Vec<Scope>> {
+    Ok(scopes
+        .split(',')
+        .filter(|scope| !scope.is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()?)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn tokens_are_stored_as_sha256() {
+        assert_eq!(hash_token("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let token = generate_token(KEY_PREFIX);
+        assert_eq!(token.len(), KEY_PREFIX.len() + 64);
+        assert!(token[KEY_PREFIX.len()..].chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(hash_token(&token).len(), 64);
+        assert_ne!(token, generate_token(KEY_PREFIX));
+    }
+
+    #[test]
+    fn tokens_dispatch_on_their_prefix() {
+        assert_eq!(credential(&generate_token(KEY_PREFIX)), Credential::ApiKey);
+        assert_eq!(credential(&generate_token(SESSION_PREFIX)), Credential::Session);
+        assert_eq!(credential("ws_"), Credential::Session);
+        // unprefixed and look-alike tokens are looked up as keys and don't match
+        assert_eq!(credential("deadbeef"), Credential::ApiKey);
+        assert_eq!(credential("WS_deadbeef"), Credential::ApiKey);
+        assert_eq!(credential("wk_ws_deadbeef"), Credential::ApiKey);
+    }
+
+    #[test]
+    fn rotated_keys_expire_after_the_grace_period() {
+        let now = 1_000;
+        assert_eq!(grace_expiry(None, now, 0), None);
+        assert_eq!(grace_expiry(Some(5_000), now, 0), None);
+        assert_eq!(grace_expiry(None, now, 300), Some(1_300));
+        // a key never outlives its own expiry
+        assert_eq!(grace_expiry(Some(1_100), now, 300), Some(1_100));
+        assert_eq!(grace_expiry(Some(5_000), now, 300), Some(1_300));
+        assert_eq!(grace_expiry(None, now, u64::MAX), Some(u64::MAX));
+
+        let key = ApiKey {
+            id: Uuid::new_v4(),
+            name: "ci".to_string(),
+            prefix: "wk_0123abcd".to_string(),
+            tenant_id: None,
+            scopes: vec![Scope::Read],
+            rate_limit: RateLimitOverride::default(),
+            created_at: 0,
+            expires_at: grace_expiry(None, now, 300),
+            revoked_at: None,
+            rotated_to: Some(Uuid::new_v4()),
+            last_used_at: None,
+            last_used_ip: None,
+        };
+        assert!(key.is_active(1_299));
+        assert!(!key.is_active(1_300));
+    }
+
+    #[test]
+    fn sessions_keep_the_scopes_their_operator_still_holds() {
+        let operator = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+        let config = AuthConfig {
+            operators: HashMap::from([(operator.to_string(), vec![Scope::Read, Scope::Watch])]),
+            ..Default::default()
+        };
+        // operators are matched by address, not by spelling
+        let address: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".parse().unwrap();
+        let granted = config.operator_scopes(address).unwrap();
+        assert_eq!(session_scopes(&[Scope::Read, Scope::Send, Scope::Watch], granted), vec![Scope::Read, Scope::Watch]);
+        assert!(session_scopes(&[Scope::Send, Scope::Admin], granted).is_empty());
+        assert!(config.operator_scopes(Address::zero()).is_none());
+
+        // admin operators keep every scope of their sessions, the others lose admin
+        assert_eq!(session_scopes(&[Scope::Send, Scope::Admin], &[Scope::Admin]), vec![Scope::Send, Scope::Admin]);
+        assert_eq!(session_scopes(&[Scope::Admin], &[Scope::Read, Scope::Sign, Scope::Send, Scope::Watch]), vec![]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::chain::eth::RpcPool;
+use crate::model::keyring::Keyring;
+use crate::model::watchlist::WatchList;
+use crate::service::erc20_service::{token_asset, ERC20};
+use crate::service::trace_service::{InternalTransfer, TraceService};
+use crate::types::Asset;
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Block, Transaction, H256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
+
+/// Blocks kept after being fetched, enough for both consumers to be a full tick apart
+const MAX_CACHED_BLOCKS: usize = 256;
+
+/// Blocks with their transactions and internal transfers, fetched once and shared
+/// by the deposit scanner and the history indexer since both walk the same new
+/// blocks. Entries of orphaned blocks are dropped by the block monitor when it
+/// detects a reorg.
+pub struct BlockFeed {
+    eth_provider: Provider<RpcPool>,
+    blocks: Mutex<BTreeMap<u64, Arc<Block<Transaction>>>>,
+    traces: Mutex<BTreeMap<u64, Arc<Vec<InternalTransfer>>>>,
+    /// Cleared once the node turns out not to support debug tracing
+    trace_internal: AtomicBool,
+    /// Symbol and decimals per token, `None` for contracts that don't expose them
+    tokens: Mutex<HashMap<Address, Option<Asset>>>,
+}
+
+impl BlockFeed {
+    pub fn new(eth: Provider<RpcPool>, trace_internal: bool) -> Self {
+        Self {
+            eth_provider: eth,
+            blocks: Mutex::new(BTreeMap::new()),
+            traces: Mutex::new(BTreeMap::new()),
+            trace_internal: AtomicBool::new(trace_internal),
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn provider(&self) -> &Provider<RpcPool> {
+        &self.eth_provider
+    }
+
+    /// Block `number` with full transactions, `None` if the node doesn't have it yet.
+    /// The cache stays locked while fetching so a block is never requested twice.
+    pub async fn block(&self, number: u64) -> Result<Option<Arc<Block<Transaction>>>> {
+        let mut blocks = self.blocks.lock().await;
+        if let Some(block) = blocks.get(&number) {
+            return Ok(Some(block.clone()));
+        }
+        let Some(block) = self.eth_provider.get_block_with_txs(number).await? else {
+            return Ok(None);
+        };
+
+        let block = Arc::new(block);
+        blocks.insert(number, block.clone());
+        while blocks.len() > MAX_CACHED_BLOCKS {
+            blocks.pop_first();
+        }
+        Ok(Some(block))
+    }
+
+    /// Internal transfers of `block`, `None` when tracing is off or the node can't
+    /// trace. Other tracing failures are returned so the caller retries the block.
+    pub async fn internal_transfers(&self, block: &Block<Transaction>) -> Result<Option<Arc<Vec<InternalTransfer>>>> {
+        if !self.trace_internal.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let number = block.number.unwrap_or_default().as_u64();
+        let mut traces = self.traces.lock().await;
+        if let Some(transfers) = traces.get(&number) {
+            return Ok(Some(transfers.clone()));
+        }
+
+        let tx_hashes: Vec<H256> = block.transactions.iter().map(|tx| tx.hash).collect();
+        let Some(transfers) = TraceService::new(&self.eth_provider)?.trace_block(number, &tx_hashes).await? else {
+            if self.trace_internal.swap(false, Ordering::Relaxed) {
+                warn!("node doesn't support debug tracing, internal transfers won't be detected");
+            }
+            return Ok(None);
+        };
+
+        let transfers = Arc::new(transfers);
+        traces.insert(number, transfers.clone());
+        while traces.len() > MAX_CACHED_BLOCKS {
+            traces.pop_first();
+        }
+        Ok(Some(transfers))
+    }
+
+    /// Asset of the token at `contract`, looked up once
+    pub async fn token(&self, contract: Address) -> Option<Asset> {
+        if let Some(asset) = self.tokens.lock().await.get(&contract) {
+            return asset.clone();
+        }
+        let asset = token_asset(&ERC20::new(contract, Arc::new(self.eth_provider.clone()))).await.ok();
+        self.tokens.lock().await.insert(contract, asset.clone());
+        asset
+    }
+
+    /// Forget blocks at or above `from_block`, they were replaced by another branch
+    pub async fn invalidate(&self, from_block: u64) {
+        self.blocks.lock().await.split_off(&from_block);
+        self.traces.lock().await.split_off(&from_block);
+    }
+}
+
+/// Keyring and watch-only addresses, the ones deposits and history are recorded for
+pub(crate) async fn managed_addresses(keyring: &RwLock<Keyring>, watched: &RwLock<WatchList>) -> HashSet<Address> {
+    let mut addresses: HashSet<Address> = keyring.read().await.addresses().into_iter().collect();
+    addresses.extend(watched.read().await.addresses());
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use serde_json::json;
+
+    const TRACE_METHOD: &str = "debug_traceBlockByNumber";
+
+    #[tokio::test]
+    async fn traces_are_shared_and_only_disabled_by_a_missing_method() {
+        let node = MockNode::start().await;
+        node.chain().mine(2);
+        let feed = BlockFeed::new(node.provider(), true);
+        let one = feed.block(1).await.unwrap().unwrap();
+        let two = feed.block(2).await.unwrap().unwrap();
+
+        node.chain().responses.insert(TRACE_METHOD.into(), Err((-32000, "historical state is not available".into())));
+        assert!(feed.internal_transfers(&one).await.is_err());
+
+        node.chain().responses.insert(TRACE_METHOD.into(), Ok(json!([])));
+        assert!(feed.internal_transfers(&one).await.unwrap().is_some());
+
+        // The second consumer gets the cached trace even though the node now fails
+        node.chain().responses.insert(TRACE_METHOD.into(), Err((-32000, "request timed out".into())));
+        assert!(feed.internal_transfers(&one).await.unwrap().is_some());
+
+        node.chain().responses.insert(TRACE_METHOD.into(), Err((-32601, "method not found".into())));
+        assert!(feed.internal_transfers(&two).await.unwrap().is_none());
+        node.chain().responses.insert(TRACE_METHOD.into(), Ok(json!([])));
+        assert!(feed.internal_transfers(&two).await.unwrap().is_none());
+    }
+}
@@ -1,14 +1,107 @@
-use anyhow::{anyhow, Result};
-use ethers::providers::{Http, Middleware, Provider};
-use ethers::types::{Block, BlockNumber, H256};
+use crate::chain::eth::RpcPool;
+use crate::error::ApiError;
+use crate::model::block_tracker::{BlockTracker, Reorg, TrackedBlock};
+use crate::model::deposit::DepositStore;
+use crate::model::event_store::EventStore;
+use crate::model::heartbeat::Heartbeats;
+use crate::model::listener::ListenerRegistry;
+use crate::service::block_feed::BlockFeed;
+use crate::service::erc20_service::index_transfers;
+use anyhow::Result;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Block, BlockId, BlockNumber, Transaction, TransactionReceipt, H256, U256};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{error, info, info_span, warn, Instrument};
+use utoipa::ToSchema;
+
+/// Upper bound of blocks fetched per monitor tick
+const MAX_BLOCKS_PER_TICK: u64 = 100;
+/// Default and maximum page size of block range queries
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+/// Blocks fetched in parallel when serving a range
+const RANGE_CONCURRENCY: usize = 10;
+
+/// Condensed view of a block header
+#[derive(Serialize, ToSchema)]
+pub struct BlockSummary {
+    pub number: Option<u64>,
+    #[schema(value_type = Option<String>)]
+    pub hash: Option<H256>,
+    #[schema(value_type = String)]
+    pub parent_hash: H256,
+    pub timestamp: u64,
+    #[schema(value_type = Option<String>)]
+    pub miner: Option<Address>,
+    pub tx_count: usize,
+    #[schema(value_type = String)]
+    pub gas_used: U256,
+    #[schema(value_type = String)]
+    pub gas_limit: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub base_fee_per_gas: Option<U256>,
+}
+
+impl<TX> From<&Block<TX>> for BlockSummary {
+    fn from(block: &Block<TX>) -> Self {
+        Self {
+            number: block.number.map(|n| n.as_u64()),
+            hash: block.hash,
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp.as_u64(),
+            miner: block.author,
+            tx_count: block.transactions.len(),
+            gas_used: block.gas_used,
+            gas_limit: block.gas_limit,
+            base_fee_per_gas: block.base_fee_per_gas,
+        }
+    }
+}
+
+/// Transaction hashes, or full transactions when requested with `full=true`
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum BlockTransactions {
+    #[schema(value_type = Vec<String>)]
+    Hashes(Vec<H256>),
+    #[schema(value_type = Vec<Object>)]
+    Full(Vec<Transaction>),
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockDetail {
+    #[serde(flatten)]
+    pub summary: BlockSummary,
+    pub transactions: BlockTransactions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<Object>>)]
+    pub receipts: Option<Vec<TransactionReceipt>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockPage {
+    pub blocks: Vec<BlockSummary>,
+    /// First block of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<u64>,
+}
 
 pub struct BlockService<'a> {
-    eth_provider: &'a Provider<Http>,
+    eth_provider: &'a Provider<RpcPool>,
+    tracker: &'a RwLock<BlockTracker>,
 }
 
 impl<'a> BlockService<'a> {
-    pub fn new(eth: &'a Provider<Http>) -> Result<Self> {
-        Ok(Self { eth_provider: eth })
+    pub fn new(eth: &'a Provider<RpcPool>, tracker: &'a RwLock<BlockTracker>) -> Result<Self> {
+        Ok(Self {
+            eth_provider: eth,
+            tracker,
+        })
     }
 
     pub async fn get_block_height(&self) -> Result<u64> {
@@ -18,6 +111,465 @@ impl<'a> BlockService<'a> {
 
     pub async fn get_latest_block(&self) -> Result<Block<H256>> {
         let latest_block = self.eth_provider.get_block(BlockNumber::Latest).await?;
-        latest_block.ok_or_else(|| anyhow!("latest block not found"))
+        latest_block.ok_or_else(|| ApiError::not_found("latest block not found").into())
+    }
+
+    /// Look up a block by number, hash or tag (`latest`, `safe`, `finalized`, `earliest`, `pending`)
+    pub async fn get_block(&self, id: &str, full: bool, receipts: bool) -> Result<BlockDetail> {
+        let block_id = id
+            .parse::<BlockId>()
+            .map_err(|e| ApiError::invalid_request(format!("invalid block id {}: {}", id, e)))?;
+
+        let (summary, transactions, hashes) = if full {
+            let block = self
+                .eth_provider
+                .get_block_with_txs(block_id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(format!("block {} not found", id)))?;
+            let hashes = block.transactions.iter().map(|tx| tx.hash).collect::<Vec<_>>();
+            (BlockSummary::from(&block), BlockTransactions::Full(block.transactions), hashes)
+        } else {
+            let block = self
+                .eth_provider
+                .get_block(block_id)
+                .await?
+                .ok_or_else(|| ApiError::not_found(format!("block {} not found", id)))?;
+            let hashes = block.transactions.clone();
+            (BlockSummary::from(&block), BlockTransactions::Hashes(block.transactions), hashes)
+        };
+
+        let receipts = match (receipts, summary.hash) {
+            (true, Some(hash)) => Some(self.get_receipts(hash, &hashes).await?),
+            _ => None,
+        };
+
+        Ok(BlockDetail {
+            summary,
+            transactions,
+            receipts,
+        })
+    }
+
+    /// Summaries of `from..=to` (`to` defaults to the chain head), one page at a time
+    pub async fn get_blocks(&self, from: u64, to: Option<u64>, limit: Option<u64>) -> Result<BlockPage> {
+        let to = match to {
+            Some(to) => to,
+            None => self.get_block_height().await?,
+        };
+        if from > to {
+            return Err(ApiError::invalid_request("from must not be greater than to").into());
+        }
+
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let page_end = to.min(from.saturating_add(limit - 1));
+
+        let blocks = stream::iter(from..=page_end)
+            .map(|number| self.eth_provider.get_block(number))
+            .buffered(RANGE_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?
+            .iter()
+            .flatten()
+            .map(BlockSummary::from)
+            .collect();
+
+        Ok(BlockPage {
+            blocks,
+            next: page_end.checked_add(1).filter(|_| page_end < to),
+        })
+    }
+
+    /// Receipts of the block with hash `block_hash`, not whatever block holds its
+    /// number by now. Prefer `eth_getBlockReceipts`, fall back to one
+    /// `eth_getTransactionReceipt` per transaction on nodes that don't support it.
+    async fn get_receipts(&self, block_hash: H256, hashes: &[H256]) -> Result<Vec<TransactionReceipt>> {
+        if let Ok(receipts) = self
+            .eth_provider
+            .request::<_, Vec<TransactionReceipt>>("eth_getBlockReceipts", [block_hash])
+            .await
+        {
+            return Ok(receipts);
+        }
+
+        let receipts = future::try_join_all(
+            hashes.iter().map(|hash| self.eth_provider.get_transaction_receipt(*hash)),
+        )
+        .await?;
+        // a transaction of an orphaned block may be included again in another one
+        if receipts.iter().flatten().any(|receipt| receipt.block_hash != Some(block_hash)) {
+            return Err(ApiError::not_found(format!(
+                "block {:?} was replaced by a reorg while fetching its receipts",
+                block_hash
+            ))
+            .into());
+        }
+        Ok(receipts.into_iter().flatten().collect())
+    }
+
+    pub async fn get_reorgs(&self) -> Result<Vec<Reorg>> {
+        Ok(self.tracker.read().await.recent_reorgs())
+    }
+}
+
+/// Background task following the canonical chain. A reorg is detected when a new
+/// block's parent hash doesn't match the tracked hash of its predecessor; the
+/// orphaned range is then rolled back from the event and deposit stores,
+/// re-indexed and broadcast to subscribers.
+///
+/// Locally this can be exercised against anvil with `evm_snapshot`/`evm_revert`
+/// followed by mining new blocks, or directly with `anvil_reorg`; the tests replay
+/// shallow and deep reorgs against a mock node.
+pub struct BlockMonitor {
+    eth_provider: Provider<RpcPool>,
+    feed: Arc<BlockFeed>,
+    tracker: Arc<RwLock<BlockTracker>>,
+    events: Arc<RwLock<EventStore>>,
+    deposits: Arc<RwLock<DepositStore>>,
+    listeners: Arc<RwLock<ListenerRegistry>>,
+    reorgs: broadcast::Sender<Reorg>,
+    poll_interval: Duration,
+    /// First block whose events were rolled back and not indexed again yet,
+    /// retried on every tick until re-indexing succeeds
+    pending_reindex: Mutex<Option<u64>>,
+}
+
+impl BlockMonitor {
+    pub fn new(
+        feed: Arc<BlockFeed>,
+        tracker: Arc<RwLock<BlockTracker>>,
+        events: Arc<RwLock<EventStore>>,
+        deposits: Arc<RwLock<DepositStore>>,
+        listeners: Arc<RwLock<ListenerRegistry>>,
+        reorgs: broadcast::Sender<Reorg>,
+        poll_interval: u64,
+    ) -> Self {
+        Self {
+            eth_provider: feed.provider().clone(),
+            feed,
+            tracker,
+            events,
+            deposits,
+            listeners,
+            reorgs,
+            poll_interval: Duration::from_secs(poll_interval),
+            pending_reindex: Mutex::new(None),
+        }
+    }
+
+    pub fn spawn(self, heartbeats: Arc<Heartbeats>) {
+        heartbeats.register("block_monitor", self.poll_interval);
+        tokio::spawn(
+            async move {
+                info!("block monitor started");
+                loop {
+                    let result = self.tick().await;
+                    if let Err(e) = &result {
+                        warn!(error = %e, "block monitor tick failed");
+                    }
+                    heartbeats.record("block_monitor", &result);
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }
+            .instrument(info_span!("block_monitor")),
+        );
+    }
+
+    async fn tick(&self) -> Result<()> {
+        let head = self.eth_provider.get_block_number().await?.as_u64();
+        self.reindex(head).await?;
+        let start = match self.tracker.read().await.head() {
+            Some(block) => block.number + 1,
+            None => head,
+        };
+
+        for number in start..=head.min(start.saturating_add(MAX_BLOCKS_PER_TICK - 1)) {
+            let Some(block) = self.fetch(number).await? else {
+                break;
+            };
+
+            let expected_parent = match number.checked_sub(1) {
+                Some(parent) => self.tracker.read().await.hash_at(parent),
+                None => None,
+            };
+            if expected_parent.is_some_and(|hash| hash != block.parent_hash) {
+                let (fork_point, deep) = self.find_fork_point(number - 1).await?;
+                let reorg = self.tracker.write().await.rewind(fork_point, deep);
+                if let Some(reorg) = reorg {
+                    self.handle_reorg(reorg, head).await?;
+                }
+                // resume from the fork point on the next tick
+                return Ok(());
+            }
+
+            self.tracker.write().await.push(block);
+        }
+        Ok(())
+    }
+
+    async fn fetch(&self, number: u64) -> Result<Option<TrackedBlock>> {
+        let block = self.eth_provider.get_block(number).await?;
+        Ok(block.and_then(|block| {
+            Some(TrackedBlock {
+                number,
+                hash: block.hash?,
+                parent_hash: block.parent_hash,
+            })
+        }))
+    }
+
+    /// Walk back from `number` until the tracked hash matches the canonical chain.
+    /// Returns the last canonical block and whether it lies below the tracked window.
+    async fn find_fork_point(&self, mut number: u64) -> Result<(u64, bool)> {
+        loop {
+            let tracked = self.tracker.read().await.hash_at(number);
+            let Some(tracked) = tracked else {
+                return Ok((self.find_deep_fork_point(number).await?, true));
+            };
+            if self.canonical_hash(number).await? == Some(tracked) || number == 0 {
+                return Ok((number, false));
+            }
+            number -= 1;
+        }
+    }
+
+    /// Fork point of a reorg deeper than the tracked window. Only the blocks the
+    /// stores hold events or deposits of can be checked; walking them newest first,
+    /// the first one still canonical bounds what has to be rolled back.
+    async fn find_deep_fork_point(&self, below_window: u64) -> Result<u64> {
+        let mut stored = self.events.read().await.blocks_below(below_window + 1);
+        stored.extend(self.deposits.read().await.blocks_below(below_window + 1));
+        stored.sort_unstable_by(|a, b| b.cmp(a));
+        stored.dedup();
+
+        let mut fork_point = below_window;
+        for (number, hash) in stored {
+            if self.canonical_hash(number).await? == Some(hash) {
+                break;
+            }
+            fork_point = number.saturating_sub(1);
+        }
+        error!(
+            fork_point,
+            tracked_from = below_window + 1,
+            "reorg deeper than the tracked window, rolling back as far as stored data was orphaned"
+        );
+        Ok(fork_point)
+    }
+
+    async fn canonical_hash(&self, number: u64) -> Result<Option<H256>> {
+        Ok(self.fetch(number).await?.map(|block| block.hash))
+    }
+
+    async fn handle_reorg(&self, reorg: Reorg, head: u64) -> Result<()> {
+        warn!(
+            from_block = reorg.from_block,
+            to_block = reorg.to_block,
+            depth = reorg.depth(),
+            deep = reorg.deep,
+            "reorg detected"
+        );
+
+        self.feed.invalidate(reorg.from_block).await;
+        let removed_events = self.events.write().await.rollback(reorg.from_block);
+        let removed_deposits = self.deposits.write().await.rollback(reorg.from_block);
+        info!(
+            events = removed_events,
+            deposits = removed_deposits,
+            from_block = reorg.from_block,
+            "rolled back orphaned blocks, re-indexing"
+        );
+
+        // Deposits are picked up again by the rewound scanner. Events are re-indexed
+        // here, and by the next ticks if that fails, since the rewound tracker
+        // won't report the rolled back range again.
+        {
+            let mut pending = self.pending_reindex.lock().await;
+            *pending = Some(pending.map_or(reorg.from_block, |from_block| from_block.min(reorg.from_block)));
+        }
+        // No subscribers is not an error
+        let _ = self.reorgs.send(reorg);
+        self.reindex(head).await
+    }
+
+    /// Query the events of every contract indexed so far, listened to or
+    /// backfilled, again from the first rolled back block up to `head`
+    async fn reindex(&self, head: u64) -> Result<()> {
+        let mut pending = self.pending_reindex.lock().await;
+        let Some(from_block) = *pending else {
+            return Ok(());
+        };
+
+        let mut contracts = self.events.read().await.contracts();
+        contracts.extend(self.listeners.read().await.contracts());
+        contracts.sort_unstable();
+        contracts.dedup();
+        for contract in contracts {
+            index_transfers(&self.eth_provider, contract, from_block, head, &self.events, None).await?;
+        }
+        *pending = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use crate::service::erc20_service::index_transfers;
+
+    struct Fixture {
+        node: MockNode,
+        monitor: BlockMonitor,
+        events: Arc<RwLock<EventStore>>,
+        reorgs: broadcast::Receiver<Reorg>,
+    }
+
+    async fn fixture(track_depth: usize) -> Fixture {
+        let node = MockNode::start().await;
+        let events = Arc::new(RwLock::new(EventStore::new()));
+        let (reorgs, receiver) = broadcast::channel(4);
+        let monitor = BlockMonitor::new(
+            Arc::new(BlockFeed::new(node.provider(), false)),
+            Arc::new(RwLock::new(BlockTracker::new(track_depth))),
+            events.clone(),
+            Arc::new(RwLock::new(DepositStore::new())),
+            Arc::new(RwLock::new(ListenerRegistry::new())),
+            reorgs,
+            1,
+        );
+        Fixture {
+            node,
+            monitor,
+            events,
+            reorgs: receiver,
+        }
+    }
+
+    async fn transfer_blocks(events: &RwLock<EventStore>, token: Address) -> Vec<(u64, H256)> {
+        events
+            .read()
+            .await
+            .transfers_by_contract(token, 0, u64::MAX)
+            .iter()
+            .map(|event| (event.block_number, event.block_hash))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reorg_rolls_back_and_reindexes_backfilled_contracts() {
+        let mut f = fixture(64).await;
+        let token = Address::repeat_byte(0x70);
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+        f.node.chain().mine(3);
+        f.monitor.tick().await.unwrap();
+        {
+            let mut chain = f.node.chain();
+            chain.mine(3);
+            chain.transfer(4, token, alice, bob, 1);
+            chain.transfer(5, token, alice, bob, 2);
+        }
+        f.monitor.tick().await.unwrap();
+        // backfilled only, nobody listens to the token
+        let provider = f.node.provider();
+        index_transfers(&provider, token, 0, 6, &f.events, None).await.unwrap();
+        let block_4 = f.node.chain().hash_at(4);
+        assert_eq!(transfer_blocks(&f.events, token).await, [(4, block_4), (5, f.node.chain().hash_at(5))]);
+
+        // blocks 5 and 6 are replaced, the new branch moves the second transfer to block 6
+        {
+            let mut chain = f.node.chain();
+            chain.reorg(2);
+            chain.transfer(6, token, alice, bob, 2);
+            chain.mine(1);
+        }
+        f.monitor.tick().await.unwrap();
+
+        let reorg = f.reorgs.try_recv().unwrap();
+        assert_eq!((reorg.from_block, reorg.to_block, reorg.orphaned.len(), reorg.deep), (5, 6, 2, false));
+        assert_eq!(transfer_blocks(&f.events, token).await, [(4, block_4), (6, f.node.chain().hash_at(6))]);
+
+        // the monitor resumes from the fork point on the new branch
+        f.monitor.tick().await.unwrap();
+        let tracker = f.monitor.tracker.read().await;
+        assert_eq!(tracker.head().map(|block| block.number), Some(7));
+        assert_eq!(tracker.hash_at(5), Some(f.node.chain().hash_at(5)));
+    }
+
+    #[tokio::test]
+    async fn failed_reindexing_is_retried_on_the_next_tick() {
+        let mut f = fixture(64).await;
+        let token = Address::repeat_byte(0x70);
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+        {
+            let mut chain = f.node.chain();
+            chain.mine(4);
+            chain.transfer(4, token, alice, bob, 1);
+        }
+        f.monitor.tick().await.unwrap();
+        let provider = f.node.provider();
+        index_transfers(&provider, token, 0, 4, &f.events, None).await.unwrap();
+
+        {
+            let mut chain = f.node.chain();
+            chain.reorg(1);
+            chain.transfer(4, token, alice, bob, 1);
+            chain.mine(1);
+            chain.responses.insert("eth_getLogs".to_string(), Err((-32000, "header not found".to_string())));
+        }
+        assert!(f.monitor.tick().await.is_err());
+        assert_eq!(f.reorgs.try_recv().unwrap().from_block, 4);
+        assert!(transfer_blocks(&f.events, token).await.is_empty());
+
+        f.node.chain().responses.remove("eth_getLogs");
+        f.monitor.tick().await.unwrap();
+        assert_eq!(transfer_blocks(&f.events, token).await, [(4, f.node.chain().hash_at(4))]);
+        assert!(f.monitor.pending_reindex.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn reorg_below_the_window_rolls_back_stale_events() {
+        let mut f = fixture(2).await;
+        let token = Address::repeat_byte(0x70);
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+        {
+            let mut chain = f.node.chain();
+            chain.mine(2);
+            chain.transfer(1, token, alice, bob, 1);
+            chain.transfer(2, token, alice, bob, 2);
+        }
+        f.monitor.tick().await.unwrap();
+        f.node.chain().mine(4);
+        f.monitor.tick().await.unwrap();
+        let provider = f.node.provider();
+        index_transfers(&provider, token, 0, 6, &f.events, None).await.unwrap();
+
+        // only blocks 5 and 6 are tracked, the reorg replaces blocks 2 to 6
+        {
+            let mut chain = f.node.chain();
+            chain.reorg(5);
+            chain.transfer(3, token, alice, bob, 2);
+            chain.mine(1);
+        }
+        f.monitor.tick().await.unwrap();
+
+        let reorg = f.reorgs.try_recv().unwrap();
+        assert_eq!((reorg.from_block, reorg.to_block, reorg.deep), (2, 6, true));
+        let chain_hash = |number| f.node.chain().hash_at(number);
+        assert_eq!(transfer_blocks(&f.events, token).await, [(1, chain_hash(1)), (3, chain_hash(3))]);
+    }
+
+    #[tokio::test]
+    async fn block_ranges_near_the_end_of_the_number_space_do_not_overflow() {
+        let node = MockNode::start().await;
+        let provider = node.provider();
+        let tracker = RwLock::new(BlockTracker::new(1));
+        let service = BlockService::new(&provider, &tracker).unwrap();
+
+        let page = service.get_blocks(u64::MAX - 1, Some(u64::MAX), Some(50)).await.unwrap();
+        assert!(page.blocks.is_empty());
+        assert!(page.next.is_none());
     }
 }
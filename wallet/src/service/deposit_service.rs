@@ -0,0 +1,335 @@
+use crate::chain::eth::RpcPool;
+use crate::error::{parse_address, ApiError, ErrorCode};
+use crate::model::deposit::{Deposit, DepositStatus, DepositStore};
+use crate::model::api_key::Principal;
+use crate::model::heartbeat::Heartbeats;
+use crate::model::keyring::Keyring;
+use crate::model::watchlist::WatchList;
+use crate::service::block_feed::{managed_addresses, BlockFeed};
+use crate::service::trace_service::InternalTransfer;
+use crate::service::erc20_service::{base_units, TransferFilter};
+use crate::service::tenant_service::{check_address, owns_address};
+use crate::types::{Amount, Asset};
+use anyhow::Result;
+use ethers::contract::parse_log;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Filter, ValueOrArray, H256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Upper bound of blocks walked per scanner tick, so a long downtime is caught up gradually
+const MAX_BLOCKS_PER_SCAN: u64 = 100;
+
+pub struct DepositService<'a> {
+    keyring: &'a RwLock<Keyring>,
+    watched: &'a RwLock<WatchList>,
+    deposits: &'a RwLock<DepositStore>,
+}
+
+impl<'a> DepositService<'a> {
+    pub fn new(
+        keyring: &'a RwLock<Keyring>,
+        watched: &'a RwLock<WatchList>,
+        deposits: &'a RwLock<DepositStore>,
+    ) -> Result<Self> {
+        Ok(Self {
+            keyring,
+            watched,
+            deposits,
+        })
+    }
+
+    pub async fn watch_address(&self, principal: &Principal, address: &str) -> Result<Address> {
+        let address = parse_address(address)?;
+        // Watching the address of another tenant's key would expose its deposits and history
+        let keyring = self.keyring.read().await;
+        if keyring.owner(address).is_some_and(|owner| !principal.can_access(owner)) {
+            return Err(ApiError::new(ErrorCode::Forbidden, format!("address {:?} belongs to another tenant", address)).into());
+        }
+        self.watched.write().await.watch(address, principal.tenant);
+        Ok(address)
+    }
+
+    /// Deposits to the addresses of the caller's tenant
+    pub async fn get_deposits(&self, principal: &Principal, address: Option<&str>) -> Result<Vec<Deposit>> {
+        let deposits = match address {
+            Some(address) => {
+                let address = parse_address(address)?;
+                check_address(principal, address, self.keyring, self.watched).await?;
+                return Ok(self.deposits.read().await.by_address(address));
+            }
+            None => self.deposits.read().await.all(),
+        };
+        if principal.tenant.is_none() {
+            return Ok(deposits);
+        }
+
+        let mut visible = Vec::with_capacity(deposits.len());
+        for deposit in deposits {
+            if owns_address(principal, deposit.address, self.keyring, self.watched).await {
+                visible.push(deposit);
+            }
+        }
+        Ok(visible)
+    }
+}
+
+/// Background scanner walking new blocks for incoming ETH and ERC20 transfers
+/// to keyring and watch-only addresses
+pub struct DepositScanner {
+    eth_provider: Provider<RpcPool>,
+    feed: Arc<BlockFeed>,
+    keyring: Arc<RwLock<Keyring>>,
+    watched: Arc<RwLock<WatchList>>,
+    deposits: Arc<RwLock<DepositStore>>,
+    confirmations: u64,
+    poll_interval: Duration,
+}
+
+impl DepositScanner {
+    pub fn new(
+        feed: Arc<BlockFeed>,
+        keyring: Arc<RwLock<Keyring>>,
+        watched: Arc<RwLock<WatchList>>,
+        deposits: Arc<RwLock<DepositStore>>,
+        confirmations: u64,
+        poll_interval: u64,
+    ) -> Self {
+        Self {
+            eth_provider: feed.provider().clone(),
+            feed,
+            keyring,
+            watched,
+            deposits,
+            confirmations,
+            poll_interval: Duration::from_secs(poll_interval),
+        }
+    }
+
+    pub fn spawn(self, heartbeats: Arc<Heartbeats>) {
+        heartbeats.register("deposit_scanner", self.poll_interval);
+        tokio::spawn(
+            async move {
+                info!(confirmations = self.confirmations, "deposit scanner started");
+                loop {
+                    let result = self.scan().await;
+                    if let Err(e) = &result {
+                        warn!(error = %e, "deposit scan failed");
+                    }
+                    heartbeats.record("deposit_scanner", &result);
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }
+            .instrument(info_span!("deposit_scanner")),
+        );
+    }
+
+    async fn scan(&self) -> Result<()> {
+        let head = self.eth_provider.get_block_number().await?.as_u64();
+        let start = match self.deposits.read().await.last_scanned() {
+            Some(last) => last + 1,
+            None => head,
+        };
+
+        if start <= head {
+            let end = head.min(start.saturating_add(MAX_BLOCKS_PER_SCAN - 1));
+            let addresses = managed_addresses(&self.keyring, &self.watched).await;
+            let mut scanned_to = Some(end);
+            if !addresses.is_empty() {
+                // a block the node doesn't serve yet is scanned from on the next tick
+                scanned_to = None;
+                for number in start..=end {
+                    if !self.scan_native(number, &addresses).await? {
+                        break;
+                    }
+                    scanned_to = Some(number);
+                }
+                if let Some(last) = scanned_to {
+                    self.scan_tokens(start, last, &addresses).await?;
+                }
+            }
+            if let Some(last) = scanned_to {
+                self.deposits.write().await.set_last_scanned(last);
+            }
+        }
+
+        for deposit in self.deposits.write().await.confirm(head, self.confirmations) {
+            info!(
+                amount = %deposit.amount,
+                address = ?deposit.address,
+                tx_hash = ?deposit.tx_hash,
+                "deposit credited"
+            );
+        }
+        Ok(())
+    }
+
+    /// Record native deposits of block `number`, `false` if the node doesn't have it yet
+    async fn scan_native(&self, number: u64, addresses: &HashSet<Address>) -> Result<bool> {
+        let Some(block) = self.feed.block(number).await? else {
+            return Ok(false);
+        };
+        let block_hash = block.hash.unwrap_or_default();
+
+        let mut store = self.deposits.write().await;
+        for tx in &block.transactions {
+            let Some(to) = tx.to.filter(|to| addresses.contains(to)) else {
+                continue;
+            };
+            if tx.value.is_zero() {
+                continue;
+            }
+            store.insert(Deposit {
+                address: to,
+                from: tx.from,
+                token: None,
+                amount: Amount::new(tx.value, Asset::ether()),
+                block_number: number,
+                block_hash,
+                tx_hash: tx.hash,
+                log_index: None,
+                trace_index: None,
+                confirmations: 0,
+                status: DepositStatus::Pending,
+            });
+        }
+        drop(store);
+
+        if let Some(transfers) = self.feed.internal_transfers(&block).await? {
+            let mut store = self.deposits.write().await;
+            for (transfer, index) in transfers.iter().zip(trace_indexes(&transfers)) {
+                if !addresses.contains(&transfer.to) {
+                    continue;
+                }
+                store.insert(Deposit {
+                    address: transfer.to,
+                    from: transfer.from,
+                    token: None,
+                    amount: transfer.value.clone(),
+                    block_number: number,
+                    block_hash,
+                    tx_hash: transfer.tx_hash,
+                    log_index: None,
+                    trace_index: Some(index),
+                    confirmations: 0,
+                    status: DepositStatus::Pending,
+                });
+            }
+        }
+        Ok(true)
+    }
+
+    async fn scan_tokens(&self, from_block: u64, to_block: u64, addresses: &HashSet<Address>) -> Result<()> {
+        let recipients: Vec<H256> = addresses.iter().map(|address| H256::from(*address)).collect();
+        let filter = Filter::new()
+            .from_block(from_block)
+            .to_block(to_block)
+            .event("Transfer(address,address,uint256)")
+            .topic2(ValueOrArray::Array(recipients));
+        let logs = self.eth_provider.get_logs(&filter).await?;
+
+        let mut deposits = Vec::new();
+        for log in logs {
+            let (Some(block_number), Some(block_hash), Some(tx_hash)) =
+                (log.block_number, log.block_hash, log.transaction_hash)
+            else {
+                continue;
+            };
+            let contract = log.address;
+            let log_index = log.log_index.map(|index| index.as_u64());
+            // ERC721 shares the Transfer signature but indexes the token id, skip what doesn't decode
+            let Ok(transfer) = parse_log::<TransferFilter>(log) else {
+                continue;
+            };
+            let asset = self.feed.token(contract).await.unwrap_or_else(|| base_units(contract));
+            deposits.push(Deposit {
+                address: transfer.to,
+                from: transfer.from,
+                token: Some(contract),
+                amount: Amount::new(transfer.value, asset),
+                block_number: block_number.as_u64(),
+                block_hash,
+                tx_hash,
+                log_index,
+                trace_index: None,
+                confirmations: 0,
+                status: DepositStatus::Pending,
+            });
+        }
+
+        let mut store = self.deposits.write().await;
+        for deposit in deposits {
+            store.insert(deposit);
+        }
+        Ok(())
+    }
+}
+
+/// Position of each internal transfer among those of its transaction. Traces come
+/// flattened for the whole block, positions restart with each transaction.
+fn trace_indexes(transfers: &[InternalTransfer]) -> Vec<u64> {
+    let mut counts: HashMap<H256, u64> = HashMap::new();
+    transfers
+        .iter()
+        .map(|transfer| {
+            let count = counts.entry(transfer.tx_hash).or_default();
+            *count += 1;
+            *count - 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use serde_json::json;
+
+    fn scanner(node: &MockNode, watched: Address) -> DepositScanner {
+        let mut watch_list = WatchList::new();
+        watch_list.watch(watched, None);
+        DepositScanner::new(
+            Arc::new(BlockFeed::new(node.provider(), false)),
+            Arc::new(RwLock::new(Keyring::new())),
+            Arc::new(RwLock::new(watch_list)),
+            Arc::new(RwLock::new(DepositStore::new())),
+            1,
+            1,
+        )
+    }
+
+    #[tokio::test]
+    async fn scanning_stops_at_blocks_the_node_does_not_serve_yet() {
+        let node = MockNode::start().await;
+        node.chain().mine(3);
+        let scanner = scanner(&node, Address::repeat_byte(1));
+        scanner.deposits.write().await.set_last_scanned(1);
+
+        // the head is announced before blocks 4 and 5 can be fetched
+        node.chain().responses.insert("eth_blockNumber".to_string(), Ok(json!("0x5")));
+        scanner.scan().await.unwrap();
+        assert_eq!(scanner.deposits.read().await.last_scanned(), Some(3));
+
+        node.chain().mine(2);
+        node.chain().responses.clear();
+        scanner.scan().await.unwrap();
+        assert_eq!(scanner.deposits.read().await.last_scanned(), Some(5));
+    }
+
+    #[test]
+    fn trace_indexes_restart_with_each_transaction() {
+        let transfer = |tx: u8| InternalTransfer {
+            tx_hash: H256::repeat_byte(tx),
+            from: Address::zero(),
+            to: Address::zero(),
+            value: Amount::new(1.into(), Asset::ether()),
+            call_type: "CALL".to_string(),
+            depth: 1,
+        };
+        let transfers = [transfer(1), transfer(1), transfer(2), transfer(1), transfer(3)];
+        assert_eq!(trace_indexes(&transfers), [0, 1, 0, 2, 0]);
+    }
+}
@@ -1,16 +1,33 @@
+use crate::chain::eth::{RpcPool, WsConnection};
+use crate::config::server_config::FeePolicy;
+use crate::error::{parse_address, ApiError, ErrorCode};
+use crate::metrics::METRICS;
+use crate::model::api_key::Principal;
+use crate::model::event_store::{EventStore, TransferEvent};
+use crate::model::job::JobRegistry;
 use crate::model::keyring::Keyring;
+use crate::model::listener::{ListenerMode, ListenerRegistry, ListenerState};
+use crate::service::ether_service::apply_fee_policy;
+use crate::service::outbound_service::{NewOutboundTx, OutboundTxService, SentTransaction};
+use crate::types::{Amount, Asset};
 use anyhow::Result;
-use ethers::contract::abigen;
+use ethers::contract::{abigen, LogMeta};
 use ethers::middleware::{Middleware, SignerMiddleware};
-use ethers::providers::{Http, Provider, Ws};
+use ethers::providers::Provider;
 use ethers::signers::Signer;
-use ethers::types::{Address, H256};
-use ethers::utils::{format_units, parse_units};
-use futures::StreamExt;
+use ethers::types::{Address, Bytes, U256};
+use ethers::utils::to_checksum;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use serde::Serialize;
-use std::collections::HashSet;
+use serde_json::json;
+use std::fmt::Display;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{watch, RwLock};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 abigen!(
     ERC20,
@@ -25,54 +42,80 @@ abigen!(
     ]"#,
 );
 
-#[derive(Serialize)]
+/// Initial block span of a backfill chunk, halved when the node rejects the range
+const BACKFILL_CHUNK_SIZE: u64 = 2_000;
+/// Maximum number of chunks queried in parallel
+const BACKFILL_CONCURRENCY: usize = 4;
+/// Pause before a listener resubscribes, so a flapping connection doesn't spin
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, ToSchema)]
 pub struct TokenInfo {
     pub name: String,
     pub symbol: String,
     pub decimals: u8,
-    pub total_supply: String,
+    pub total_supply: Amount,
 }
 
 pub struct ERC20Service<'a> {
-    eth_provider: &'a Provider<Http>,
-    ws_provider: Option<&'a Provider<Ws>>,
+    eth_provider: &'a Provider<RpcPool>,
+    ws: &'a Arc<WsConnection>,
     keyring: &'a RwLock<Keyring>,
-    listening: &'a RwLock<HashSet<Address>>,
+    listeners: &'a Arc<RwLock<ListenerRegistry>>,
+    events: &'a Arc<RwLock<EventStore>>,
+    jobs: &'a Arc<RwLock<JobRegistry>>,
+    fee: &'a FeePolicy,
 }
 
 impl<'a> ERC20Service<'a> {
     pub fn new(
-        eth: &'a Provider<Http>,
-        eth_ws: Option<&'a Provider<Ws>>,
+        eth: &'a Provider<RpcPool>,
+        eth_ws: &'a Arc<WsConnection>,
         ring: &'a RwLock<Keyring>,
-        listening: &'a RwLock<HashSet<Address>>,
+        listeners: &'a Arc<RwLock<ListenerRegistry>>,
+        events: &'a Arc<RwLock<EventStore>>,
+        jobs: &'a Arc<RwLock<JobRegistry>>,
+        fee: &'a FeePolicy,
     ) -> Result<Self> {
         Ok(Self {
             eth_provider: eth,
-            ws_provider: eth_ws,
+            ws: eth_ws,
             keyring: ring,
-            listening,
+            listeners,
+            events,
+            jobs,
+            fee,
         })
     }
 
-    pub async fn get_balance(&self, address: &str, contract_address: &str) -> Result<String> {
-        let address = address.parse::<Address>()?;
-        let contract_address = contract_address.parse::<Address>()?;
+    pub async fn get_balance(&self, address: &str, contract_address: &str) -> Result<Amount> {
+        let address = parse_address(address)?;
+        let contract_address = parse_address(contract_address)?;
 
         let contract = ERC20::new(contract_address, Arc::new(self.eth_provider.clone()));
 
         let balance = contract.balance_of(address).call().await?;
-        let decimals = contract.decimals().call().await?;
-        let balance_formatted = format_units(balance, decimals as u32)?;
-
-        Ok(balance_formatted)
+        Ok(Amount::new(balance, token_asset(&contract).await?))
     }
 
-    pub async fn send_transaction(&self, from: &str, to: &str, amount: &str, contract_address: &str) -> Result<H256> {
-        let from_addr = from.parse::<Address>()?;
-        let to_addr = to.parse::<Address>()?;
-        let contract_addr = contract_address.parse::<Address>()?;
-        let key_entry = self.keyring.read().await.get_by_address(from_addr)?;
+    /// Send tokens from a keyring address owned by the caller's tenant, recorded
+    /// in `outbound_txs` for the tracker to follow
+    pub async fn send_transaction(
+        &self,
+        principal: &Principal,
+        outbound: &OutboundTxService<'_>,
+        from: &str,
+        to: &str,
+        amount: &str,
+        contract_address: &str,
+    ) -> Result<SentTransaction> {
+        let from_addr = parse_address(from)?;
+        let to_addr = parse_address(to)?;
+        let contract_addr = parse_address(contract_address)?;
+        let (key_entry, tenant_id) = {
+            let keyring = self.keyring.read().await;
+            (keyring.get_by_address(from_addr, principal)?, keyring.owner(from_addr).flatten())
+        };
 
         let chain_id = self.eth_provider.get_chainid().await?.as_u64();
         let signer = key_entry.clone().with_chain_id(chain_id);
@@ -80,114 +123,565 @@ impl<'a> ERC20Service<'a> {
 
         let contract = ERC20::new(contract_addr, client);
 
-        let decimals = contract.decimals().call().await?;
-        let amount_formatted = parse_units(amount, decimals as u32)?.into();
+        let requested = json!({ "from": from, "to": to, "amount": amount, "contract_address": contract_address });
+        let amount = Amount::parse(amount, token_asset(&contract).await?)?;
+
+        // Token balance is confirmed by a quorum of endpoints before withdrawing
+        let balance_call = contract.balance_of(from_addr).tx;
+        let balance: Bytes = self
+            .eth_provider
+            .as_ref()
+            .request_quorum("eth_call", (balance_call, "latest"))
+            .await?;
+        let balance = balance.get(..32).map(U256::from_big_endian).unwrap_or_default();
+        if balance < amount.raw() {
+            return Err(ApiError::new(
+                ErrorCode::InsufficientFunds,
+                format!("insufficient token balance: {}", Amount::new(balance, amount.asset().clone())),
+            )
+            .into());
+        }
 
-        let call = contract.transfer(to_addr, amount_formatted);
-        let pending_tx = call.send().await?;
-        Ok(pending_tx.tx_hash())
+        let mut tx = contract.transfer(to_addr, amount.raw()).tx;
+        apply_fee_policy(contract.client_ref(), &mut tx, self.fee).await?;
+        let new = NewOutboundTx {
+            tenant_id,
+            kind: "erc20",
+            from: from_addr,
+            to: to_addr,
+            amount,
+            request: requested,
+        };
+        outbound.sign_and_send(new, contract.client_ref(), tx).await
     }
 
     pub async fn get_info(&self, contract_address: &str) -> Result<TokenInfo> {
-        let contract_addr = contract_address.parse::<Address>()?;
+        let contract_addr = parse_address(contract_address)?;
         let contract = ERC20::new(contract_addr, Arc::new(self.eth_provider.clone()));
 
         let name = contract.name().call().await?;
-        let symbol = contract.symbol().call().await?;
-        let decimals = contract.decimals().call().await?;
+        let asset = token_asset(&contract).await?;
         let total_supply = contract.total_supply().call().await?;
-        let total_supply_formatted = format_units(total_supply, decimals as u32)?;
 
         Ok(TokenInfo {
             name,
-            symbol,
-            decimals,
-            total_supply: total_supply_formatted,
+            symbol: asset.symbol.clone(),
+            decimals: asset.decimals,
+            total_supply: Amount::new(total_supply, asset),
         })
     }
 
-    pub async fn listen(&mut self, contract_address: &str) -> Result<String> {
-        let contract_addr = contract_address.parse::<Address>()?;
-        // listen should be executed only once
-        if self.listening.read().await.contains(&contract_addr) {
+    pub async fn listen(&mut self, principal: &Principal, contract_address: &str) -> Result<String> {
+        let contract_addr = parse_address(contract_address)?;
+        // listen should be executed only once, later callers are added as owners
+        if !self.listeners.write().await.register(contract_addr, principal.tenant) {
             return Ok("already listening".to_string());
         }
-        self.listening.write().await.insert(contract_addr);
-
-        // Prefer WebSocket Provider, fallback to HTTP Provider if unavailable
-        let use_ws = self.ws_provider.is_some();
-        let provider_type = if use_ws { "WebSocket" } else { "HTTP" };
-        
-        println!("Using {} Provider to listen for Transfer events on contract {}", provider_type, contract_addr);
-
-        if let Some(ws_provider) = self.ws_provider {
-            // Use WebSocket Provider (real-time push, better performance)
-            let contract = ERC20::new(contract_addr, Arc::new(ws_provider.clone()));
-            let filter = contract.transfer_filter();
-
-            tokio::spawn(async move {
-                match filter.stream().await {
-                    Ok(mut stream) => {
-                        println!("✅ WebSocket event stream created successfully, starting to listen for Transfer events...");
-
-                        while let Some(result) = stream.next().await {
-                            match result {
-                                Ok(transfer) => {
-                                    println!(
-                                        "Transfer detected: from {:?} to {:?}, value {}",
-                                        transfer.from,
-                                        transfer.to,
-                                        transfer.value
-                                    );
-                                }
-                                Err(e) => {
-                                    eprintln!("⚠️  Error receiving event: {}", e);
-                                    // Can choose to continue listening or exit
-                                }
-                            }
-                        }
-                        println!("⚠️  WebSocket event stream ended");
-                    }
+
+        // Prefer WebSocket Provider, the listener falls back to HTTP polling while
+        // it is unavailable and upgrades once it (re)connects
+        let provider_type = if self.ws.is_connected().await { "WebSocket" } else { "HTTP" };
+        info!(contract = ?contract_addr, provider = provider_type, "listening for Transfer events");
+
+        TransferListener {
+            contract: contract_addr,
+            eth_provider: self.eth_provider.clone(),
+            ws: self.ws.clone(),
+            events: self.events.clone(),
+            listeners: self.listeners.clone(),
+        }
+        .spawn();
+
+        Ok(format!("listening with {}", provider_type))
+    }
+
+    pub async fn get_listeners(&self, principal: &Principal) -> Vec<ListenerState> {
+        let listeners = self.listeners.read().await;
+        match principal.tenant {
+            Some(tenant) => listeners.all_of(tenant),
+            None => listeners.all(),
+        }
+    }
+
+    /// Start a background job indexing `Transfer` logs of a contract over a block range
+    pub async fn backfill(&self, contract_address: &str, from_block: u64, to_block: u64) -> Result<Uuid> {
+        let contract_addr = parse_address(contract_address)?;
+        if from_block > to_block {
+            return Err(ApiError::invalid_request("from_block must not be greater than to_block").into());
+        }
+
+        // saturates for a range covering every block number
+        let total = (to_block - from_block).saturating_add(1);
+        let job_id = self.jobs.write().await.create("erc20_backfill", total);
+        let eth_provider = self.eth_provider.clone();
+        let events = self.events.clone();
+        let jobs = self.jobs.clone();
+
+        let span = info_span!("erc20_backfill", %job_id, contract = ?contract_addr, from_block, to_block);
+        tokio::spawn(
+            async move {
+                jobs.write().await.start(job_id);
+                let result =
+                    index_transfers(&eth_provider, contract_addr, from_block, to_block, &events, Some((&jobs, job_id)))
+                        .await;
+                match result {
+                    Ok(_) => jobs.write().await.complete(job_id),
                     Err(e) => {
-                        eprintln!("❌ Failed to create WebSocket event stream: {}", e);
+                        error!(error = %e, "backfill job failed");
+                        jobs.write().await.fail(job_id, e.to_string());
                     }
                 }
-            });
-        } else {
-            // Fallback to HTTP Provider (polling mode)
-            let contract = ERC20::new(contract_addr, Arc::new(self.eth_provider.clone()));
-            let filter = contract.transfer_filter();
-
-            tokio::spawn(async move {
-                match filter.stream().await {
-                    Ok(mut stream) => {
-                        println!("✅ HTTP event stream created successfully, starting to poll for Transfer events...");
-
-                        while let Some(result) = stream.next().await {
-                            match result {
-                                Ok(transfer) => {
-                                    println!(
-                                        "Transfer detected: from {:?} to {:?}, value {}",
-                                        transfer.from,
-                                        transfer.to,
-                                        transfer.value
-                                    );
-                                }
-                                Err(e) => {
-                                    eprintln!("⚠️  Error receiving event: {}", e);
-                                }
-                            }
+            }
+            .instrument(span),
+        );
+
+        Ok(job_id)
+    }
+}
+
+/// Supervised `Transfer` listener of one contract. It subscribes over WebSocket
+/// when connected and polls over HTTP otherwise; whenever the connection is
+/// established or lost it resubscribes, first querying the logs emitted since
+/// the last block it saw so nothing is missed in between.
+struct TransferListener {
+    contract: Address,
+    eth_provider: Provider<RpcPool>,
+    ws: Arc<WsConnection>,
+    events: Arc<RwLock<EventStore>>,
+    listeners: Arc<RwLock<ListenerRegistry>>,
+}
+
+impl TransferListener {
+    fn spawn(self) {
+        // outlives the request that started it, so not a child of its span
+        let span = info_span!(parent: None, "transfer_listener", contract = ?self.contract);
+        tokio::spawn(async move { self.run().await }.instrument(span));
+    }
+
+    async fn run(&self) {
+        // logs before the first subscription are left to explicit backfills
+        let mut last_seen = self.eth_provider.get_block_number().await.ok().map(|n| n.as_u64());
+        let token = ERC20::new(self.contract, Arc::new(self.eth_provider.clone()));
+        let asset = token_asset(&token).await.unwrap_or_else(|_| base_units(self.contract));
+        let mut first = true;
+
+        loop {
+            if !first {
+                self.set_mode(ListenerMode::Reconnecting, |state| state.restarts += 1).await;
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+            first = false;
+
+            // subscribe before catching up so logs of blocks mined in between are
+            // not lost; duplicates are harmless since the store is keyed by log position
+            let mut generation = self.ws.subscribe();
+            generation.mark_unchanged();
+
+            match self.ws.provider().await {
+                Some(ws_provider) => {
+                    let contract = ERC20::new(self.contract, Arc::new(ws_provider));
+                    let filter = contract.transfer_filter();
+                    match filter.stream_with_meta().await {
+                        Ok(stream) => {
+                            info!("WebSocket event stream created");
+                            self.set_mode(ListenerMode::WebSocket, |_| {}).await;
+                            self.catch_up(&asset, &mut last_seen).await;
+                            self.consume(stream, &asset, &mut generation, &mut last_seen).await;
+                            warn!("WebSocket event stream ended");
                         }
-                        println!("⚠️  HTTP event stream ended");
+                        Err(e) => self.record_error(format!("failed to create WebSocket event stream: {}", e)).await,
                     }
-                    Err(e) => {
-                        eprintln!("❌ Failed to create HTTP event stream: {}", e);
+                }
+                None => {
+                    info!("polling Transfer logs over HTTP");
+                    self.set_mode(ListenerMode::Http, |_| {}).await;
+                    // ends as soon as the WebSocket connects, upgrading the listener
+                    self.poll(&asset, &mut generation, &mut last_seen).await;
+                }
+            }
+        }
+    }
+
+    /// Record events until the stream ends or the WebSocket connection changes
+    async fn consume<S, E>(
+        &self,
+        mut stream: S,
+        asset: &Asset,
+        generation: &mut watch::Receiver<u64>,
+        last_seen: &mut Option<u64>,
+    ) where
+        S: Stream<Item = std::result::Result<(TransferFilter, LogMeta), E>> + Unpin,
+        E: Display,
+    {
+        loop {
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(Ok((transfer, meta))) => {
+                        debug!(from = ?transfer.from, to = ?transfer.to, value = %transfer.value, "Transfer detected");
+                        self.record(transfer, meta, asset, last_seen).await;
+                    }
+                    Some(Err(e)) => warn!(error = %e, "error receiving event"),
+                    None => return,
+                },
+                changed = generation.changed() => {
+                    if changed.is_err() {
+                        // the connection manager is gone, keep the current stream
+                        std::future::pending::<()>().await;
                     }
+                    return;
                 }
-            });
+            }
         }
+    }
 
-        Ok(format!("listening with {}", provider_type))
+    /// Index new logs every poll interval until the WebSocket connection changes.
+    /// Logs are queried by block range rather than through a server-side filter: a
+    /// filter only exists on the node that created it, and the pool may send the
+    /// next poll to another one.
+    async fn poll(&self, asset: &Asset, generation: &mut watch::Receiver<u64>, last_seen: &mut Option<u64>) {
+        let mut connection_watched = true;
+        loop {
+            self.catch_up(asset, last_seen).await;
+            tokio::select! {
+                () = tokio::time::sleep(self.eth_provider.get_interval()) => {}
+                changed = generation.changed(), if connection_watched => {
+                    if changed.is_err() {
+                        // the connection manager is gone, keep polling
+                        connection_watched = false;
+                        continue;
+                    }
+                    return;
+                }
+            }
+        }
     }
 
+    /// Index logs emitted after the last block seen, in chunks like a backfill
+    /// since the listener may have been down for long. When the head couldn't be
+    /// read at startup, the first head read here takes its place.
+    async fn catch_up(&self, asset: &Asset, last_seen: &mut Option<u64>) {
+        let head = match self.eth_provider.get_block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(e) => return self.record_error(format!("failed to index new events: {}", e)).await,
+        };
+        let Some(from_block) = last_seen.map(|block| block + 1) else {
+            *last_seen = Some(head);
+            self.listeners
+                .write()
+                .await
+                .update(self.contract, |state| state.last_seen_block = *last_seen);
+            return;
+        };
+        if from_block > head {
+            return;
+        }
+
+        match index_logs(&self.eth_provider, self.contract, asset, from_block, head, &self.events, None).await {
+            Ok(found) => {
+                if found > 0 {
+                    info!(events = found, from_block, to_block = head, "indexed Transfer events");
+                    METRICS
+                        .listener_events
+                        .with_label_values(&[&format!("{:?}", self.contract)])
+                        .inc_by(found);
+                }
+                *last_seen = Some(last_seen.unwrap_or(head).max(head));
+                self.listeners.write().await.update(self.contract, |state| {
+                    state.events += found;
+                    state.last_seen_block = *last_seen;
+                });
+            }
+            Err(e) => self.record_error(format!("failed to index new events: {}", e)).await,
+        }
+    }
+
+    async fn record(&self, transfer: TransferFilter, meta: LogMeta, asset: &Asset, last_seen: &mut Option<u64>) {
+        let block = meta.block_number.as_u64();
+        *last_seen = Some(last_seen.unwrap_or(block).max(block));
+        self.events.write().await.insert_transfer(to_transfer_event(transfer, meta, asset));
+        METRICS
+            .listener_events
+            .with_label_values(&[&format!("{:?}", self.contract)])
+            .inc();
+        self.listeners.write().await.update(self.contract, |state| {
+            state.events += 1;
+            state.last_seen_block = *last_seen;
+        });
+    }
+
+    async fn set_mode(&self, mode: ListenerMode, f: impl FnOnce(&mut ListenerState)) {
+        self.listeners.write().await.update(self.contract, |state| {
+            state.mode = mode;
+            f(state);
+        });
+    }
+
+    async fn record_error(&self, error: String) {
+        error!(%error, "listener error");
+        self.listeners
+            .write()
+            .await
+            .update(self.contract, |state| state.last_error = Some(error));
+    }
+}
+
+/// Index `Transfer` logs of a contract over `from_block..=to_block` into the event
+/// store, in chunks queried in parallel. Progress is reported to `job` if given.
+/// Returns the number of logs found.
+pub(crate) async fn index_transfers(
+    eth_provider: &Provider<RpcPool>,
+    contract: Address,
+    from_block: u64,
+    to_block: u64,
+    events: &RwLock<EventStore>,
+    job: Option<(&RwLock<JobRegistry>, Uuid)>,
+) -> Result<u64> {
+    let token = ERC20::new(contract, Arc::new(eth_provider.clone()));
+    let asset = token_asset(&token).await.unwrap_or_else(|_| base_units(contract));
+    index_logs(eth_provider, contract, &asset, from_block, to_block, events, job).await
+}
+
+/// [`index_transfers`] of a token whose asset is already known
+async fn index_logs(
+    eth_provider: &Provider<RpcPool>,
+    contract: Address,
+    asset: &Asset,
+    from_block: u64,
+    to_block: u64,
+    events: &RwLock<EventStore>,
+    job: Option<(&RwLock<JobRegistry>, Uuid)>,
+) -> Result<u64> {
+    events.write().await.track(contract);
+    let contract = ERC20::new(contract, Arc::new(eth_provider.clone()));
+    stream::iter(chunks(from_block, to_block, BACKFILL_CHUNK_SIZE))
+        .map(|(start, end)| {
+            let contract = &contract;
+            async move {
+                let logs = query_split(start, end, |start, end| {
+                    let query = contract.transfer_filter().from_block(start).to_block(end);
+                    async move { query.query_with_meta().await }
+                })
+                .await?;
+
+                let found = logs.len() as u64;
+                let mut store = events.write().await;
+                for (transfer, meta) in logs {
+                    store.insert_transfer(to_transfer_event(transfer, meta, asset));
+                }
+                drop(store);
+                if let Some((jobs, job_id)) = job {
+                    jobs.write().await.progress(job_id, end - start + 1, found);
+                }
+                Ok::<_, anyhow::Error>(found)
+            }
+        })
+        .buffer_unordered(BACKFILL_CONCURRENCY)
+        .try_fold(0, |total, found| async move { Ok(total + found) })
+        .await
+}
+
+/// Consecutive ranges of at most `size` blocks covering `from_block..=to_block`
+fn chunks(from_block: u64, to_block: u64, size: u64) -> impl Iterator<Item = (u64, u64)> {
+    std::iter::successors(Some(from_block), move |start| start.checked_add(size).filter(|next| *next <= to_block))
+        .map(move |start| (start, to_block.min(start.saturating_add(size - 1))))
+}
+
+/// Run `query` over `from_block..=to_block`, splitting the range in half whenever
+/// the node reports it as too large. Other errors, rate limiting included, are
+/// returned as is, retrying them is left to the RPC pool.
+async fn query_split<T, E, F, Fut>(from_block: u64, to_block: u64, query: F) -> Result<Vec<T>>
+where
+    F: Fn(u64, u64) -> Fut,
+    Fut: Future<Output = std::result::Result<Vec<T>, E>>,
+    E: Display + Into<anyhow::Error>,
+{
+    let mut results = Vec::new();
+    let mut ranges = vec![(from_block, to_block)];
+
+    while let Some((start, end)) = ranges.pop() {
+        match query(start, end).await {
+            Ok(found) => results.extend(found),
+            Err(e) if start < end && is_range_too_large(&e.to_string()) => {
+                let mid = start + (end - start) / 2;
+                ranges.push((mid + 1, end));
+                ranges.push((start, mid));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Providers word their block range and result size caps differently, match the
+/// common ones. Throttling ("too many requests", "rate limit exceeded") must not
+/// match, splitting the range would only send more requests.
+fn is_range_too_large(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "query returned more than",
+        "block range is too large",
+        "block range too large",
+        "range too large",
+        "exceed maximum block range",
+        "response size exceeded",
+        "log response size exceeded",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+pub(crate) fn to_transfer_event(transfer: TransferFilter, meta: LogMeta, asset: &Asset) -> TransferEvent {
+    TransferEvent {
+        contract: meta.address,
+        from: transfer.from,
+        to: transfer.to,
+        value: Amount::new(transfer.value, asset.clone()),
+        block_number: meta.block_number.as_u64(),
+        block_hash: meta.block_hash,
+        tx_hash: meta.transaction_hash,
+        log_index: meta.log_index.as_u64(),
+    }
+}
+
+/// Symbol and decimals of a token, amounts of it are parsed and formatted with these
+pub(crate) async fn token_asset<M: Middleware + 'static>(contract: &ERC20<M>) -> Result<Asset> {
+    let (symbol, decimals) = (contract.symbol(), contract.decimals());
+    let (symbol, decimals) = futures::try_join!(symbol.call(), decimals.call())?;
+    Ok(Asset::token(to_checksum(&contract.address(), None), symbol, decimals))
+}
+
+/// Asset of a token that doesn't expose its symbol and decimals, amounts of it
+/// found on chain are kept in base units
+pub(crate) fn base_units(contract: Address) -> Asset {
+    Asset::token(to_checksum(&contract, None), "", 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use std::sync::Mutex;
+
+    #[test]
+    fn chunks_cover_the_range_once() {
+        assert_eq!(chunks(0, 4_999, 2_000).collect::<Vec<_>>(), [(0, 1_999), (2_000, 3_999), (4_000, 4_999)]);
+        assert_eq!(chunks(10, 10, 2_000).collect::<Vec<_>>(), [(10, 10)]);
+        assert_eq!(
+            chunks(u64::MAX - 2, u64::MAX, 2).collect::<Vec<_>>(),
+            [(u64::MAX - 2, u64::MAX - 1), (u64::MAX, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn range_caps_are_recognised() {
+        assert!(is_range_too_large("query returned more than 10000 results"));
+        assert!(is_range_too_large("eth_getLogs block range is too large, max 500"));
+        assert!(is_range_too_large("Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range"));
+        assert!(!is_range_too_large("HTTP 429 Too Many Requests"));
+        assert!(!is_range_too_large("daily request rate limit exceeded"));
+        assert!(!is_range_too_large("execution reverted"));
+    }
+
+    /// Node serving at most `cap` blocks per query, recording every range it was asked for
+    async fn capped_query(calls: &Mutex<Vec<(u64, u64)>>, cap: u64, start: u64, end: u64) -> Result<Vec<u64>> {
+        calls.lock().unwrap().push((start, end));
+        if end - start + 1 > cap {
+            anyhow::bail!("query returned more than 10000 results");
+        }
+        Ok((start..=end).collect())
+    }
+
+    #[tokio::test]
+    async fn query_split_halves_until_the_node_accepts() {
+        let calls = Mutex::new(Vec::new());
+        let blocks = query_split(0, 99, |start, end| capped_query(&calls, 30, start, end)).await.unwrap();
+
+        assert_eq!(blocks, (0..=99).collect::<Vec<_>>());
+        let calls = calls.into_inner().unwrap();
+        assert_eq!(calls, [(0, 99), (0, 49), (0, 24), (25, 49), (50, 99), (50, 74), (75, 99)]);
+    }
+
+    #[tokio::test]
+    async fn query_split_stops_at_single_blocks() {
+        let calls = Mutex::new(Vec::new());
+        let result = query_split(5, 6, |start, end| capped_query(&calls, 0, start, end)).await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.into_inner().unwrap(), [(5, 6), (5, 5)]);
+    }
+
+    #[tokio::test]
+    async fn query_split_does_not_split_on_rate_limits() {
+        let calls = Mutex::new(0);
+        let result = query_split(0, 1_999, |_, _| {
+            *calls.lock().unwrap() += 1;
+            async { Err::<Vec<u64>, _>(anyhow::anyhow!("429 Too Many Requests")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.into_inner().unwrap(), 1);
+    }
+
+    async fn listener(node: &MockNode, token: Address) -> TransferListener {
+        let listeners = Arc::new(RwLock::new(ListenerRegistry::new()));
+        listeners.write().await.register(token, None);
+        TransferListener {
+            contract: token,
+            eth_provider: node.provider(),
+            ws: WsConnection::new(Vec::new()),
+            events: Arc::new(RwLock::new(EventStore::new())),
+            listeners,
+        }
+    }
+
+    #[tokio::test]
+    async fn catch_up_starts_lazily_then_indexes_missed_blocks() {
+        let node = MockNode::start().await;
+        let token = Address::repeat_byte(7);
+        let listener = listener(&node, token).await;
+        let listeners = listener.listeners.clone();
+        let asset = base_units(token);
+
+        // the head couldn't be read at startup, the first catch up only records it
+        node.chain().mine(2);
+        let mut last_seen = None;
+        listener.catch_up(&asset, &mut last_seen).await;
+        assert_eq!(last_seen, Some(2));
+
+        node.chain().mine(3);
+        node.chain().transfer(4, token, Address::repeat_byte(1), Address::repeat_byte(2), 10);
+        listener.catch_up(&asset, &mut last_seen).await;
+
+        assert_eq!(last_seen, Some(5));
+        assert_eq!(listener.events.read().await.transfers_by_contract(token, 0, 5).len(), 1);
+        let state = listeners.read().await.all().remove(0);
+        assert_eq!((state.events, state.last_seen_block), (1, Some(5)));
+    }
+
+    #[tokio::test]
+    async fn polling_queries_logs_until_the_connection_changes() {
+        let node = MockNode::start().await;
+        let token = Address::repeat_byte(7);
+        let listener = listener(&node, token).await;
+        let (connection, mut generation) = watch::channel(0);
+
+        node.chain().mine(2);
+        let mut last_seen = Some(1);
+        node.chain().transfer(2, token, Address::repeat_byte(1), Address::repeat_byte(2), 10);
+
+        let indexed = async {
+            while listener.events.read().await.transfers_by_contract(token, 0, 2).is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            connection.send(1).unwrap();
+        };
+        let asset = base_units(token);
+        let polling = listener.poll(&asset, &mut generation, &mut last_seen);
+        tokio::time::timeout(Duration::from_secs(5), async { tokio::join!(polling, indexed) })
+            .await
+            .expect("polling stopped once the connection changed");
+        assert_eq!(last_seen, Some(2));
+    }
 }
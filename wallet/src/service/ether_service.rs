@@ -1,54 +1,228 @@
+use crate::chain::eth::RpcPool;
+use crate::config::server_config::FeePolicy;
+use crate::error::{parse_address, parse_hash, ApiError, ErrorCode};
+use crate::model::abi_registry::{AbiRegistry, DecodedCall, DecodedLog};
+use crate::model::api_key::Principal;
 use crate::model::keyring::Keyring;
+use crate::service::outbound_service::{NewOutboundTx, OutboundTxService, SentTransaction};
+use crate::types::{Amount, Asset};
 use anyhow::Result;
 use ethers::middleware::{Middleware, SignerMiddleware};
-use ethers::providers::{Http, Provider};
+use ethers::providers::{Provider};
 use ethers::signers::Signer;
-use ethers::types::{Address, Transaction, TransactionRequest, H256};
-use ethers::utils::{format_ether, parse_ether};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Transaction, TransactionReceipt, TransactionRequest, U256};
+use ethers::utils::format_units;
+use serde::Serialize;
+use serde_json::json;
 use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStatus {
+    /// Not mined yet, there is no receipt
+    Pending,
+    Success,
+    Reverted,
+    /// Mined before Byzantium, receipts of that era carry no status
+    Unknown,
+}
+
+impl ExecutionStatus {
+    pub fn of(receipt: Option<&TransactionReceipt>) -> Self {
+        match receipt.map(|receipt| receipt.status) {
+            None => ExecutionStatus::Pending,
+            Some(None) => ExecutionStatus::Unknown,
+            Some(Some(status)) if status.as_u64() == 1 => ExecutionStatus::Success,
+            Some(Some(_)) => ExecutionStatus::Reverted,
+        }
+    }
+}
+
+/// Transaction with its receipt, fee and decoded calldata and logs
+#[derive(Serialize, ToSchema)]
+pub struct TransactionDetail {
+    #[schema(value_type = Object)]
+    pub transaction: Transaction,
+    #[schema(value_type = Option<Object>)]
+    pub receipt: Option<TransactionReceipt>,
+    pub status: ExecutionStatus,
+    /// gas used * effective gas price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call: Option<DecodedCall>,
+    pub logs: Vec<DecodedLog>,
+}
 
 pub struct EtherService<'a> {
-    eth_provider: &'a Provider<Http>,
+    eth_provider: &'a Provider<RpcPool>,
     keyring: &'a RwLock<Keyring>,
+    abis: &'a RwLock<AbiRegistry>,
+    fee: &'a FeePolicy,
 }
 
 impl<'a> EtherService<'a> {
-    pub fn new(eth: &'a Provider<Http>, ring: &'a RwLock<Keyring>) -> Result<Self> {
+    pub fn new(
+        eth: &'a Provider<RpcPool>,
+        ring: &'a RwLock<Keyring>,
+        abis: &'a RwLock<AbiRegistry>,
+        fee: &'a FeePolicy,
+    ) -> Result<Self> {
         Ok(Self {
             eth_provider: eth,
             keyring: ring,
+            abis,
+            fee,
         })
     }
 
-    pub async fn get_balance(&self, address: &str) -> Result<String> {
-        let address = address.parse::<Address>()?;
+    pub async fn get_balance(&self, address: &str) -> Result<Amount> {
+        let address = parse_address(address)?;
         let balance = self.eth_provider.get_balance(address, None).await?;
-        Ok(format_ether(balance))
+        Ok(Amount::new(balance, Asset::ether()))
     }
 
-    pub async fn get_transaction(&self, hash: &str) -> Result<Option<Transaction>> {
-        let tx_hash = hash.parse::<H256>()?;
-        let transaction = self.eth_provider.get_transaction(tx_hash).await?;
-        Ok(transaction)
+    pub async fn get_transaction(&self, hash: &str) -> Result<TransactionDetail> {
+        let tx_hash = parse_hash(hash)?;
+        let transaction = self
+            .eth_provider
+            .get_transaction(tx_hash)
+            .await?
+            .ok_or_else(|| ApiError::not_found(format!("transaction {:?} not found", tx_hash)))?;
+        let receipt = self.eth_provider.get_transaction_receipt(tx_hash).await?;
+
+        let status = ExecutionStatus::of(receipt.as_ref());
+        let fee = receipt.as_ref().and_then(|r| {
+            let gas_price = r.effective_gas_price.or(transaction.gas_price)?;
+            Some(Amount::new(r.gas_used? * gas_price, Asset::ether()))
+        });
+
+        let abis = self.abis.read().await;
+        let call = abis.decode_call(transaction.to, &transaction.input);
+        let logs = receipt
+            .as_ref()
+            .map(|r| r.logs.iter().map(|log| abis.decode_log(log)).collect())
+            .unwrap_or_default();
+        drop(abis);
+
+        Ok(TransactionDetail {
+            transaction,
+            receipt,
+            status,
+            fee,
+            call,
+            logs,
+        })
     }
 
-    pub async fn send_transaction(&self, from: &str, to: &str, amount: &str) -> Result<H256> {
-        let from_addr = from.parse::<Address>()?;
-        let to_addr = to.parse::<Address>()?;
-        let key_entry = self.keyring.read().await.get_by_address(from_addr)?;
+    /// Send ETH from a keyring address owned by the caller's tenant, recorded
+    /// in `outbound_txs` for the tracker to follow
+    pub async fn send_transaction(
+        &self,
+        principal: &Principal,
+        outbound: &OutboundTxService<'_>,
+        from: &str,
+        to: &str,
+        amount: &str,
+    ) -> Result<SentTransaction> {
+        let from_addr = parse_address(from)?;
+        let to_addr = parse_address(to)?;
+        let (key_entry, tenant_id) = {
+            let keyring = self.keyring.read().await;
+            (keyring.get_by_address(from_addr, principal)?, keyring.owner(from_addr).flatten())
+        };
 
         let chain_id = self.eth_provider.get_chainid().await?.as_u64();
         let signer = key_entry.clone().with_chain_id(chain_id);
         let client = SignerMiddleware::new(self.eth_provider.clone(), signer);
 
-        let amount = parse_ether(amount)?;
-        let tx = TransactionRequest::new()
+        let requested = json!({ "from": from, "to": to, "amount": amount });
+        let amount = Amount::parse(amount, Asset::ether())?;
+        // Balance is confirmed by a quorum of endpoints so a stale node can't approve a withdrawal
+        let balance: U256 = self
+            .eth_provider
+            .as_ref()
+            .request_quorum("eth_getBalance", (from_addr, "latest"))
+            .await?;
+        if balance < amount.raw() {
+            return Err(ApiError::new(
+                ErrorCode::InsufficientFunds,
+                format!("insufficient funds: balance {}", Amount::new(balance, Asset::ether())),
+            )
+            .into());
+        }
+
+        let mut tx: TypedTransaction = TransactionRequest::new()
             .from(from_addr)
             .to(to_addr)
-            .value(amount);
+            .value(amount.raw())
+            .into();
+        apply_fee_policy(&client, &mut tx, self.fee).await?;
+
+        let new = NewOutboundTx {
+            tenant_id,
+            kind: "eth",
+            from: from_addr,
+            to: to_addr,
+            amount,
+            request: requested,
+        };
+        outbound.sign_and_send(new, &client, tx).await
+    }
+}
+
+/// Fill gas limit and price of `tx`, add the configured gas limit margin and
+/// refuse to send while the price is above the configured cap
+pub(crate) async fn apply_fee_policy<M>(client: &M, tx: &mut TypedTransaction, fee: &FeePolicy) -> Result<()>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    client.fill_transaction(tx, None).await?;
+
+    if fee.gas_limit_margin_percent > 0
+        && let Some(gas) = tx.gas().copied()
+    {
+        tx.set_gas(gas + gas * fee.gas_limit_margin_percent / 100);
+    }
+
+    if let Some(cap) = fee.max_gas_price_gwei {
+        let price = match tx {
+            TypedTransaction::Eip1559(inner) => inner.max_fee_per_gas,
+            _ => tx.gas_price(),
+        };
+        if let Some(price) = price
+            && price > U256::from(cap) * U256::exp10(9)
+        {
+            return Err(ApiError::new(
+                ErrorCode::FeeTooHigh,
+                format!(
+                    "gas price {} gwei exceeds the configured cap of {} gwei",
+                    format_units(price, "gwei")?,
+                    cap
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Send transaction and get transaction hash
-        let pending_tx = client.send_transaction(tx, None).await?;
-        Ok(pending_tx.tx_hash())
+    #[test]
+    fn execution_status_follows_the_receipt() {
+        let receipt = |status: Option<u64>| TransactionReceipt {
+            status: status.map(Into::into),
+            ..Default::default()
+        };
+        assert_eq!(ExecutionStatus::of(None), ExecutionStatus::Pending);
+        assert_eq!(ExecutionStatus::of(Some(&receipt(Some(1)))), ExecutionStatus::Success);
+        assert_eq!(ExecutionStatus::of(Some(&receipt(Some(0)))), ExecutionStatus::Reverted);
+        assert_eq!(ExecutionStatus::of(Some(&receipt(None))), ExecutionStatus::Unknown);
     }
 }
@@ -0,0 +1,197 @@
+use crate::chain::eth::{RpcPool, WsConnection};
+use crate::config::server_config::ChainConfig;
+use crate::model::heartbeat::{Heartbeats, TaskHealth};
+use crate::model::listener::{ListenerMode, ListenerRegistry};
+use anyhow::{anyhow, Result};
+use ethers::providers::{Middleware, Provider};
+use ethers::types::BlockNumber;
+use serde::Serialize;
+use sqlx::{MySql, Pool};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+/// Upper bound of a single dependency check, a hanging dependency is a down one
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    /// Working with reduced functionality, e.g. listeners polling over HTTP
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    /// A critical dependency being down makes the service not ready
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
+
+pub struct HealthService<'a> {
+    db: &'a Pool<MySql>,
+    eth_provider: &'a Provider<RpcPool>,
+    ws: &'a Arc<WsConnection>,
+    listeners: &'a RwLock<ListenerRegistry>,
+    heartbeats: &'a Heartbeats,
+    eth_config: &'a ChainConfig,
+}
+
+impl<'a> HealthService<'a> {
+    pub fn new(
+        db: &'a Pool<MySql>,
+        eth: &'a Provider<RpcPool>,
+        eth_ws: &'a Arc<WsConnection>,
+        listeners: &'a RwLock<ListenerRegistry>,
+        heartbeats: &'a Heartbeats,
+        eth_config: &'a ChainConfig,
+    ) -> Result<Self> {
+        Ok(Self {
+            db,
+            eth_provider: eth,
+            ws: eth_ws,
+            listeners,
+            heartbeats,
+            eth_config,
+        })
+    }
+
+    pub async fn ready(&self) -> ReadinessReport {
+        let (mysql, chain) = tokio::join!(self.check_mysql(), self.check_chain());
+
+        let mut checks = BTreeMap::new();
+        checks.insert("mysql".to_string(), mysql);
+        checks.insert("chain.eth".to_string(), chain);
+        checks.insert("websocket".to_string(), self.check_websocket().await);
+        checks.insert("listeners".to_string(), self.check_listeners().await);
+        for task in self.heartbeats.tasks(Instant::now()) {
+            checks.insert(format!("task.{}", task.name), check_task(task));
+        }
+
+        let ready = checks
+            .values()
+            .all(|check| !check.critical || check.status != CheckStatus::Down);
+        ReadinessReport { ready, checks }
+    }
+
+    async fn check_mysql(&self) -> DependencyCheck {
+        timed(true, async {
+            sqlx::query("SELECT 1").execute(self.db).await?;
+            Ok((CheckStatus::Up, None))
+        })
+        .await
+    }
+
+    /// Reachable through the pool, at least one healthy endpoint and a recent head
+    async fn check_chain(&self) -> DependencyCheck {
+        timed(true, async {
+            let block = self
+                .eth_provider
+                .get_block(BlockNumber::Latest)
+                .await?
+                .ok_or_else(|| anyhow!("node returned no latest block"))?;
+            let number = block.number.map(|n| n.as_u64()).unwrap_or_default();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let age = now.saturating_sub(block.timestamp.as_u64());
+            if age > self.eth_config.max_block_age {
+                return Ok((
+                    CheckStatus::Down,
+                    Some(format!("latest block {} is {}s old", number, age)),
+                ));
+            }
+
+            let endpoints = self.eth_provider.as_ref().status();
+            let healthy = endpoints.iter().filter(|e| e.healthy).count();
+            let detail = format!("block {} ({}s old), {}/{} endpoints healthy", number, age, healthy, endpoints.len());
+            let status = if healthy == endpoints.len() { CheckStatus::Up } else { CheckStatus::Degraded };
+            Ok((status, Some(detail)))
+        })
+        .await
+    }
+
+    /// Listeners fall back to HTTP polling without it, so not critical
+    async fn check_websocket(&self) -> DependencyCheck {
+        let connected = self.ws.is_connected().await;
+        DependencyCheck {
+            status: if connected { CheckStatus::Up } else { CheckStatus::Degraded },
+            critical: false,
+            latency_ms: None,
+            detail: (!connected).then(|| "disconnected, reconnecting in the background".to_string()),
+        }
+    }
+
+    async fn check_listeners(&self) -> DependencyCheck {
+        let listeners = self.listeners.read().await.all();
+        let count = |mode| listeners.iter().filter(|l| l.mode == mode).count();
+        let (ws, http, reconnecting) = (
+            count(ListenerMode::WebSocket),
+            count(ListenerMode::Http),
+            count(ListenerMode::Reconnecting),
+        );
+        DependencyCheck {
+            status: if http + reconnecting == 0 { CheckStatus::Up } else { CheckStatus::Degraded },
+            critical: false,
+            latency_ms: None,
+            detail: Some(format!(
+                "{} listeners: {} websocket, {} http, {} reconnecting",
+                listeners.len(),
+                ws,
+                http,
+                reconnecting
+            )),
+        }
+    }
+}
+
+/// A background task without a successful tick for several intervals has died or
+/// keeps failing, deposits or transaction statuses silently stop updating
+fn check_task(task: TaskHealth) -> DependencyCheck {
+    let since = match task.since_success {
+        Some(secs) => format!("last successful tick {}s ago", secs),
+        None => "no successful tick yet".to_string(),
+    };
+    let detail = match task.last_error {
+        Some(error) => format!("{}, last tick failed: {}", since, error),
+        None => since,
+    };
+    DependencyCheck {
+        status: if task.stale { CheckStatus::Down } else { CheckStatus::Up },
+        critical: true,
+        latency_ms: None,
+        detail: Some(detail),
+    }
+}
+
+/// Run a check under the timeout, mapping errors and timeouts to `Down`
+async fn timed<F>(critical: bool, check: F) -> DependencyCheck
+where
+    F: Future<Output = Result<(CheckStatus, Option<String>)>>,
+{
+    let started = Instant::now();
+    let (status, detail) = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => (CheckStatus::Down, Some(e.to_string())),
+        Err(_) => (CheckStatus::Down, Some(format!("timed out after {:?}", CHECK_TIMEOUT))),
+    };
+    DependencyCheck {
+        status,
+        critical,
+        latency_ms: Some(started.elapsed().as_millis() as u64),
+        detail,
+    }
+}
@@ -0,0 +1,597 @@
+use crate::chain::eth::RpcPool;
+use crate::error::{parse_address, ApiError};
+use crate::model::block_tracker::Reorg;
+use crate::model::heartbeat::Heartbeats;
+use crate::model::history::{Direction, HistoryEntry, HistoryKind, NewHistoryEntry, NATIVE_ASSET};
+use crate::model::keyring::Keyring;
+use crate::model::watchlist::WatchList;
+use crate::service::block_feed::{managed_addresses, BlockFeed};
+use crate::service::erc20_service::TransferFilter;
+use crate::service::trace_service::InternalTransfer;
+use crate::types::{Amount, Asset};
+use anyhow::Result;
+use ethers::contract::parse_log;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Filter, Log, ValueOrArray, H256};
+use serde::Serialize;
+use sqlx::{MySql, Pool, QueryBuilder};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{info, info_span, warn, Instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 200;
+/// Upper bound of blocks indexed per tick
+const MAX_BLOCKS_PER_TICK: u64 = 100;
+/// Row of `indexer_cursor` holding the last indexed block
+const CURSOR_NAME: &str = "tx_history";
+
+#[derive(Serialize, ToSchema)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Opaque cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+pub struct HistoryService<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl<'a> HistoryService<'a> {
+    pub fn new(db: &'a Pool<MySql>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Newest first history of an address as recorded for `tenant`, or the
+    /// platform's copy for `None`. `direction` is `in` or `out`, self transfers
+    /// match both, or `self` for self transfers only. `asset` is `ETH` or a token
+    /// contract address.
+    pub async fn get_history(
+        &self,
+        tenant: Option<Uuid>,
+        address: &str,
+        cursor: Option<&str>,
+        limit: Option<u64>,
+        direction: Option<&str>,
+        asset: Option<&str>,
+    ) -> Result<HistoryPage> {
+        let address = format_address(parse_address(address)?);
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT * FROM tx_history WHERE tenant_id = ");
+        query.push_bind(tenant_column(tenant)).push(" AND address = ").push_bind(address);
+
+        if let Some(cursor) = cursor {
+            let (block_number, id) = decode_cursor(cursor)?;
+            query
+                .push(" AND (block_number < ")
+                .push_bind(block_number)
+                .push(" OR (block_number = ")
+                .push_bind(block_number)
+                .push(" AND id < ")
+                .push_bind(id)
+                .push("))");
+        }
+
+        if let Some(direction) = direction {
+            query.push(" AND direction IN (").push(directions(direction)?).push(")");
+        }
+
+        match asset {
+            None => {}
+            Some(asset) if asset.eq_ignore_ascii_case(NATIVE_ASSET) => {
+                query.push(" AND asset = ").push_bind(NATIVE_ASSET);
+            }
+            Some(asset) => {
+                query.push(" AND asset = ").push_bind(format_address(parse_address(asset)?));
+            }
+        }
+
+        query
+            .push(" ORDER BY block_number DESC, id DESC LIMIT ")
+            .push_bind(limit + 1);
+
+        let entries = query.build_query_as::<HistoryEntry>().fetch_all(self.db).await?;
+        Ok(paginate(entries, limit))
+    }
+}
+
+/// Background indexer recording every transaction touching keyring and watched
+/// addresses into `tx_history`: native transfers, contract calls and ERC20
+/// transfers, in both directions. Rows of reorged blocks are deleted and indexed again.
+pub struct HistoryIndexer {
+    db: Pool<MySql>,
+    eth_provider: Provider<RpcPool>,
+    feed: Arc<BlockFeed>,
+    keyring: Arc<RwLock<Keyring>>,
+    watched: Arc<RwLock<WatchList>>,
+    reorgs: broadcast::Receiver<Reorg>,
+    poll_interval: Duration,
+}
+
+impl HistoryIndexer {
+    pub fn new(
+        db: Pool<MySql>,
+        feed: Arc<BlockFeed>,
+        keyring: Arc<RwLock<Keyring>>,
+        watched: Arc<RwLock<WatchList>>,
+        reorgs: broadcast::Receiver<Reorg>,
+        poll_interval: u64,
+    ) -> Self {
+        Self {
+            db,
+            eth_provider: feed.provider().clone(),
+            feed,
+            keyring,
+            watched,
+            reorgs,
+            poll_interval: Duration::from_secs(poll_interval),
+        }
+    }
+
+    pub fn spawn(mut self, heartbeats: Arc<Heartbeats>) {
+        heartbeats.register("history_indexer", self.poll_interval);
+        tokio::spawn(
+            async move {
+                info!("history indexer started");
+                loop {
+                    let result = self.tick().await;
+                    if let Err(e) = &result {
+                        warn!(error = %e, "history indexing failed");
+                    }
+                    heartbeats.record("history_indexer", &result);
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }
+            .instrument(info_span!("history_indexer")),
+        );
+    }
+
+    async fn tick(&mut self) -> Result<()> {
+        loop {
+            match self.reorgs.try_recv() {
+                Ok(reorg) => self.rollback(reorg.from_block).await?,
+                Err(TryRecvError::Lagged(skipped)) => {
+                    warn!(skipped, "history indexer missed reorg notifications");
+                }
+                Err(_) => break,
+            }
+        }
+
+        let head = self.eth_provider.get_block_number().await?.as_u64();
+        let start = match self.load_cursor().await? {
+            Some(last) => last + 1,
+            None => head,
+        };
+        if start > head {
+            return Ok(());
+        }
+        let mut end = head.min(start.saturating_add(MAX_BLOCKS_PER_TICK - 1));
+
+        let addresses = managed_addresses(&self.keyring, &self.watched).await;
+        let tenants = address_tenants(&self.keyring, &self.watched).await;
+        if !addresses.is_empty() {
+            let mut entries = Vec::new();
+            let mut timestamps = HashMap::new();
+            for number in start..=end {
+                // a block the node doesn't serve yet is indexed from on the next tick
+                let Some(block) = self.feed.block(number).await? else {
+                    if number == start {
+                        return Ok(());
+                    }
+                    end = number - 1;
+                    break;
+                };
+                let block_hash = format_hash(block.hash.unwrap_or_default());
+                let timestamp = block.timestamp.as_u64();
+                timestamps.insert(number, timestamp);
+
+                if let Some(transfers) = self.feed.internal_transfers(&block).await? {
+                    entries.extend(self.internal_entries(number, &block_hash, timestamp, &transfers, &addresses)?);
+                }
+
+                for tx in &block.transactions {
+                    let kind = if tx.input.is_empty() { HistoryKind::Native } else { HistoryKind::Call };
+                    let template = NewHistoryEntry {
+                        tenant_id: None,
+                        address: String::new(),
+                        counterparty: None,
+                        direction: Direction::Out,
+                        kind,
+                        asset: NATIVE_ASSET.to_string(),
+                        amount: tx.value.to_string(),
+                        amount_formatted: Some(Amount::new(tx.value, Asset::ether()).formatted()),
+                        symbol: Some(NATIVE_ASSET.to_string()),
+                        decimals: Some(18),
+                        tx_hash: format_hash(tx.hash),
+                        log_index: -1,
+                        block_number: number,
+                        block_hash: block_hash.clone(),
+                        block_timestamp: timestamp,
+                    };
+                    entries.extend(sides(tx.from, tx.to, &addresses, template));
+                }
+            }
+
+            for log in self.token_logs(start, end, &addresses).await? {
+                if let Some((from, to, template)) = self.token_entry(log, &timestamps).await {
+                    entries.extend(sides(from, Some(to), &addresses, template));
+                }
+            }
+
+            self.insert(&tenant_copies(entries, &tenants)).await?;
+        }
+
+        self.save_cursor(end).await
+    }
+
+    /// Internal ETH transfers of a block touching managed addresses. Their log index
+    /// is negative, `-2 - n` for the n-th internal transfer, to keep rows unique.
+    fn internal_entries(
+        &self,
+        number: u64,
+        block_hash: &str,
+        timestamp: u64,
+        transfers: &[InternalTransfer],
+        addresses: &HashSet<Address>,
+    ) -> Result<Vec<NewHistoryEntry>> {
+        let mut entries = Vec::new();
+        for (index, transfer) in transfers.iter().enumerate() {
+            let template = NewHistoryEntry {
+                tenant_id: None,
+                address: String::new(),
+                counterparty: None,
+                direction: Direction::Out,
+                kind: HistoryKind::Internal,
+                asset: NATIVE_ASSET.to_string(),
+                amount: transfer.value.raw().to_string(),
+                amount_formatted: Some(transfer.value.formatted()),
+                symbol: Some(NATIVE_ASSET.to_string()),
+                decimals: Some(18),
+                tx_hash: format_hash(transfer.tx_hash),
+                log_index: -2 - index as i64,
+                block_number: number,
+                block_hash: block_hash.to_string(),
+                block_timestamp: timestamp,
+            };
+            entries.extend(sides(transfer.from, Some(transfer.to), addresses, template));
+        }
+        Ok(entries)
+    }
+
+    /// Transfer logs with a managed sender or recipient
+    async fn token_logs(&self, from_block: u64, to_block: u64, addresses: &HashSet<Address>) -> Result<Vec<Log>> {
+        let topics: Vec<H256> = addresses.iter().map(|address| H256::from(*address)).collect();
+        let base = Filter::new()
+            .from_block(from_block)
+            .to_block(to_block)
+            .event("Transfer(address,address,uint256)");
+
+        let sent = self
+            .eth_provider
+            .get_logs(&base.clone().topic1(ValueOrArray::Array(topics.clone())))
+            .await?;
+        let received = self
+            .eth_provider
+            .get_logs(&base.topic2(ValueOrArray::Array(topics)))
+            .await?;
+
+        let mut seen = HashSet::new();
+        Ok(sent
+            .into_iter()
+            .chain(received)
+            .filter(|log| seen.insert((log.transaction_hash, log.log_index)))
+            .collect())
+    }
+
+    async fn token_entry(&self, log: Log, timestamps: &HashMap<u64, u64>) -> Option<(Address, Address, NewHistoryEntry)> {
+        let contract = log.address;
+        let block_number = log.block_number?.as_u64();
+        let block_hash = log.block_hash?;
+        let tx_hash = log.transaction_hash?;
+        let log_index = log.log_index?.as_u64() as i64;
+        // ERC721 shares the Transfer signature but indexes the token id, skip what doesn't decode
+        let transfer = parse_log::<TransferFilter>(log).ok()?;
+
+        let asset = self.feed.token(contract).await;
+        let amount_formatted = asset.as_ref().map(|asset| Amount::new(transfer.value, asset.clone()).formatted());
+
+        let entry = NewHistoryEntry {
+            tenant_id: None,
+            address: String::new(),
+            counterparty: None,
+            direction: Direction::Out,
+            kind: HistoryKind::Token,
+            asset: format_address(contract),
+            amount: transfer.value.to_string(),
+            amount_formatted,
+            symbol: asset.as_ref().map(|asset| asset.symbol.clone()),
+            decimals: asset.map(|asset| asset.decimals),
+            tx_hash: format_hash(tx_hash),
+            log_index,
+            block_number,
+            block_hash: format_hash(block_hash),
+            block_timestamp: timestamps.get(&block_number).copied().unwrap_or_default(),
+        };
+        Some((transfer.from, transfer.to, entry))
+    }
+
+    async fn insert(&self, entries: &[NewHistoryEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut query = QueryBuilder::<MySql>::new(
+            "INSERT IGNORE INTO tx_history (tenant_id, address, counterparty, direction, kind, asset, amount, \
+             amount_formatted, symbol, decimals, tx_hash, log_index, block_number, block_hash, block_timestamp) ",
+        );
+        query.push_values(entries, |mut row, entry| {
+            row.push_bind(tenant_column(entry.tenant_id))
+                .push_bind(&entry.address)
+                .push_bind(&entry.counterparty)
+                .push_bind(entry.direction.as_str())
+                .push_bind(entry.kind.as_str())
+                .push_bind(&entry.asset)
+                .push_bind(&entry.amount)
+                .push_bind(&entry.amount_formatted)
+                .push_bind(&entry.symbol)
+                .push_bind(entry.decimals)
+                .push_bind(&entry.tx_hash)
+                .push_bind(entry.log_index)
+                .push_bind(entry.block_number)
+                .push_bind(&entry.block_hash)
+                .push_bind(entry.block_timestamp);
+        });
+        query.build().execute(&self.db).await?;
+        Ok(())
+    }
+
+    async fn rollback(&self, from_block: u64) -> Result<()> {
+        sqlx::query("DELETE FROM tx_history WHERE block_number >= ?")
+            .bind(from_block)
+            .execute(&self.db)
+            .await?;
+        sqlx::query("UPDATE indexer_cursor SET block_number = LEAST(block_number, ?) WHERE name = ?")
+            .bind(from_block.saturating_sub(1))
+            .bind(CURSOR_NAME)
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn load_cursor(&self) -> Result<Option<u64>> {
+        let cursor: Option<(u64,)> = sqlx::query_as("SELECT block_number FROM indexer_cursor WHERE name = ?")
+            .bind(CURSOR_NAME)
+            .fetch_optional(&self.db)
+            .await?;
+        Ok(cursor.map(|(block_number,)| block_number))
+    }
+
+    async fn save_cursor(&self, block_number: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO indexer_cursor (name, block_number) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE block_number = VALUES(block_number)",
+        )
+        .bind(CURSOR_NAME)
+        .bind(block_number)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Tenants getting their own copy of the history of each managed address: the
+/// owner of its key, or else every tenant watching it
+async fn address_tenants(keyring: &RwLock<Keyring>, watched: &RwLock<WatchList>) -> HashMap<String, Vec<Uuid>> {
+    let keyring = keyring.read().await;
+    let watched = watched.read().await;
+    let mut tenants = HashMap::new();
+    for address in keyring.addresses() {
+        if let Some(Some(owner)) = keyring.owner(address) {
+            tenants.insert(format_address(address), vec![owner]);
+        }
+    }
+    for address in watched.addresses() {
+        if keyring.owner(address).is_none() {
+            tenants.insert(format_address(address), watched.tenants(address));
+        }
+    }
+    tenants
+}
+
+/// The platform's copy of each entry followed by one copy per tenant of its address
+fn tenant_copies(entries: Vec<NewHistoryEntry>, tenants: &HashMap<String, Vec<Uuid>>) -> Vec<NewHistoryEntry> {
+    let mut copies = Vec::with_capacity(entries.len());
+    for entry in entries {
+        for tenant in tenants.get(&entry.address).into_iter().flatten() {
+            copies.push(NewHistoryEntry {
+                tenant_id: Some(*tenant),
+                ..entry.clone()
+            });
+        }
+        copies.push(entry);
+    }
+    copies
+}
+
+/// `tenant_id` column value, the platform's copy is stored under an empty id
+/// so it takes part in the unique key
+fn tenant_column(tenant: Option<Uuid>) -> String {
+    tenant.map(|tenant| tenant.to_string()).unwrap_or_default()
+}
+
+/// Split a transfer into one entry per managed side
+fn sides(from: Address, to: Option<Address>, addresses: &HashSet<Address>, template: NewHistoryEntry) -> Vec<NewHistoryEntry> {
+    let mut entries = Vec::new();
+    if addresses.contains(&from) {
+        entries.push(NewHistoryEntry {
+            address: format_address(from),
+            counterparty: to.map(format_address),
+            direction: if to == Some(from) { Direction::SelfTransfer } else { Direction::Out },
+            ..template.clone()
+        });
+    }
+    if let Some(to) = to.filter(|to| *to != from && addresses.contains(to)) {
+        entries.push(NewHistoryEntry {
+            address: format_address(to),
+            counterparty: Some(format_address(from)),
+            direction: Direction::In,
+            ..template
+        });
+    }
+    entries
+}
+
+pub(crate) fn format_address(address: Address) -> String {
+    format!("{:?}", address)
+}
+
+pub(crate) fn format_hash(hash: H256) -> String {
+    format!("{:?}", hash)
+}
+
+/// Page of `limit` entries out of `limit + 1` fetched, the extra one only tells
+/// whether a next page exists
+fn paginate(mut entries: Vec<HistoryEntry>, limit: u64) -> HistoryPage {
+    let next_cursor = if entries.len() as u64 > limit {
+        entries.truncate(limit as usize);
+        entries.last().map(|entry| encode_cursor(entry.block_number, entry.id))
+    } else {
+        None
+    };
+    HistoryPage { entries, next_cursor }
+}
+
+fn encode_cursor(block_number: u64, id: u64) -> String {
+    format!("{}-{}", block_number, id)
+}
+
+fn decode_cursor(cursor: &str) -> Result<(u64, u64)> {
+    let invalid = || ApiError::invalid_request(format!("invalid cursor {}", cursor));
+    let (block_number, id) = cursor.split_once('-').ok_or_else(invalid)?;
+    Ok((block_number.parse().map_err(|_| invalid())?, id.parse().map_err(|_| invalid())?))
+}
+
+/// Stored directions matching a `direction` filter, self transfers are both in and out
+fn directions(direction: &str) -> Result<&'static str, ApiError> {
+    match direction {
+        "in" => Ok("'in', 'self'"),
+        "out" => Ok("'out', 'self'"),
+        "self" => Ok("'self'"),
+        other => Err(ApiError::invalid_request(format!("invalid direction {}, expected in, out or self", other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(block_number: u64, id: u64) -> HistoryEntry {
+        HistoryEntry {
+            id,
+            address: format_address(Address::repeat_byte(1)),
+            counterparty: None,
+            direction: Direction::In.as_str().to_string(),
+            kind: HistoryKind::Native.as_str().to_string(),
+            asset: NATIVE_ASSET.to_string(),
+            amount: "1".to_string(),
+            amount_formatted: None,
+            symbol: None,
+            decimals: None,
+            tx_hash: format_hash(H256::repeat_byte(id as u8)),
+            log_index: -1,
+            block_number,
+            block_hash: format_hash(H256::zero()),
+            block_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = encode_cursor(19_000_000, 42);
+        assert_eq!(cursor, "19000000-42");
+        assert_eq!(decode_cursor(&cursor).unwrap(), (19_000_000, 42));
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        for cursor in ["", "12", "12-", "-3", "a-1", "1-b", "1-2-3"] {
+            assert!(decode_cursor(cursor).is_err(), "{:?} was accepted", cursor);
+        }
+    }
+
+    #[test]
+    fn next_cursor_points_at_the_last_entry_of_a_full_page() {
+        let page = paginate(vec![entry(10, 7), entry(10, 5), entry(9, 9)], 2);
+        assert_eq!(page.entries.iter().map(|e| e.id).collect::<Vec<_>>(), [7, 5]);
+        assert_eq!(page.next_cursor.as_deref(), Some("10-5"));
+    }
+
+    #[test]
+    fn last_page_has_no_cursor() {
+        let page = paginate(vec![entry(10, 7), entry(9, 9)], 2);
+        assert_eq!(page.entries.len(), 2);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn self_transfers_match_every_direction() {
+        assert_eq!(directions("in").unwrap(), "'in', 'self'");
+        assert_eq!(directions("out").unwrap(), "'out', 'self'");
+        assert_eq!(directions("self").unwrap(), "'self'");
+        assert!(directions("both").is_err());
+    }
+
+    #[tokio::test]
+    async fn rows_are_copied_to_the_key_owner_or_the_watchers() {
+        let (owner, watcher, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let keyring = RwLock::new(Keyring::new());
+        let managed = keyring
+            .write()
+            .await
+            .add_from_private_key("0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318", Some(owner))
+            .unwrap();
+        let watched = RwLock::new(WatchList::new());
+        let watch_only = Address::repeat_byte(0x11);
+        watched.write().await.watch(watch_only, Some(watcher));
+        // Watching a key address doesn't earn a copy of its history
+        watched.write().await.watch(managed, Some(other));
+
+        let tenants = address_tenants(&keyring, &watched).await;
+        let template = |address: Address| NewHistoryEntry {
+            tenant_id: None,
+            address: format_address(address),
+            counterparty: None,
+            direction: Direction::In,
+            kind: HistoryKind::Native,
+            asset: NATIVE_ASSET.to_string(),
+            amount: "1".to_string(),
+            amount_formatted: None,
+            symbol: None,
+            decimals: None,
+            tx_hash: format_hash(H256::zero()),
+            log_index: -1,
+            block_number: 1,
+            block_hash: format_hash(H256::zero()),
+            block_timestamp: 0,
+        };
+        let copies = tenant_copies(vec![template(managed), template(watch_only)], &tenants);
+        let owners: Vec<_> = copies.iter().map(|entry| (entry.address.clone(), entry.tenant_id)).collect();
+        assert_eq!(
+            owners,
+            [
+                (format_address(managed), Some(owner)),
+                (format_address(managed), None),
+                (format_address(watch_only), Some(watcher)),
+                (format_address(watch_only), None),
+            ]
+        );
+        assert_eq!(tenant_column(None), "");
+        assert_eq!(tenant_column(Some(owner)), owner.to_string());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::{ApiError, ErrorCode};
+use anyhow::Result;
+use ethers::utils::hex;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, MySql, Pool};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, info_span, warn, Instrument};
+
+/// Seconds a key is remembered, a retry after that runs the request again
+const RETENTION: u64 = 24 * 3600;
+/// Seconds a claim stays in progress without being renewed. Running requests
+/// renew it, so only one that crashed, or whose response couldn't be stored,
+/// lets a retry take the key over instead of holding it for the whole retention.
+const LEASE: u64 = 120;
+/// Running requests renew their claim three times per lease, a renewal that fails
+/// once doesn't let the claim expire
+const RENEW_INTERVAL: Duration = Duration::from_secs(LEASE / 3);
+/// Expired keys are deleted in the background instead of on every claim
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Response stored for a completed request
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub body: String,
+}
+
+/// Outcome of claiming an `Idempotency-Key`
+pub enum Claim {
+    /// First use of the key, the request must run and then be completed. The
+    /// claim is dated `claimed_at` and must be renewed while the request runs.
+    New { claimed_at: u64 },
+    /// The same request already completed, its response is replayed
+    Replay(StoredResponse),
+}
+
+#[derive(FromRow)]
+struct IdempotencyRow {
+    fingerprint: String,
+    status_code: Option<u16>,
+    response_body: Option<String>,
+    created_at: u64,
+}
+
+/// `Idempotency-Key` records in `idempotency_keys`. A key is claimed before the
+/// request runs, so a retry racing the first attempt is rejected instead of
+/// signing twice, until the claim's lease runs out.
+pub struct IdempotencyService<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl<'a> IdempotencyService<'a> {
+    pub fn new(db: &'a Pool<MySql>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    pub async fn claim(&self, principal: &str, key: &str, method: &str, path: &str, body: &[u8]) -> Result<Claim> {
+        let now = unix_now();
+        let fingerprint = fingerprint(method, path, body);
+        let inserted = sqlx::query(
+            "INSERT INTO idempotency_keys (principal, idempotency_key, method, path, fingerprint, created_at, expires_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(principal)
+        .bind(key)
+        .bind(method)
+        .bind(path)
+        .bind(&fingerprint)
+        .bind(now)
+        .bind(now + RETENTION)
+        .execute(self.db)
+        .await;
+        match inserted {
+            Ok(_) => return Ok(Claim::New { claimed_at: now }),
+            Err(e) if e.as_database_error().is_some_and(|e| e.is_unique_violation()) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let row: IdempotencyRow = sqlx::query_as(
+            "SELECT fingerprint, status_code, response_body, created_at FROM idempotency_keys \
+             WHERE principal = ? AND idempotency_key = ?",
+        )
+        .bind(principal)
+        .bind(key)
+        .fetch_one(self.db)
+        .await?;
+        if row.fingerprint != fingerprint {
+            return Err(ApiError::new(
+                ErrorCode::IdempotencyKeyReused,
+                "Idempotency-Key was already used for a different request",
+            )
+            .into());
+        }
+        match (row.status_code, row.response_body) {
+            (Some(status_code), Some(body)) => Ok(Claim::Replay(StoredResponse { status_code, body })),
+            _ if lease_expired(row.created_at, now) && self.renew(principal, key, row.created_at, now).await? => {
+                warn!(principal, key, "idempotency key reclaimed after its lease ran out");
+                Ok(Claim::New { claimed_at: now })
+            }
+            _ => Err(ApiError::new(
+                ErrorCode::IdempotencyKeyInUse,
+                "a request with this Idempotency-Key is still being processed",
+            )
+            .into()),
+        }
+    }
+
+    /// Move a claim dated `created_at` to `now`, starting a new lease. Matching on
+    /// its date lets a single one of concurrent retries take over an expired claim,
+    /// also after an expired key was deleted and claimed again, and keeps a request
+    /// from renewing a claim it already lost.
+    async fn renew(&self, principal: &str, key: &str, created_at: u64, now: u64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE idempotency_keys SET created_at = ?, expires_at = ? \
+             WHERE principal = ? AND idempotency_key = ? AND created_at = ? AND status_code IS NULL",
+        )
+        .bind(now)
+        .bind(now + RETENTION)
+        .bind(principal)
+        .bind(key)
+        .bind(created_at)
+        .execute(self.db)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Renew the claim of a running request until it completes, the returned future
+    /// is meant to be dropped with it. Only returns if the claim was lost, after
+    /// failing to renew it for a whole lease.
+    pub async fn keep_alive(&self, principal: &str, key: &str, mut claimed_at: u64) {
+        loop {
+            tokio::time::sleep(RENEW_INTERVAL).await;
+            let now = unix_now();
+            match self.renew(principal, key, claimed_at, now).await {
+                Ok(true) => claimed_at = now,
+                Ok(false) => {
+                    warn!(principal, key, "idempotency key was taken over while its request was running");
+                    return;
+                }
+                Err(e) => warn!(key, error = %e, "failed to renew idempotency key"),
+            }
+        }
+    }
+
+    /// Delete the keys past their retention, returns how many were deleted
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= ?")
+            .bind(unix_now())
+            .execute(self.db)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Store the response of a claimed request, with the transaction hash it returned if any
+    pub async fn complete(&self, principal: &str, key: &str, response: &StoredResponse, tx_hash: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status_code = ?, response_body = ?, tx_hash = ?, completed_at = ? \
+             WHERE principal = ? AND idempotency_key = ?",
+        )
+        .bind(response.status_code)
+        .bind(&response.body)
+        .bind(tx_hash)
+        .bind(unix_now())
+        .bind(principal)
+        .bind(key)
+        .execute(self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Periodically delete expired keys
+pub fn spawn_cleanup(db: Pool<MySql>) {
+    tokio::spawn(
+        async move {
+            loop {
+                tokio::time::sleep(CLEANUP_INTERVAL).await;
+                match (IdempotencyService { db: &db }).purge_expired().await {
+                    Ok(0) => {}
+                    Ok(deleted) => info!(deleted, "expired idempotency keys deleted"),
+                    Err(e) => warn!(error = %e, "failed to delete expired idempotency keys"),
+                }
+            }
+        }
+        .instrument(info_span!("idempotency_cleanup")),
+    );
+}
+
+/// Whether a claim made at `created_at` may be taken over by a retry at `now`
+fn lease_expired(created_at: u64, now: u64) -> bool {
+    created_at + LEASE <= now
+}
+
+fn fingerprint(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims_can_be_taken_over_once_their_lease_runs_out() {
+        assert!(!lease_expired(1_000, 1_000));
+        assert!(!lease_expired(1_000, 1_000 + LEASE - 1));
+        assert!(lease_expired(1_000, 1_000 + LEASE));
+    }
+
+    #[test]
+    fn fingerprint_covers_method_path_and_body() {
+        let base = fingerprint("POST", "/wallet/send", b"{}");
+        assert_eq!(base, fingerprint("POST", "/wallet/send", b"{}"));
+        assert_ne!(base, fingerprint("DELETE", "/wallet/send", b"{}"));
+        assert_ne!(base, fingerprint("POST", "/erc20/send", b"{}"));
+        assert_ne!(base, fingerprint("POST", "/wallet/send", b"{\"to\":1}"));
+    }
+}
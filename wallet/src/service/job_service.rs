@@ -0,0 +1,39 @@
+use crate::error::ApiError;
+use crate::model::job::{Job, JobRegistry};
+use anyhow::Result;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+pub struct JobService<'a> {
+    jobs: &'a RwLock<JobRegistry>,
+}
+
+impl<'a> JobService<'a> {
+    pub fn new(jobs: &'a RwLock<JobRegistry>) -> Result<Self> {
+        Ok(Self { jobs })
+    }
+
+    pub async fn get_job(&self, id: &str) -> Result<Job> {
+        let id = id
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::invalid_request(format!("invalid job id {}", id)))?;
+        self.jobs.read().await.get(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[tokio::test]
+    async fn malformed_ids_are_client_errors() {
+        let jobs = RwLock::new(JobRegistry::new());
+        let service = JobService::new(&jobs).unwrap();
+        let code = |error: anyhow::Error| error.downcast_ref::<ApiError>().map(|e| e.code);
+
+        assert_eq!(code(service.get_job("not-a-uuid").await.unwrap_err()), Some(ErrorCode::InvalidRequest));
+        let missing = Uuid::new_v4().to_string();
+        assert_eq!(code(service.get_job(&missing).await.unwrap_err()), Some(ErrorCode::NotFound));
+    }
+}
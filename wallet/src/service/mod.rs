@@ -1,4 +1,17 @@
+pub mod block_feed;
 pub mod block_service;
 pub mod wallet_service;
 pub mod erc20_service;
 pub mod ether_service;
+pub mod job_service;
+pub mod deposit_service;
+pub mod history_service;
+pub mod abi_service;
+pub mod trace_service;
+pub mod rpc_service;
+pub mod health_service;
+pub mod auth_service;
+pub mod siwe_service;
+pub mod tenant_service;
+pub mod idempotency_service;
+pub mod outbound_service;
@@ -0,0 +1,582 @@
+use crate::chain::eth::{EthereumAdapter, RpcPool};
+use crate::chain::ChainAdapter;
+use crate::error::{ApiError, ErrorCode};
+use crate::metrics::METRICS;
+use crate::model::api_key::Principal;
+use crate::model::heartbeat::Heartbeats;
+use crate::model::outbound_tx::{OutboundTx, OutboundTxStatus, TxTransition};
+use crate::types::{Amount, Asset, ChainId, TxHash, TxStatus};
+use anyhow::Result;
+use ethers::middleware::SignerMiddleware;
+use ethers::providers::{Middleware, Provider};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockNumber, H256, U256};
+use ethers::utils::{hex, keccak256, to_checksum};
+use sqlx::{FromRow, MySql, Pool, QueryBuilder};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, info_span, warn, Instrument};
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 200;
+/// Upper bound of transactions checked per tracker tick
+const MAX_TRACKED_PER_TICK: u64 = 500;
+/// Seconds a transaction may stay built. Signing follows within milliseconds,
+/// an older one was abandoned by a failed request.
+const BUILT_EXPIRY: u64 = 300;
+
+pub type WalletClient = SignerMiddleware<Provider<RpcPool>, LocalWallet>;
+
+/// Transfer about to be signed, as requested by the caller
+pub struct NewOutboundTx {
+    /// Owner of the sending key
+    pub tenant_id: Option<Uuid>,
+    /// `eth` or `erc20`, also the `kind` label of the `transactions_sent` metric
+    pub kind: &'static str,
+    pub from: Address,
+    pub to: Address,
+    /// Recorded in base units, with the token contract of ERC20 transfers
+    pub amount: Amount,
+    pub request: serde_json::Value,
+}
+
+pub struct SentTransaction {
+    pub id: Uuid,
+    pub tx_hash: H256,
+}
+
+#[derive(FromRow)]
+struct OutboundTxRow {
+    id: String,
+    tenant_id: Option<String>,
+    kind: String,
+    from_address: String,
+    to_address: String,
+    token: Option<String>,
+    amount: String,
+    symbol: Option<String>,
+    decimals: Option<u8>,
+    request: String,
+    nonce: Option<u64>,
+    gas_limit: Option<String>,
+    gas_price: Option<String>,
+    max_fee_per_gas: Option<String>,
+    max_priority_fee_per_gas: Option<String>,
+    signed_payload: Option<String>,
+    tx_hash: Option<String>,
+    status: String,
+    stuck: bool,
+    block_number: Option<u64>,
+    confirmations: Option<u64>,
+    error: Option<String>,
+    created_at: u64,
+    updated_at: u64,
+    submitted_at: Option<u64>,
+    finalized_at: Option<u64>,
+}
+
+impl TryFrom<OutboundTxRow> for OutboundTx {
+    type Error = anyhow::Error;
+
+    fn try_from(row: OutboundTxRow) -> Result<Self> {
+        let asset = match (row.symbol, row.decimals, &row.token) {
+            (Some(symbol), Some(decimals), Some(token)) => Asset::token(token, symbol, decimals),
+            (Some(symbol), Some(decimals), None) => Asset::new(symbol, decimals),
+            (_, _, Some(token)) => Asset::token(token, "", 0),
+            (_, _, None) => Asset::ether(),
+        };
+        Ok(Self {
+            id: row.id.parse()?,
+            tenant_id: row.tenant_id.map(|id| id.parse()).transpose()?,
+            kind: row.kind,
+            from: row.from_address,
+            to: row.to_address,
+            token: row.token,
+            amount: Amount::new(U256::from_dec_str(&row.amount)?, asset),
+            request: serde_json::from_str(&row.request)?,
+            nonce: row.nonce,
+            gas_limit: row.gas_limit,
+            gas_price: row.gas_price,
+            max_fee_per_gas: row.max_fee_per_gas,
+            max_priority_fee_per_gas: row.max_priority_fee_per_gas,
+            signed_payload: row.signed_payload,
+            tx_hash: row.tx_hash,
+            status: row.status.parse()?,
+            stuck: row.stuck,
+            block_number: row.block_number,
+            confirmations: row.confirmations,
+            error: row.error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            submitted_at: row.submitted_at,
+            finalized_at: row.finalized_at,
+            transitions: Vec::new(),
+        })
+    }
+}
+
+#[derive(FromRow)]
+struct TransitionRow {
+    from_status: Option<String>,
+    to_status: String,
+    detail: Option<String>,
+    at: u64,
+}
+
+/// Every transaction the wallet signs is recorded in `outbound_txs` before it is
+/// broadcast, with each status change logged in `outbound_tx_transitions`
+pub struct OutboundTxService<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl<'a> OutboundTxService<'a> {
+    pub fn new(db: &'a Pool<MySql>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Assign the nonce, record, sign and broadcast `tx`. When the broadcast
+    /// outcome is unknown the record stays `signed` and the tracker finds out
+    /// whether it reached the network.
+    pub async fn sign_and_send(
+        &self,
+        new: NewOutboundTx,
+        client: &WalletClient,
+        mut tx: TypedTransaction,
+    ) -> Result<SentTransaction> {
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(client.signer().chain_id());
+        }
+        if tx.nonce().is_none() {
+            let nonce = client
+                .get_transaction_count(new.from, Some(BlockNumber::Pending.into()))
+                .await?;
+            tx.set_nonce(nonce);
+        }
+        let id = self.insert(&new, &tx).await?;
+
+        let signature = match client.signer().sign_transaction(&tx).await {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.fail(id, OutboundTxStatus::Built, &e.to_string()).await?;
+                METRICS.transaction_sent(new.kind, "failed");
+                return Err(e.into());
+            }
+        };
+        let raw = tx.rlp_signed(&signature);
+        let tx_hash = H256::from(keccak256(&raw));
+        sqlx::query("UPDATE outbound_txs SET signed_payload = ?, tx_hash = ? WHERE id = ?")
+            .bind(format!("0x{}", hex::encode(&raw)))
+            .bind(format!("{:?}", tx_hash))
+            .bind(id.to_string())
+            .execute(self.db)
+            .await?;
+        self.set_status(id, Some(OutboundTxStatus::Built), OutboundTxStatus::Signed, None)
+            .await?;
+
+        match client.inner().send_raw_transaction(raw).await {
+            Ok(_) => {
+                sqlx::query("UPDATE outbound_txs SET submitted_at = ? WHERE id = ?")
+                    .bind(unix_now())
+                    .bind(id.to_string())
+                    .execute(self.db)
+                    .await?;
+                self.set_status(id, Some(OutboundTxStatus::Signed), OutboundTxStatus::Submitted, None)
+                    .await?;
+                METRICS.transaction_sent(new.kind, "submitted");
+                Ok(SentTransaction { id, tx_hash })
+            }
+            Err(e) => {
+                let e = anyhow::Error::from(e);
+                let error = ApiError::classify(&e);
+                if error.code == ErrorCode::RpcUnavailable {
+                    METRICS.transaction_sent(new.kind, "unknown");
+                    return Err(ApiError::new(
+                        ErrorCode::RpcUnavailable,
+                        format!(
+                            "broadcast of {:?} was not acknowledged ({}), follow it at /tx/{}",
+                            tx_hash, error.message, id
+                        ),
+                    )
+                    .into());
+                }
+                self.fail(id, OutboundTxStatus::Signed, &error.message).await?;
+                METRICS.transaction_sent(new.kind, "failed");
+                Err(e)
+            }
+        }
+    }
+
+    /// A transaction with its transitions, if visible to the caller's tenant
+    pub async fn get(&self, principal: &Principal, id: &str) -> Result<OutboundTx> {
+        let id = id
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::invalid_request(format!("invalid transaction id {}", id)))?;
+        let row: Option<OutboundTxRow> = sqlx::query_as("SELECT * FROM outbound_txs WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(self.db)
+            .await?;
+        let mut tx = match row {
+            Some(row) => OutboundTx::try_from(row)?,
+            None => return Err(ApiError::not_found(format!("transaction {} not found", id)).into()),
+        };
+        if !principal.can_access(tx.tenant_id) {
+            return Err(ApiError::not_found(format!("transaction {} not found", id)).into());
+        }
+
+        let transitions: Vec<TransitionRow> = sqlx::query_as(
+            "SELECT from_status, to_status, detail, at FROM outbound_tx_transitions WHERE tx_id = ? ORDER BY id",
+        )
+        .bind(id.to_string())
+        .fetch_all(self.db)
+        .await?;
+        tx.transitions = transitions
+            .into_iter()
+            .map(|row| {
+                Ok(TxTransition {
+                    from: row.from_status.map(|status| status.parse()).transpose()?,
+                    to: row.to_status.parse()?,
+                    detail: row.detail,
+                    at: row.at,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(tx)
+    }
+
+    /// Newest transactions of the caller's tenant first
+    pub async fn list(
+        &self,
+        principal: &Principal,
+        status: Option<&str>,
+        stuck: Option<bool>,
+        limit: Option<u64>,
+    ) -> Result<Vec<OutboundTx>> {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let mut query = QueryBuilder::<MySql>::new("SELECT * FROM outbound_txs WHERE 1 = 1");
+        if let Some(tenant) = principal.tenant {
+            query.push(" AND tenant_id = ").push_bind(tenant.to_string());
+        }
+        if let Some(status) = status {
+            let status: OutboundTxStatus = status.parse()?;
+            query.push(" AND status = ").push_bind(status.as_str());
+        }
+        if let Some(stuck) = stuck {
+            query.push(" AND stuck = ").push_bind(stuck);
+        }
+        query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit);
+
+        let rows: Vec<OutboundTxRow> = query.build_query_as().fetch_all(self.db).await?;
+        rows.into_iter().map(OutboundTx::try_from).collect()
+    }
+
+    async fn insert(&self, new: &NewOutboundTx, tx: &TypedTransaction) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        let now = unix_now();
+        let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match tx {
+            TypedTransaction::Eip1559(inner) => (None, inner.max_fee_per_gas, inner.max_priority_fee_per_gas),
+            _ => (tx.gas_price(), None, None),
+        };
+        sqlx::query(
+            "INSERT INTO outbound_txs (id, tenant_id, kind, from_address, to_address, token, amount, symbol, decimals, \
+             request, nonce, gas_limit, gas_price, max_fee_per_gas, max_priority_fee_per_gas, status, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(new.tenant_id.map(|id| id.to_string()))
+        .bind(new.kind)
+        .bind(to_checksum(&new.from, None))
+        .bind(to_checksum(&new.to, None))
+        .bind(new.amount.asset().contract.as_deref())
+        .bind(new.amount.raw().to_string())
+        .bind(&new.amount.asset().symbol)
+        .bind(new.amount.asset().decimals)
+        .bind(new.request.to_string())
+        .bind(tx.nonce().map(|nonce| nonce.as_u64()))
+        .bind(tx.gas().map(|gas| gas.to_string()))
+        .bind(gas_price.map(|price| price.to_string()))
+        .bind(max_fee_per_gas.map(|fee| fee.to_string()))
+        .bind(max_priority_fee_per_gas.map(|fee| fee.to_string()))
+        .bind(OutboundTxStatus::Built.as_str())
+        .bind(now)
+        .bind(now)
+        .execute(self.db)
+        .await?;
+        self.log_transition(id, None, OutboundTxStatus::Built, None, now).await?;
+        Ok(id)
+    }
+
+    async fn fail(&self, id: Uuid, from: OutboundTxStatus, error: &str) -> Result<()> {
+        sqlx::query("UPDATE outbound_txs SET error = ? WHERE id = ?")
+            .bind(error)
+            .bind(id.to_string())
+            .execute(self.db)
+            .await?;
+        self.set_status(id, Some(from), OutboundTxStatus::Rejected, Some(error)).await
+    }
+
+    /// Move a transaction to `to` and log the transition. Final statuses also set `finalized_at`.
+    async fn set_status(
+        &self,
+        id: Uuid,
+        from: Option<OutboundTxStatus>,
+        to: OutboundTxStatus,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let now = unix_now();
+        let finalized = !OutboundTxStatus::IN_FLIGHT.contains(&to) && to != OutboundTxStatus::Built;
+        sqlx::query(
+            "UPDATE outbound_txs SET status = ?, updated_at = ?, \
+             finalized_at = IF(?, COALESCE(finalized_at, ?), NULL) WHERE id = ?",
+        )
+        .bind(to.as_str())
+        .bind(now)
+        .bind(finalized)
+        .bind(now)
+        .bind(id.to_string())
+        .execute(self.db)
+        .await?;
+        self.log_transition(id, from, to, detail, now).await
+    }
+
+    async fn log_transition(
+        &self,
+        id: Uuid,
+        from: Option<OutboundTxStatus>,
+        to: OutboundTxStatus,
+        detail: Option<&str>,
+        at: u64,
+    ) -> Result<()> {
+        sqlx::query("INSERT INTO outbound_tx_transitions (tx_id, from_status, to_status, detail, at) VALUES (?, ?, ?, ?, ?)")
+            .bind(id.to_string())
+            .bind(from.map(|status| status.as_str()))
+            .bind(to.as_str())
+            .bind(detail)
+            .bind(at)
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Background tracker following in-flight transactions with `watch_tx` until
+/// they are final. Transactions not mined after `stuck_after` seconds are
+/// flagged, those unknown to the node after `dropped_after` seconds, or whose
+/// nonce was used by another transaction, are marked dropped. Transactions left
+/// built expire.
+pub struct TxTracker {
+    db: Pool<MySql>,
+    eth_provider: Provider<RpcPool>,
+    adapter: EthereumAdapter,
+    confirmations: u64,
+    poll_interval: Duration,
+    stuck_after: u64,
+    dropped_after: u64,
+}
+
+impl TxTracker {
+    pub fn new(
+        db: Pool<MySql>,
+        eth: Provider<RpcPool>,
+        confirmations: u64,
+        poll_interval: u64,
+        stuck_after: u64,
+        dropped_after: u64,
+    ) -> Self {
+        Self {
+            db,
+            adapter: EthereumAdapter::new(eth.clone(), None),
+            eth_provider: eth,
+            confirmations,
+            poll_interval: Duration::from_secs(poll_interval),
+            stuck_after,
+            dropped_after,
+        }
+    }
+
+    pub fn spawn(self, heartbeats: Arc<Heartbeats>) {
+        heartbeats.register("tx_tracker", self.poll_interval);
+        tokio::spawn(
+            async move {
+                info!(confirmations = self.confirmations, "transaction tracker started");
+                loop {
+                    let result = self.tick().await;
+                    if let Err(e) = &result {
+                        warn!(error = %e, "transaction tracking failed");
+                    }
+                    heartbeats.record("tx_tracker", &result);
+                    tokio::time::sleep(self.poll_interval).await;
+                }
+            }
+            .instrument(info_span!("tx_tracker")),
+        );
+    }
+
+    async fn tick(&self) -> Result<()> {
+        let service = OutboundTxService::new(&self.db)?;
+        self.expire_built(&service).await?;
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT * FROM outbound_txs WHERE status IN (");
+        let mut statuses = query.separated(", ");
+        for status in OutboundTxStatus::IN_FLIGHT {
+            statuses.push_bind(status.as_str());
+        }
+        query
+            .push(") ORDER BY updated_at LIMIT ")
+            .push_bind(MAX_TRACKED_PER_TICK);
+        let rows: Vec<OutboundTxRow> = query.build_query_as().fetch_all(&self.db).await?;
+
+        for row in rows {
+            let tx = OutboundTx::try_from(row)?;
+            if let Err(e) = self.check(&service, &tx).await {
+                warn!(id = %tx.id, tx_hash = ?tx.tx_hash, error = %e, "failed to check transaction");
+            }
+        }
+        Ok(())
+    }
+
+    /// Expire transactions a failed request left built. They were never signed
+    /// with a broadcast in flight, so their nonce is free again.
+    async fn expire_built(&self, service: &OutboundTxService<'_>) -> Result<()> {
+        let abandoned: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, kind FROM outbound_txs WHERE status = ? AND created_at <= ? ORDER BY created_at LIMIT ?")
+                .bind(OutboundTxStatus::Built.as_str())
+                .bind(unix_now().saturating_sub(BUILT_EXPIRY))
+                .bind(MAX_TRACKED_PER_TICK)
+                .fetch_all(&self.db)
+                .await?;
+        for (id, kind) in abandoned {
+            let detail = format!("still built after {}s, never broadcast", BUILT_EXPIRY);
+            service
+                .set_status(id.parse()?, Some(OutboundTxStatus::Built), OutboundTxStatus::Expired, Some(&detail))
+                .await?;
+            METRICS.transaction_sent(&kind, "expired");
+            warn!(id, "abandoned transaction expired");
+        }
+        Ok(())
+    }
+
+    async fn check(&self, service: &OutboundTxService<'_>, tx: &OutboundTx) -> Result<()> {
+        let Some(hash) = tx.tx_hash.clone() else {
+            return Ok(());
+        };
+        let info = self.adapter.watch_tx(TxHash::new(ChainId::Ethereum, hash.clone())).await?;
+        let now = unix_now();
+
+        match info.status {
+            TxStatus::Confirmed | TxStatus::Failed => {
+                let confirmations = info.confirmations.unwrap_or_default();
+                // Mined, no longer stuck whatever it becomes
+                sqlx::query(
+                    "UPDATE outbound_txs SET block_number = ?, confirmations = ?, error = ?, stuck = FALSE WHERE id = ?",
+                )
+                .bind(info.block_number)
+                .bind(confirmations)
+                .bind(info.error.as_deref())
+                .bind(tx.id.to_string())
+                .execute(&self.db)
+                .await?;
+                if confirmations >= self.confirmations {
+                    let (to, label) = match info.status {
+                        TxStatus::Failed => (OutboundTxStatus::Reverted, "reverted"),
+                        _ => (OutboundTxStatus::Confirmed, "confirmed"),
+                    };
+                    service.set_status(tx.id, Some(tx.status), to, info.error.as_deref()).await?;
+                    METRICS.transaction_sent(&tx.kind, label);
+                    info!(id = %tx.id, tx_hash = %hash, status = to.as_str(), "transaction final");
+                } else if tx.status != OutboundTxStatus::Included {
+                    let detail = format!("mined in block {}", info.block_number.unwrap_or_default());
+                    service
+                        .set_status(tx.id, Some(tx.status), OutboundTxStatus::Included, Some(&detail))
+                        .await?;
+                }
+            }
+            TxStatus::Pending => {
+                match tx.status {
+                    OutboundTxStatus::Included => {
+                        self.unmine(service, tx, "block reorged out, back in the mempool").await?;
+                    }
+                    OutboundTxStatus::Signed => {
+                        service
+                            .set_status(tx.id, Some(tx.status), OutboundTxStatus::Submitted, Some("seen by the node"))
+                            .await?;
+                    }
+                    _ => {}
+                }
+                self.flag_stuck(service, tx, now).await?;
+            }
+            TxStatus::NotFound => {
+                if tx.status == OutboundTxStatus::Included {
+                    self.unmine(service, tx, "block reorged out").await?;
+                }
+                if let Some(reason) = self.drop_reason(tx, &hash, now).await? {
+                    service
+                        .set_status(tx.id, Some(tx.status), OutboundTxStatus::Dropped, Some(&reason))
+                        .await?;
+                    METRICS.transaction_sent(&tx.kind, "dropped");
+                    warn!(id = %tx.id, tx_hash = %hash, reason, "transaction dropped");
+                } else {
+                    self.flag_stuck(service, tx, now).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn unmine(&self, service: &OutboundTxService<'_>, tx: &OutboundTx, detail: &str) -> Result<()> {
+        sqlx::query("UPDATE outbound_txs SET block_number = NULL, confirmations = NULL WHERE id = ?")
+            .bind(tx.id.to_string())
+            .execute(&self.db)
+            .await?;
+        service
+            .set_status(tx.id, Some(tx.status), OutboundTxStatus::Submitted, Some(detail))
+            .await
+    }
+
+    /// Why a transaction the node doesn't know is dropped, `None` while it may still show up
+    async fn drop_reason(&self, tx: &OutboundTx, hash: &str, now: u64) -> Result<Option<String>> {
+        if let Some(nonce) = tx.nonce {
+            let from: Address = tx.from.parse()?;
+            let mined_nonce = self.eth_provider.get_transaction_count(from, None).await?.as_u64();
+            if mined_nonce > nonce {
+                // Checked again, the transaction may have been mined since the first lookup
+                let info = self.adapter.watch_tx(TxHash::new(ChainId::Ethereum, hash.to_string())).await?;
+                if info.status == TxStatus::NotFound {
+                    return Ok(Some(format!("nonce {} was used by another transaction", nonce)));
+                }
+                return Ok(None);
+            }
+        }
+        let since = tx.submitted_at.unwrap_or(tx.created_at);
+        if now.saturating_sub(since) >= self.dropped_after {
+            return Ok(Some(format!("unknown to the node for {}s", now - since)));
+        }
+        Ok(None)
+    }
+
+    async fn flag_stuck(&self, service: &OutboundTxService<'_>, tx: &OutboundTx, now: u64) -> Result<()> {
+        let since = tx.submitted_at.unwrap_or(tx.created_at);
+        if tx.stuck || now.saturating_sub(since) < self.stuck_after {
+            return Ok(());
+        }
+        sqlx::query("UPDATE outbound_txs SET stuck = TRUE, updated_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(tx.id.to_string())
+            .execute(&self.db)
+            .await?;
+        let detail = format!("stuck, not mined {}s after submission", now - since);
+        service
+            .log_transition(tx.id, Some(tx.status), tx.status, Some(&detail), now)
+            .await?;
+        warn!(id = %tx.id, tx_hash = ?tx.tx_hash, "transaction stuck");
+        Ok(())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
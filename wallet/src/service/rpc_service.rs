@@ -0,0 +1,18 @@
+use crate::chain::eth::pool::EndpointStatus;
+use crate::chain::eth::RpcPool;
+use anyhow::Result;
+use ethers::providers::Provider;
+
+pub struct RpcService<'a> {
+    eth_provider: &'a Provider<RpcPool>,
+}
+
+impl<'a> RpcService<'a> {
+    pub fn new(eth: &'a Provider<RpcPool>) -> Result<Self> {
+        Ok(Self { eth_provider: eth })
+    }
+
+    pub async fn get_endpoints(&self) -> Result<Vec<EndpointStatus>> {
+        Ok(self.eth_provider.as_ref().status())
+    }
+}
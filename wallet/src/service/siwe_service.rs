@@ -0,0 +1,166 @@
+use crate::chain::eth::RpcPool;
+use crate::config::server_config::AuthConfig;
+use crate::error::{ApiError, ErrorCode};
+use crate::model::api_key::Scope;
+use crate::model::siwe::SiweMessage;
+use crate::service::auth_service::AuthService;
+use anyhow::Result;
+use chrono::Utc;
+use ethers::contract::abigen;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::{Address, Signature};
+use ethers::utils::{hash_message, hex};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Serialize;
+use sqlx::{MySql, Pool};
+use std::sync::Arc;
+use tracing::{debug, info};
+use utoipa::ToSchema;
+
+abigen!(
+    EIP1271,
+    r#"[
+        function isValidSignature(bytes32 hash, bytes signature) view returns (bytes4)
+    ]"#,
+);
+
+/// Returned by `isValidSignature` when the contract accepts the signature
+const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+const NONCE_LEN: usize = 16;
+
+#[derive(Serialize, ToSchema)]
+pub struct SiweNonce {
+    pub nonce: String,
+    pub expires_at: u64,
+}
+
+/// Session opened by a successful sign-in, the token is only returned here
+#[derive(Serialize, ToSchema)]
+pub struct SiweSession {
+    /// Sent as `Authorization: Bearer <token>`, like an API key
+    pub token: String,
+    #[schema(value_type = String)]
+    pub address: Address,
+    pub scopes: Vec<Scope>,
+    pub expires_at: u64,
+}
+
+/// Sign-In with Ethereum (EIP-4361) for the operators listed in `auth.operators`.
+/// Signatures are checked with ecrecover, or with EIP-1271 for contract wallets.
+pub struct SiweService<'a> {
+    db: &'a Pool<MySql>,
+    eth_provider: &'a Provider<RpcPool>,
+    config: &'a AuthConfig,
+}
+
+impl<'a> SiweService<'a> {
+    pub fn new(db: &'a Pool<MySql>, eth: &'a Provider<RpcPool>, config: &'a AuthConfig) -> Result<Self> {
+        if config.siwe_domain.is_empty() {
+            return Err(ApiError::new(ErrorCode::NotImplemented, "Sign-In with Ethereum is not configured").into());
+        }
+        Ok(Self {
+            db,
+            eth_provider: eth,
+            config,
+        })
+    }
+
+    /// Single use nonce to embed in the next SIWE message
+    pub async fn issue_nonce(&self) -> Result<SiweNonce> {
+        let now = Utc::now().timestamp() as u64;
+        sqlx::query("DELETE FROM siwe_nonces WHERE expires_at <= ?")
+            .bind(now)
+            .execute(self.db)
+            .await?;
+
+        let nonce: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(NONCE_LEN)
+            .map(char::from)
+            .collect();
+        let expires_at = now + self.config.nonce_ttl;
+        sqlx::query("INSERT INTO siwe_nonces (nonce, created_at, expires_at) VALUES (?, ?, ?)")
+            .bind(&nonce)
+            .bind(now)
+            .bind(expires_at)
+            .execute(self.db)
+            .await?;
+        Ok(SiweNonce { nonce, expires_at })
+    }
+
+    /// Check a signed SIWE message and open a session for its address
+    pub async fn verify(&self, message: &str, signature: &str) -> Result<SiweSession> {
+        let siwe: SiweMessage = message.parse()?;
+        let chain_id = match self.config.siwe_chain_id {
+            Some(chain_id) => chain_id,
+            None => self.eth_provider.get_chainid().await?.as_u64(),
+        };
+        let now = Utc::now();
+        siwe.validate(&self.config.siwe_domain, chain_id, now)?;
+
+        let scopes = self
+            .config
+            .operator_scopes(siwe.address)
+            .ok_or_else(|| ApiError::new(ErrorCode::Forbidden, format!("{:?} is not an operator", siwe.address)))?;
+
+        let signature = hex::decode(signature.trim().trim_start_matches("0x"))
+            .map_err(|_| ApiError::invalid_request("signature must be hex encoded"))?;
+        self.verify_signature(message, siwe.address, &signature).await?;
+
+        // Consumed last and atomically, so a nonce can't be replayed by concurrent logins
+        let now = now.timestamp() as u64;
+        let consumed = sqlx::query("UPDATE siwe_nonces SET used_at = ? WHERE nonce = ? AND used_at IS NULL AND expires_at > ?")
+            .bind(now)
+            .bind(&siwe.nonce)
+            .bind(now)
+            .execute(self.db)
+            .await?;
+        if consumed.rows_affected() == 0 {
+            return Err(ApiError::new(ErrorCode::Unauthorized, "unknown, used or expired nonce").into());
+        }
+
+        let mut expires_at = now + self.config.session_ttl;
+        if let Some(expiration_time) = siwe.expiration_time {
+            expires_at = expires_at.min(expiration_time.timestamp() as u64);
+        }
+        let token = AuthService::new(self.db)?
+            .create_session(siwe.address, scopes, expires_at)
+            .await?;
+        info!(address = ?siwe.address, expires_at, "operator signed in");
+
+        Ok(SiweSession {
+            token,
+            address: siwe.address,
+            scopes: scopes.to_vec(),
+            expires_at,
+        })
+    }
+
+    /// EIP-191 signature of `message` by `address`, recovered for externally owned
+    /// accounts and checked by the contract itself for smart contract wallets
+    async fn verify_signature(&self, message: &str, address: Address, signature: &[u8]) -> Result<()> {
+        let hash = hash_message(message);
+        if let Ok(signature) = Signature::try_from(signature)
+            && signature.recover(hash).ok() == Some(address)
+        {
+            return Ok(());
+        }
+
+        let code = self.eth_provider.get_code(address, None).await?;
+        if !code.is_empty() {
+            let contract = EIP1271::new(address, Arc::new(self.eth_provider.clone()));
+            match contract
+                .is_valid_signature(hash.into(), signature.to_vec().into())
+                .call()
+                .await
+            {
+                Ok(value) if value == EIP1271_MAGIC_VALUE => return Ok(()),
+                Ok(value) => debug!(?address, value = %hex::encode(value), "EIP-1271 signature rejected"),
+                Err(e) => debug!(?address, error = %e, "EIP-1271 signature check reverted"),
+            }
+        }
+
+        Err(ApiError::new(ErrorCode::Unauthorized, "signature does not match the address").into())
+    }
+}
@@ -0,0 +1,263 @@
+use crate::error::{ApiError, ErrorCode};
+use crate::model::api_key::Principal;
+use crate::model::keyring::Keyring;
+use crate::model::listener::ListenerRegistry;
+use crate::model::tenant::{Tenant, TenantAssets};
+use crate::model::watchlist::WatchList;
+use anyhow::Result;
+use ethers::types::Address;
+use sqlx::{FromRow, MySql, Pool};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(FromRow)]
+struct TenantRow {
+    id: String,
+    name: String,
+    created_at: u64,
+}
+
+impl TryFrom<TenantRow> for Tenant {
+    type Error = anyhow::Error;
+
+    fn try_from(row: TenantRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id.parse()?,
+            name: row.name,
+            created_at: row.created_at,
+        })
+    }
+}
+
+/// Tenants are stored in `tenants`, the assets they own live with the keyring,
+/// watch list and listener registry
+pub struct TenantService<'a> {
+    db: &'a Pool<MySql>,
+}
+
+impl<'a> TenantService<'a> {
+    pub fn new(db: &'a Pool<MySql>) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    pub async fn create_tenant(&self, name: &str) -> Result<Tenant> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(ApiError::invalid_request(format!("name must be 1 to {} characters", MAX_NAME_LEN)).into());
+        }
+
+        let tenant = Tenant {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        let inserted = sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES (?, ?, ?)")
+            .bind(tenant.id.to_string())
+            .bind(&tenant.name)
+            .bind(tenant.created_at)
+            .execute(self.db)
+            .await;
+        match inserted {
+            Ok(_) => Ok(tenant),
+            Err(e) if e.as_database_error().is_some_and(|e| e.is_unique_violation()) => {
+                Err(ApiError::invalid_request(format!("tenant {} already exists", tenant.name)).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn list_tenants(&self) -> Result<Vec<Tenant>> {
+        let rows: Vec<TenantRow> = sqlx::query_as("SELECT id, name, created_at FROM tenants ORDER BY created_at, id")
+            .fetch_all(self.db)
+            .await?;
+        rows.into_iter().map(Tenant::try_from).collect()
+    }
+
+    pub async fn get_tenant(&self, id: Uuid) -> Result<Tenant> {
+        let row: Option<TenantRow> = sqlx::query_as("SELECT id, name, created_at FROM tenants WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(self.db)
+            .await?;
+        match row {
+            Some(row) => row.try_into(),
+            None => Err(ApiError::not_found(format!("tenant {} not found", id)).into()),
+        }
+    }
+
+    pub async fn get_assets(
+        &self,
+        id: &str,
+        keyring: &RwLock<Keyring>,
+        watched: &RwLock<WatchList>,
+        listeners: &RwLock<ListenerRegistry>,
+    ) -> Result<TenantAssets> {
+        let id = id
+            .parse::<Uuid>()
+            .map_err(|_| ApiError::invalid_request(format!("invalid tenant id {}", id)))?;
+        let tenant = self.get_tenant(id).await?;
+
+        let mut keys = keyring.read().await.addresses_of(id);
+        let mut watched = watched.read().await.addresses_of(id);
+        let mut listeners = listeners.read().await.contracts_of(id);
+        keys.sort();
+        watched.sort();
+        listeners.sort();
+        Ok(TenantAssets {
+            tenant,
+            keys,
+            watched,
+            listeners,
+        })
+    }
+}
+
+/// Whether the caller's tenant holds the key of `address` or watches it. The
+/// owner of a key decides alone, watching another tenant's key grants nothing.
+pub async fn owns_address(
+    principal: &Principal,
+    address: Address,
+    keyring: &RwLock<Keyring>,
+    watched: &RwLock<WatchList>,
+) -> bool {
+    let Some(tenant) = principal.tenant else {
+        return true;
+    };
+    match keyring.read().await.owner(address) {
+        Some(owner) => owner == Some(tenant),
+        None => watched.read().await.is_watched_by(address, tenant),
+    }
+}
+
+/// Reject requests for addresses outside the caller's tenant
+pub async fn check_address(
+    principal: &Principal,
+    address: Address,
+    keyring: &RwLock<Keyring>,
+    watched: &RwLock<WatchList>,
+) -> Result<()> {
+    if owns_address(principal, address, keyring, watched).await {
+        return Ok(());
+    }
+    Err(ApiError::new(ErrorCode::Forbidden, format!("address {:?} does not belong to your tenant", address)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::eth::mock::MockNode;
+    use crate::config::server_config::FeePolicy;
+    use crate::model::abi_registry::AbiRegistry;
+    use crate::model::api_key::{Scope, Subject};
+    use crate::model::deposit::DepositStore;
+    use crate::model::rate_limit::RateLimitOverride;
+    use crate::service::deposit_service::DepositService;
+    use crate::service::ether_service::EtherService;
+    use crate::service::outbound_service::OutboundTxService;
+    use crate::service::wallet_service::WalletService;
+    use sqlx::mysql::MySqlPoolOptions;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn principal(tenant: Option<Uuid>) -> Principal {
+        Principal {
+            subject: Subject::ApiKey(Uuid::new_v4()),
+            tenant,
+            scopes: vec![Scope::Read, Scope::Sign, Scope::Send, Scope::Watch],
+            rate_limit: RateLimitOverride::default(),
+        }
+    }
+
+    fn code(error: anyhow::Error) -> ErrorCode {
+        error.downcast::<ApiError>().expect("API error").code
+    }
+
+    /// Keyring holding `KEY` for tenant `owner`
+    async fn keyring_of(owner: Uuid) -> (RwLock<Keyring>, Address) {
+        let keyring = RwLock::new(Keyring::new());
+        let address = keyring.write().await.add_from_private_key(KEY, Some(owner)).unwrap();
+        (keyring, address)
+    }
+
+    #[tokio::test]
+    async fn watching_another_tenants_key_is_refused_and_grants_nothing() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let (keyring, address) = keyring_of(owner).await;
+        let watched = RwLock::new(WatchList::new());
+        let deposits = RwLock::new(DepositStore::new());
+        let service = DepositService::new(&keyring, &watched, &deposits).unwrap();
+
+        let error = service.watch_address(&principal(Some(other)), &format!("{:?}", address)).await.unwrap_err();
+        assert_eq!(code(error), ErrorCode::Forbidden);
+        assert!(!watched.read().await.contains(address));
+        assert!(service.watch_address(&principal(Some(owner)), &format!("{:?}", address)).await.is_ok());
+        assert!(service.watch_address(&principal(None), &format!("{:?}", address)).await.is_ok());
+
+        // A watch recorded before the key was imported doesn't outlive the import
+        watched.write().await.watch(address, Some(other));
+        assert!(!owns_address(&principal(Some(other)), address, &keyring, &watched).await);
+        assert!(owns_address(&principal(Some(owner)), address, &keyring, &watched).await);
+    }
+
+    #[tokio::test]
+    async fn history_of_another_tenants_address_is_forbidden() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let (keyring, address) = keyring_of(owner).await;
+        let watched = RwLock::new(WatchList::new());
+
+        let error = check_address(&principal(Some(other)), address, &keyring, &watched).await.unwrap_err();
+        assert_eq!(code(error), ErrorCode::Forbidden);
+        assert!(check_address(&principal(Some(owner)), address, &keyring, &watched).await.is_ok());
+        assert!(check_address(&principal(None), address, &keyring, &watched).await.is_ok());
+
+        let watch_only = Address::repeat_byte(0x11);
+        watched.write().await.watch(watch_only, Some(owner));
+        assert!(check_address(&principal(Some(other)), watch_only, &keyring, &watched).await.is_err());
+        assert!(check_address(&principal(Some(owner)), watch_only, &keyring, &watched).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn exporting_another_tenants_key_reports_it_missing() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let (keyring, address) = keyring_of(owner).await;
+        let service = WalletService::new(&keyring, None).unwrap();
+
+        let error = service
+            .export_keystore(&principal(Some(other)), &format!("{:?}", address), "password".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(code(error), ErrorCode::KeyNotFound);
+    }
+
+    #[tokio::test]
+    async fn sending_from_another_tenants_key_reports_it_missing() {
+        let (owner, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let (keyring, address) = keyring_of(owner).await;
+        let node = MockNode::start().await;
+        let provider = node.provider();
+        let abis = RwLock::new(AbiRegistry::new());
+        let fee = FeePolicy::default();
+        // Never connected: the request must be refused before anything is recorded
+        let db = MySqlPoolOptions::new().connect_lazy("mysql://wallet@127.0.0.1:1/wallet").unwrap();
+        let service = EtherService::new(&provider, &keyring, &abis, &fee).unwrap();
+
+        let Err(error) = service
+            .send_transaction(
+                &principal(Some(other)),
+                &OutboundTxService::new(&db).unwrap(),
+                &format!("{:?}", address),
+                &format!("{:?}", Address::repeat_byte(0x22)),
+                "0.1",
+            )
+            .await
+        else {
+            panic!("sent from another tenant's key");
+        };
+        assert_eq!(code(error), ErrorCode::KeyNotFound);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::chain::eth::RpcPool;
+use crate::error::parse_hash;
+use crate::types::{Amount, Asset};
+use anyhow::Result;
+use ethers::providers::{JsonRpcError, Middleware, Provider, RpcError};
+use ethers::types::{
+    Address, BlockNumber, CallFrame, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions,
+    GethTrace, GethTraceFrame, NameOrAddress, H256,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// ETH moved by a nested call, invisible in the transaction's own `value`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InternalTransfer {
+    #[schema(value_type = String)]
+    pub tx_hash: H256,
+    #[schema(value_type = String)]
+    pub from: Address,
+    #[schema(value_type = String)]
+    pub to: Address,
+    pub value: Amount,
+    pub call_type: String,
+    pub depth: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionTrace {
+    #[schema(value_type = String)]
+    pub tx_hash: H256,
+    /// False when the node doesn't expose `debug_traceTransaction`
+    pub supported: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub internal_transfers: Vec<InternalTransfer>,
+    /// Raw `callTracer` output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub call: Option<CallFrame>,
+}
+
+/// Internal transfers via geth's `callTracer`, available on geth, erigon, reth and anvil
+pub struct TraceService<'a> {
+    eth_provider: &'a Provider<RpcPool>,
+}
+
+impl<'a> TraceService<'a> {
+    pub fn new(eth: &'a Provider<RpcPool>) -> Result<Self> {
+        Ok(Self { eth_provider: eth })
+    }
+
+    pub async fn trace_transaction(&self, hash: &str) -> Result<TransactionTrace> {
+        let tx_hash = parse_hash(hash)?;
+        match self.eth_provider.debug_trace_transaction(tx_hash, call_tracer_options()).await {
+            Ok(GethTrace::Known(GethTraceFrame::CallTracer(frame))) => Ok(TransactionTrace {
+                tx_hash,
+                supported: true,
+                reason: None,
+                internal_transfers: flatten_call_frame(tx_hash, &frame),
+                call: Some(frame),
+            }),
+            Ok(_) => Ok(unsupported(tx_hash, "node returned an unexpected trace format".to_string())),
+            Err(e) if e.as_error_response().is_some_and(is_method_not_found) => Ok(unsupported(tx_hash, e.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Internal transfers of every transaction of a block, `tx_hashes` in block order.
+    /// Returns `None` when the node doesn't support debug tracing, any other failure
+    /// is an error so callers retry instead of giving up on tracing.
+    pub async fn trace_block(&self, number: u64, tx_hashes: &[H256]) -> Result<Option<Vec<InternalTransfer>>> {
+        let traces = match self
+            .eth_provider
+            .debug_trace_block_by_number(Some(BlockNumber::Number(number.into())), call_tracer_options())
+            .await
+        {
+            Ok(traces) => traces,
+            Err(e) if e.as_error_response().is_some_and(is_method_not_found) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(
+            traces
+                .iter()
+                .zip(tx_hashes)
+                .flat_map(|(trace, tx_hash)| match trace {
+                    GethTrace::Known(GethTraceFrame::CallTracer(frame)) => flatten_call_frame(*tx_hash, frame),
+                    _ => Vec::new(),
+                })
+                .collect(),
+        ))
+    }
+}
+
+fn call_tracer_options() -> GethDebugTracingOptions {
+    GethDebugTracingOptions {
+        tracer: Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer)),
+        ..Default::default()
+    }
+}
+
+fn unsupported(tx_hash: H256, reason: String) -> TransactionTrace {
+    TransactionTrace {
+        tx_hash,
+        supported: false,
+        reason: Some(reason),
+        internal_transfers: Vec::new(),
+        call: None,
+    }
+}
+
+/// Value carrying sub-calls of a call tree. Reverted frames and everything
+/// below them are skipped since their transfers never happened.
+fn flatten_call_frame(tx_hash: H256, root: &CallFrame) -> Vec<InternalTransfer> {
+    let mut transfers = Vec::new();
+    if root.error.is_some() {
+        return transfers;
+    }
+    let mut frames: Vec<(&CallFrame, usize)> = root
+        .calls
+        .iter()
+        .flatten()
+        .rev()
+        .map(|frame| (frame, 1))
+        .collect();
+
+    while let Some((frame, depth)) = frames.pop() {
+        if frame.error.is_some() {
+            continue;
+        }
+
+        let value = frame.value.unwrap_or_default();
+        let moves_value = matches!(frame.typ.as_str(), "CALL" | "CREATE" | "CREATE2" | "SELFDESTRUCT");
+        if let (true, Some(NameOrAddress::Address(to))) = (moves_value && !value.is_zero(), &frame.to) {
+            transfers.push(InternalTransfer {
+                tx_hash,
+                from: frame.from,
+                to: *to,
+                value: Amount::new(value, Asset::ether()),
+                call_type: frame.typ.clone(),
+                depth,
+            });
+        }
+
+        frames.extend(frame.calls.iter().flatten().rev().map(|child| (child, depth + 1)));
+    }
+
+    transfers
+}
+
+/// Only a missing method means the node can't trace. Messages like "not available"
+/// also come from pruned state or an endpoint that is briefly overloaded.
+fn is_method_not_found(error: &JsonRpcError) -> bool {
+    error.code == -32601 || error.message.to_lowercase().contains("method not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_error(code: i64, message: &str) -> JsonRpcError {
+        JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn only_missing_methods_disable_tracing() {
+        assert!(is_method_not_found(&rpc_error(-32601, "the method debug_traceBlockByNumber does not exist/is not available")));
+        assert!(is_method_not_found(&rpc_error(-32000, "Method not found")));
+
+        assert!(!is_method_not_found(&rpc_error(-32000, "required historical state is not available")));
+        assert!(!is_method_not_found(&rpc_error(-32000, "block 0x10 does not exist")));
+        assert!(!is_method_not_found(&rpc_error(-32005, "service temporarily not available")));
+    }
+}
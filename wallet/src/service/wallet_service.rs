@@ -1,34 +1,233 @@
+use crate::error::{parse_address, ApiError, ErrorCode};
+use crate::model::api_key::Principal;
 use crate::model::keyring::Keyring;
-use anyhow::{anyhow, Result};
+use crate::model::keystore::{self, KeyStore};
+use anyhow::Result;
+use ethers::signers::coins_bip39::{English, Mnemonic};
+use ethers::signers::{LocalWallet, MnemonicBuilder, Signer};
 use ethers::types::Address;
+use ethers::utils::{hex, to_checksum};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::sync::RwLock;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+/// BIP-39 phrase lengths accepted for new wallets
+const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Wallet created from a fresh mnemonic. The mnemonic is only returned once and
+/// is the backup of the key, the private key itself is never returned.
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct WalletEntity {
     pub address: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mnemonic: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_key: Option<String>,
+    /// Uncompressed secp256k1 public key, hex
     #[serde(skip_serializing_if = "Option::is_none")]
     pub public_key: Option<String>,
 }
 
 pub struct WalletService<'a> {
     keyring: &'a RwLock<Keyring>,
+    keystore: Option<&'a KeyStore>,
 }
 
 impl<'a> WalletService<'a> {
-    pub fn new(ring: &'a RwLock<Keyring>) -> Result<Self> {
-        Ok(Self { keyring: ring })
+    pub fn new(ring: &'a RwLock<Keyring>, keystore: Option<&'a KeyStore>) -> Result<Self> {
+        Ok(Self { keyring: ring, keystore })
+    }
+
+    /// Import a key owned by the caller's tenant
+    pub async fn import_private_key(&mut self, principal: &Principal, private_key: &str) -> Result<Address> {
+        let wallet: LocalWallet = private_key
+            .parse()
+            .map_err(|_| ApiError::new(ErrorCode::InvalidPrivateKey, "invalid private key"))?;
+        self.add(wallet, principal.tenant).await
+    }
+
+    /// Import a key from a V3 keystore encrypted with `password`
+    pub async fn import_keystore(&mut self, principal: &Principal, keystore: Value, password: String) -> Result<Address> {
+        let wallet = tokio::task::spawn_blocking(move || keystore::decrypt(&keystore, &password)).await??;
+        self.add(wallet, principal.tenant).await
+    }
+
+    /// V3 keystore of a key owned by the caller's tenant, encrypted with `password`
+    pub async fn export_keystore(&self, principal: &Principal, address: &str, password: String) -> Result<(Address, Value)> {
+        let address = parse_address(address)?;
+        let wallet = self.keyring.read().await.get_by_address(address, principal)?;
+        let exported = tokio::task::spawn_blocking(move || keystore::encrypt(&wallet, &password)).await??;
+        info!(address = ?address, "key exported");
+        Ok((address, exported))
+    }
+
+    /// Generate a mnemonic of `word_count` words and add its first account
+    /// (`m/44'/60'/0'/0/0`) to the keyring for the caller's tenant
+    pub async fn create_wallet(
+        &mut self,
+        principal: &Principal,
+        word_count: usize,
+        passphrase: Option<&str>,
+    ) -> Result<WalletEntity> {
+        if !WORD_COUNTS.contains(&word_count) {
+            return Err(ApiError::invalid_request(format!(
+                "word_count must be one of {:?}, got {}",
+                WORD_COUNTS, word_count
+            ))
+            .into());
+        }
+        let phrase = Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), word_count)?.to_phrase();
+        let wallet = first_account(&phrase, passphrase)?;
+        let public_key = hex::encode(wallet.signer().verifying_key().to_encoded_point(false).as_bytes());
+
+        let address = self.add(wallet, principal.tenant).await?;
+        info!(address = ?address, word_count, "wallet created");
+        Ok(WalletEntity {
+            address: to_checksum(&address, None),
+            mnemonic: Some(phrase),
+            private_key: None,
+            public_key: Some(format!("0x{}", public_key)),
+        })
+    }
+
+    /// Restore the first account of an existing mnemonic for the caller's tenant
+    pub async fn import_mnemonic(
+        &mut self,
+        principal: &Principal,
+        mnemonic: &str,
+        passphrase: Option<&str>,
+    ) -> Result<Address> {
+        let phrase = mnemonic.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        let wallet = first_account(&phrase, passphrase)?;
+        self.add(wallet, principal.tenant).await
+    }
+
+    /// Save `wallet` to the keystore when one is configured, then add it to the
+    /// keyring, so a key is never usable without surviving a restart. Encrypting
+    /// takes a while, the keyring is only locked for writing to insert. When a
+    /// concurrent import by another tenant wins in between, the file saved here
+    /// is removed again.
+    async fn add(&self, wallet: LocalWallet, tenant: Option<Uuid>) -> Result<Address> {
+        let address = wallet.address();
+        let forbidden = || ApiError::new(ErrorCode::Forbidden, format!("key for {:?} belongs to another tenant", address));
+        if self.keyring.read().await.owner(address).is_some_and(|owner| owner != tenant) {
+            return Err(forbidden().into());
+        }
+        let saved = match self.keystore.cloned() {
+            Some(store) => {
+                let wallet = wallet.clone();
+                tokio::task::spawn_blocking(move || store.save(&wallet, tenant)).await??
+            }
+            None => false,
+        };
+
+        let mut keyring = self.keyring.write().await;
+        if keyring.owner(address).is_some_and(|owner| owner != tenant) {
+            drop(keyring);
+            if saved && let Some(store) = self.keystore {
+                store.remove(address, tenant)?;
+            }
+            return Err(forbidden().into());
+        }
+        keyring.add_wallet(wallet, tenant)
+    }
+}
+
+fn first_account(phrase: &str, passphrase: Option<&str>) -> Result<LocalWallet> {
+    let mut builder = MnemonicBuilder::<English>::default().phrase(phrase);
+    if let Some(passphrase) = passphrase {
+        builder = builder.password(passphrase);
     }
+    builder
+        .build()
+        .map_err(|e| ApiError::new(ErrorCode::InvalidMnemonic, format!("invalid mnemonic: {}", e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::api_key::{Scope, Subject};
+    use crate::model::rate_limit::RateLimitOverride;
+
+    /// Hardhat and anvil's default mnemonic
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+    fn principal() -> Principal {
+        Principal {
+            subject: Subject::ApiKey(Uuid::new_v4()),
+            tenant: Some(Uuid::new_v4()),
+            scopes: vec![Scope::Sign],
+            rate_limit: RateLimitOverride::default(),
+        }
+    }
+
+    fn code(error: anyhow::Error) -> ErrorCode {
+        error.downcast::<ApiError>().expect("API error").code
+    }
+
+    #[tokio::test]
+    async fn mnemonics_restore_the_first_account() {
+        let keyring = RwLock::new(Keyring::new());
+        let mut service = WalletService::new(&keyring, None).unwrap();
+        let principal = principal();
+
+        // extra whitespace and capitals are normalized away
+        let address = service.import_mnemonic(&principal, &format!("  {}\n", MNEMONIC.to_uppercase()), None).await.unwrap();
+        assert_eq!(to_checksum(&address, None), "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert!(keyring.read().await.get_by_address(address, &principal).is_ok());
 
-    pub async fn import_private_key(&mut self, private_key: &str) -> Result<Address> {
-        self.keyring.write().await.add_from_private_key(private_key)
+        // a passphrase derives another seed
+        let other = service.import_mnemonic(&principal, MNEMONIC, Some("passphrase")).await.unwrap();
+        assert_ne!(other, address);
     }
 
-    pub async fn create_wallet(&self) -> Result<WalletEntity> {
-        Err(anyhow!("not implemented"))
+    #[tokio::test]
+    async fn created_wallets_restore_from_their_mnemonic() {
+        let keyring = RwLock::new(Keyring::new());
+        let mut service = WalletService::new(&keyring, None).unwrap();
+        let principal = principal();
+
+        let created = service.create_wallet(&principal, 24, None).await.unwrap();
+        let mnemonic = created.mnemonic.unwrap();
+        assert_eq!(mnemonic.split(' ').count(), 24);
+        let address = first_account(&mnemonic, None).unwrap().address();
+        assert_eq!(created.address, to_checksum(&address, None));
+    }
+
+    #[tokio::test]
+    async fn keys_of_another_tenant_are_neither_imported_nor_saved() {
+        let dir = std::env::temp_dir().join(format!("wallet-service-test-{}", Uuid::new_v4()));
+        let store = KeyStore::open(&dir, "passphrase").unwrap();
+        let keyring = RwLock::new(Keyring::new());
+        let mut service = WalletService::new(&keyring, Some(&store)).unwrap();
+        let (owner, other) = (principal(), principal());
+
+        let address = service.import_mnemonic(&owner, MNEMONIC, None).await.unwrap();
+        let error = service.import_mnemonic(&other, MNEMONIC, None).await.unwrap_err();
+        assert_eq!(code(error), ErrorCode::Forbidden);
+        // importing again for the owner is a no-op
+        assert_eq!(service.import_mnemonic(&owner, MNEMONIC, None).await.unwrap(), address);
+
+        let stored: Vec<_> = store.load().unwrap().into_iter().map(|(wallet, tenant)| (wallet.address(), tenant)).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(stored, [(address, owner.tenant)]);
+    }
+
+    #[tokio::test]
+    async fn unsupported_word_counts_are_rejected() {
+        let keyring = RwLock::new(Keyring::new());
+        let mut service = WalletService::new(&keyring, None).unwrap();
+
+        for word_count in [0, 11, 13, 25] {
+            let error = service.create_wallet(&principal(), word_count, None).await.err().expect("rejected");
+            assert_eq!(code(error), ErrorCode::InvalidRequest);
+        }
+        assert!(keyring.read().await.addresses().is_empty());
+
+        let error = service.import_mnemonic(&principal(), "test test junk", None).await.unwrap_err();
+        assert_eq!(code(error), ErrorCode::InvalidMnemonic);
     }
 }
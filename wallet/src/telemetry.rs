@@ -0,0 +1,125 @@
+use crate::config::server_config::{LogFormat, LoggingConfig};
+use regex::Regex;
+use std::io::{self, Write};
+use std::sync::LazyLock;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Field names whose values never reach the log output
+const SENSITIVE_FIELDS: &str =
+    "private_?key|secret|signature|passphrase|password|mnemonic|seed|api_?key|token|authorization";
+
+/// `"private_key":"..."` as written by the JSON formatter
+static JSON_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r#"(?i)"([a-z_]*(?:{SENSITIVE_FIELDS}))"\s*:\s*"[^"]*""#)).unwrap());
+/// `private_key=...` as written by the text formatter
+static TEXT_FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r#"(?i)\b([a-z_]*(?:{SENSITIVE_FIELDS}))=("[^"]*"|\S+)"#)).unwrap());
+/// 65-byte hex strings are ECDSA signatures wherever they appear
+static SIGNATURE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b0x[0-9a-fA-F]{130}\b").unwrap());
+
+/// Install the global subscriber. `RUST_LOG` takes precedence over the configured
+/// level, both accept per-module directives such as `info,wallet::chain=debug`.
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+    // colour codes would split `field=value` pairs and defeat redaction
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(RedactingWriter);
+
+    match config.format {
+        LogFormat::Json => builder.json().with_current_span(true).with_span_list(true).init(),
+        LogFormat::Text => builder.init(),
+    }
+}
+
+pub fn redact(line: &str) -> String {
+    let line = JSON_FIELD.replace_all(line, r#""$1":"[REDACTED]""#);
+    let line = TEXT_FIELD.replace_all(&line, "$1=[REDACTED]");
+    SIGNATURE.replace_all(&line, "[REDACTED]").into_owned()
+}
+
+/// Stdout writer scrubbing secrets from every formatted event. The fmt layer
+/// formats an event into a buffer first, so each write holds whole lines.
+struct RedactingWriter;
+
+impl<'a> MakeWriter<'a> for RedactingWriter {
+    type Writer = RedactingStdout;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingStdout(io::stdout())
+    }
+}
+
+struct RedactingStdout(io::Stdout);
+
+impl Write for RedactingStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0.lock().write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    const TX_HASH: &str = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+
+    fn signature() -> String {
+        format!("0x{}1b", "ab".repeat(64))
+    }
+
+    #[test]
+    fn json_fields_are_redacted() {
+        let line = format!(r#"{{"level":"INFO","fields":{{"private_key":"{KEY}","Password": "hunter2","to":"0x01"}}}}"#);
+        let redacted = redact(&line);
+        assert!(!redacted.contains(KEY) && !redacted.contains("hunter2"), "{}", redacted);
+        assert!(redacted.contains(r#""private_key":"[REDACTED]""#));
+        assert!(redacted.contains(r#""Password":"[REDACTED]""#));
+        assert!(redacted.contains(r#""to":"0x01""#));
+    }
+
+    #[test]
+    fn text_fields_are_redacted() {
+        let line = format!(r#"INFO import: private_key={KEY} mnemonic="test test junk" api_key=wk_abc address=0x01"#);
+        let redacted = redact(&line);
+        assert_eq!(
+            redacted,
+            "INFO import: private_key=[REDACTED] mnemonic=[REDACTED] api_key=[REDACTED] address=0x01"
+        );
+    }
+
+    #[test]
+    fn nested_fields_are_redacted() {
+        let json = r#"{"span":{"request":{"wallet":{"passphrase":"s3cret"}}},"fields":{"auth":{"authorization":"Bearer wk_abc"}}}"#;
+        let redacted = redact(json);
+        assert!(!redacted.contains("s3cret") && !redacted.contains("wk_abc"), "{}", redacted);
+
+        let text = "INFO request{body.seed=deadbeef id=7}: sent request.client_secret=abc";
+        assert_eq!(redact(text), "INFO request{body.seed=[REDACTED] id=7}: sent request.client_secret=[REDACTED]");
+    }
+
+    #[test]
+    fn signatures_are_redacted_anywhere() {
+        let line = format!("INFO verified message signed with {} by 0x01", signature());
+        assert_eq!(redact(&line), "INFO verified message signed with [REDACTED] by 0x01");
+        let json = format!(r#"{{"message":"siwe {}"}}"#, signature());
+        assert_eq!(redact(&json), r#"{"message":"siwe [REDACTED]"}"#);
+    }
+
+    #[test]
+    fn hashes_and_addresses_are_kept() {
+        let line = format!(
+            r#"{{"tx_hash":"{TX_HASH}","token_address":"0x01","block_hash":"{TX_HASH}"}} tx_hash={TX_HASH} tokens_listened=3"#
+        );
+        assert_eq!(redact(&line), line);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::error::{ApiError, ErrorCode};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::openapi::schema::Schema;
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+/// Native coin or token an amount is denominated in
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Asset {
+    pub symbol: String,
+    pub decimals: u8,
+    /// Token contract, `None` for the native coin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+}
+
+impl Asset {
+    pub fn new(symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals,
+            contract: None,
+        }
+    }
+
+    pub fn ether() -> Self {
+        Self::new("ETH", 18)
+    }
+
+    pub fn token(contract: impl Into<String>, symbol: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            symbol: symbol.into(),
+            decimals,
+            contract: Some(contract.into()),
+        }
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.contract.is_none()
+    }
+}
+
+/// Exact amount of an asset, held in base units (wei, token units).
+/// Serialized with both the raw base units and the human readable value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "AmountRepr", try_from = "AmountRepr")]
+pub struct Amount {
+    raw: U256,
+    asset: Asset,
+}
+
+impl Amount {
+    pub fn new(raw: U256, asset: Asset) -> Self {
+        Self { raw, asset }
+    }
+
+    /// Parse a human readable decimal such as `1.5` into base units. Signs,
+    /// exponents, separators and digits beyond the asset's decimals are rejected
+    /// rather than rounded.
+    pub fn parse(value: &str, asset: Asset) -> Result<Self, ApiError> {
+        let invalid = |reason: String| {
+            ApiError::new(ErrorCode::InvalidAmount, format!("invalid amount {:?}: {}", value, reason))
+        };
+        let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+
+        let (whole, fraction) = match value.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (value, None),
+        };
+        if !is_digits(whole) || fraction.is_some_and(|fraction| !is_digits(fraction)) {
+            return Err(invalid(if value.starts_with('-') {
+                "amounts can't be negative".to_string()
+            } else {
+                "expected a decimal number such as 1.5".to_string()
+            }));
+        }
+
+        let fraction = fraction.unwrap_or_default().trim_end_matches('0');
+        let decimals = asset.decimals as usize;
+        if fraction.len() > decimals {
+            return Err(invalid(format!("{} has {} decimals", asset.symbol, decimals)));
+        }
+        let digits = format!("{}{:0<width$}", whole, fraction, width = decimals);
+        let raw = U256::from_dec_str(&digits).map_err(|_| invalid("too large".to_string()))?;
+        Ok(Self { raw, asset })
+    }
+
+    /// Amount in base units
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn asset(&self) -> &Asset {
+        &self.asset
+    }
+
+    /// Human readable value without trailing zeros, e.g. `1.5` or `0`
+    pub fn formatted(&self) -> String {
+        let digits = self.raw.to_string();
+        let decimals = self.asset.decimals as usize;
+        if decimals == 0 {
+            return digits;
+        }
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (whole, fraction) = padded.split_at(padded.len() - decimals);
+        match fraction.trim_end_matches('0') {
+            "" => whole.to_string(),
+            fraction => format!("{}.{}", whole, fraction),
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.formatted(), self.asset.symbol)
+    }
+}
+
+/// Amount of an asset in base units, with its human readable value. `formatted`
+/// is always present in responses and optional in requests.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[schema(as = Amount)]
+struct AmountRepr {
+    /// Base units as a decimal integer
+    raw: String,
+    /// `raw` scaled down by `decimals`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    formatted: Option<String>,
+    symbol: String,
+    decimals: u8,
+    /// Token contract, absent for the native coin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contract: Option<String>,
+}
+
+impl From<Amount> for AmountRepr {
+    fn from(amount: Amount) -> Self {
+        Self {
+            raw: amount.raw.to_string(),
+            formatted: Some(amount.formatted()),
+            symbol: amount.asset.symbol,
+            decimals: amount.asset.decimals,
+            contract: amount.asset.contract,
+        }
+    }
+}
+
+impl TryFrom<AmountRepr> for Amount {
+    type Error = ApiError;
+
+    fn try_from(repr: AmountRepr) -> Result<Self, ApiError> {
+        let asset = Asset {
+            symbol: repr.symbol,
+            decimals: repr.decimals,
+            contract: repr.contract,
+        };
+        let invalid = || ApiError::new(ErrorCode::InvalidAmount, format!("invalid raw amount {:?}", repr.raw));
+        if repr.raw.is_empty() || !repr.raw.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let raw = U256::from_dec_str(&repr.raw).map_err(|_| invalid())?;
+        let amount = Self { raw, asset };
+        if let Some(formatted) = repr.formatted
+            && Amount::parse(&formatted, amount.asset.clone()).ok().as_ref() != Some(&amount)
+        {
+            return Err(ApiError::new(
+                ErrorCode::InvalidAmount,
+                format!("formatted amount {} doesn't match raw amount {}", formatted, repr.raw),
+            ));
+        }
+        Ok(amount)
+    }
+}
+
+impl PartialSchema for Amount {
+    fn schema() -> RefOr<Schema> {
+        AmountRepr::schema()
+    }
+}
+
+impl ToSchema for Amount {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc() -> Asset {
+        Asset::token("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC", 6)
+    }
+
+    fn raw(value: &str, asset: Asset) -> U256 {
+        Amount::parse(value, asset).unwrap().raw()
+    }
+
+    #[test]
+    fn parses_decimals_into_base_units() {
+        assert_eq!(raw("1", usdc()), U256::from(1_000_000));
+        assert_eq!(raw("1.5", usdc()), U256::from(1_500_000));
+        assert_eq!(raw("0.000001", usdc()), U256::one());
+        assert_eq!(raw("1", Asset::ether()), U256::exp10(18));
+        assert_eq!(raw("7", Asset::new("UNIT", 0)), U256::from(7));
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for value in ["-1", "1.", ".5", "", ".", "1e18", "1,5", " 1", "+1", "0x10", "1.2.3"] {
+            let error = Amount::parse(value, usdc()).unwrap_err();
+            assert_eq!(error.code, ErrorCode::InvalidAmount, "{:?}", value);
+        }
+        assert!(Amount::parse("-1", usdc()).unwrap_err().message.contains("negative"));
+    }
+
+    #[test]
+    fn rejects_digits_beyond_the_decimals() {
+        let error = Amount::parse("1.0000001", usdc()).unwrap_err();
+        assert!(error.message.contains("USDC has 6 decimals"));
+        assert!(Amount::parse("1.5", Asset::new("UNIT", 0)).is_err());
+        assert!(Amount::parse("0.0000000000000000001", Asset::ether()).is_err());
+    }
+
+    #[test]
+    fn trailing_zeros_beyond_the_decimals_are_accepted() {
+        assert_eq!(raw("1.500000000", usdc()), U256::from(1_500_000));
+        assert_eq!(raw("2.000000000000", Asset::new("UNIT", 0)), U256::from(2));
+        assert!(Amount::parse("1.5000001000", usdc()).is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_beyond_u256() {
+        let max = U256::MAX.to_string();
+        assert_eq!(max.len(), 78);
+        assert_eq!(raw(&max, Asset::new("UNIT", 0)), U256::MAX);
+
+        let too_many_digits = "1".repeat(79);
+        let error = Amount::parse(&too_many_digits, Asset::new("UNIT", 0)).unwrap_err();
+        assert!(error.message.contains("too large"));
+        // fits in 78 digits, but not once scaled to base units
+        assert!(Amount::parse(&max, usdc()).is_err());
+    }
+
+    #[test]
+    fn formats_without_trailing_zeros() {
+        assert_eq!(Amount::new(U256::from(1_500_000), usdc()).formatted(), "1.5");
+        assert_eq!(Amount::new(U256::from(1), usdc()).formatted(), "0.000001");
+        assert_eq!(Amount::new(U256::zero(), usdc()).formatted(), "0");
+        assert_eq!(Amount::new(U256::exp10(18), Asset::ether()).to_string(), "1 ETH");
+    }
+
+    #[test]
+    fn formatted_must_match_raw() {
+        let amount = Amount::new(U256::from(1_500_000), usdc());
+        let json = serde_json::to_value(&amount).unwrap();
+        assert_eq!(json["raw"], "1500000");
+        assert_eq!(json["formatted"], "1.5");
+        assert_eq!(serde_json::from_value::<Amount>(json.clone()).unwrap(), amount);
+
+        let mut mismatched = json;
+        mismatched["formatted"] = "15".into();
+        assert!(serde_json::from_value::<Amount>(mismatched).is_err());
+    }
+}
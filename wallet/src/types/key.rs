@@ -39,7 +39,7 @@ impl Key for EthereumKey {
         // to use proper message signing (EIP-191) or transaction signing
         use ethers::core::utils::keccak256;
         let hash = keccak256(data);
-        let signature = self.wallet.sign_message(&hash).await?;
+        let signature = self.wallet.sign_message(hash).await?;
         Ok(signature.to_vec())
     }
 
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 /// Chain identifier - unique identifier for each blockchain
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -15,8 +16,10 @@ pub enum ChainId {
     Sui,
 }
 
-impl ChainId {
-    pub fn from_str(s: &str) -> anyhow::Result<Self> {
+impl FromStr for ChainId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
         match s.to_lowercase().as_str() {
             "eth" | "ethereum" => Ok(ChainId::Ethereum),
             "sol" | "solana" => Ok(ChainId::Solana),
@@ -25,7 +28,9 @@ impl ChainId {
             _ => Err(anyhow::anyhow!("Unsupported chain: {}", s)),
         }
     }
+}
 
+impl ChainId {
     pub fn as_str(&self) -> &'static str {
         match self {
             ChainId::Ethereum => "eth",
@@ -68,34 +73,17 @@ impl fmt::Display for Address {
     }
 }
 
-/// Balance representation (as string to handle large numbers)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Balance {
-    pub amount: String,
-    pub decimals: u8,
-    pub symbol: String,
-}
-
-impl Balance {
-    pub fn new(amount: String, decimals: u8, symbol: String) -> Self {
-        Self {
-            amount,
-            decimals,
-            symbol,
-        }
-    }
-}
-
 /// Transaction request - input for building a transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxRequest {
     pub from: Address,
     pub to: Address,
-    pub amount: String,
+    pub amount: Amount,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Vec<u8>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gas_limit: Option<u64>,
+    /// Decimal gwei, e.g. `1.5`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gas_price: Option<String>,
 }
@@ -170,5 +158,8 @@ pub trait Key: Send + Sync {
     fn supported_chains(&self) -> Vec<ChainId>;
 }
 
+pub mod amount;
 pub mod key;
 
+pub use amount::{Amount, Asset};
+
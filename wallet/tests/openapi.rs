@@ -0,0 +1,216 @@
+//! Keeps the checked-in `openapi.json` in sync with the handlers. Regenerate
+//! it after an intended API change with `UPDATE_OPENAPI=1 cargo test --test openapi`.
+
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use utoipa::OpenApi;
+use wallet::openapi::ApiDoc;
+
+/// Routes serving the spec and docs UI themselves, not part of the API
+const UNDOCUMENTED_ROUTES: &[&str] = &["/openapi.json"];
+
+#[test]
+fn spec_matches_checked_in_file() {
+    let generated = ApiDoc::openapi().to_pretty_json().expect("spec serializes") + "\n";
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(&path, &generated).expect("write openapi.json");
+        return;
+    }
+
+    let checked_in = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "openapi.json is out of date, regenerate it with `UPDATE_OPENAPI=1 cargo test --test openapi`"
+    );
+}
+
+#[test]
+fn every_route_is_documented() {
+    let router = include_str!("../src/router.rs");
+    let route = Regex::new(r#"\.route\("([^"]+)",(.*)"#).unwrap();
+    let method = Regex::new(r"\b(get|post|put|patch|delete)\(").unwrap();
+
+    let mut routes = BTreeSet::new();
+    for captures in route.captures_iter(router) {
+        let path = captures[1].to_string();
+        if UNDOCUMENTED_ROUTES.contains(&path.as_str()) {
+            continue;
+        }
+        for m in method.captures_iter(&captures[0]) {
+            routes.insert((path.clone(), m[1].to_string()));
+        }
+    }
+    assert!(!routes.is_empty(), "no routes found in router.rs");
+
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let documented: BTreeSet<(String, String)> = spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (path.clone(), method.clone()))
+        })
+        .collect();
+
+    let missing: Vec<_> = routes.difference(&documented).collect();
+    let stale: Vec<_> = documented.difference(&routes).collect();
+    assert!(missing.is_empty(), "routes missing from the OpenAPI spec: {:?}", missing);
+    assert!(stale.is_empty(), "documented routes not in router.rs: {:?}", stale);
+}
+
+/// Signature of a handler as written in `src/handler`
+#[derive(Debug)]
+struct HandlerSignature {
+    /// Type of the `Json<..>` body and whether it is required
+    body: Option<(String, bool)>,
+    /// Type of the `Query<..>` parameters
+    query: Option<String>,
+    /// Type inside the `Json<..>` response, `None` for non-JSON responses
+    response: Option<String>,
+}
+
+/// Contents of the outermost `<..>` following `prefix` in `text`
+fn generic_arg<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = text.find(prefix)? + prefix.len();
+    let mut depth = 1;
+    for (offset, c) in text[start..].char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(&text[start..start + offset]);
+        }
+    }
+    None
+}
+
+/// Handler signatures keyed like `router.rs` refers to them, `EtherHandler::get_balance` or `healthy`
+fn handler_signatures() -> (BTreeMap<String, HandlerSignature>, BTreeMap<String, Vec<String>>) {
+    let item = Regex::new(
+        r"(?s)(?:impl (\w+) \{)|(?:pub async fn (\w+)\s*\((.*?)\)\s*(?:->\s*(.*?))?\s*\{)|(?:pub struct (\w+) \{(.*?)\n\})",
+    )
+    .unwrap();
+    let field = Regex::new(r"pub (\w+):").unwrap();
+
+    let mut signatures = BTreeMap::new();
+    let mut structs = BTreeMap::new();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/handler");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let source = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        let mut handler = None;
+        for captures in item.captures_iter(&source) {
+            if let Some(name) = captures.get(1) {
+                handler = Some(name.as_str().to_string());
+            } else if let Some(name) = captures.get(2) {
+                let params = &captures[3];
+                let returns = captures.get(4).map_or("", |m| m.as_str());
+                let key = match &handler {
+                    Some(handler) => format!("{}::{}", handler, name.as_str()),
+                    None => name.as_str().to_string(),
+                };
+                let body = generic_arg(params, "Json<")
+                    .map(|body| (body.to_string(), !params.contains(&format!("Option<Json<{}>>", body))));
+                let signature = HandlerSignature {
+                    body,
+                    query: generic_arg(params, "Query<").map(str::to_string),
+                    response: generic_arg(returns, "Json<").map(str::to_string),
+                };
+                signatures.insert(key, signature);
+            } else if let Some(name) = captures.get(5) {
+                let fields = field.captures_iter(&captures[6]).map(|c| c[1].to_string()).collect();
+                structs.insert(name.as_str().to_string(), fields);
+            }
+        }
+    }
+    (signatures, structs)
+}
+
+/// Name utoipa gives the schema of a Rust type, `ApiResponse<Vec<T>>` is `ApiResponse_Vec_T`
+fn schema_name(rust_type: &str) -> String {
+    rust_type.replace(['<', ','], "_").replace(['>', ' '], "")
+}
+
+fn schema_ref(content: &serde_json::Value) -> Option<String> {
+    let schema = &content["application/json"]["schema"];
+    let reference = schema["$ref"]
+        .as_str()
+        .or_else(|| schema["oneOf"].as_array()?.iter().find_map(|s| s["$ref"].as_str()))?;
+    Some(reference.trim_start_matches("#/components/schemas/").to_string())
+}
+
+/// The spec is declared by stubs in `openapi.rs`, since handlers live in `impl`
+/// blocks. Check each stub against the signature of the handler `router.rs`
+/// mounts on its route, so the two can't drift apart.
+#[test]
+fn stubs_match_handler_signatures() {
+    let router = include_str!("../src/router.rs");
+    let route = Regex::new(r#"\.route\("([^"]+)",(.*)"#).unwrap();
+    let method = Regex::new(r"\b(get|post|put|patch|delete)\(([\w:]+)\)").unwrap();
+    let (signatures, structs) = handler_signatures();
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for captures in route.captures_iter(router) {
+        let path = &captures[1];
+        if UNDOCUMENTED_ROUTES.contains(&path) {
+            continue;
+        }
+        for m in method.captures_iter(&captures[0]) {
+            let (verb, handler) = (&m[1], &m[2]);
+            let signature = signatures
+                .get(handler)
+                .unwrap_or_else(|| panic!("handler {} of {} {} not found in src/handler", handler, verb, path));
+            let operation = &spec["paths"][path][verb];
+            let mut mismatch = |what: &str, expected: &dyn std::fmt::Debug, documented: &dyn std::fmt::Debug| {
+                mismatches.push(format!(
+                    "{} {} ({}): {} is {:?}, documented as {:?}",
+                    verb, path, handler, what, expected, documented
+                ))
+            };
+
+            let body = signature.body.as_ref().map(|(body, required)| (schema_name(body), *required));
+            let documented_body = operation.get("requestBody").map(|body| {
+                let required = body["required"].as_bool().unwrap_or(false);
+                (schema_ref(&body["content"]).unwrap_or_default(), required)
+            });
+            if body != documented_body {
+                mismatch("body", &body, &documented_body);
+            }
+
+            let response = signature.response.as_deref().map(schema_name);
+            let documented_response = schema_ref(&operation["responses"]["200"]["content"]);
+            if response != documented_response {
+                mismatch("response", &response, &documented_response);
+            }
+
+            let query: Vec<String> = signature
+                .query
+                .as_ref()
+                .map(|query| structs.get(query).cloned().unwrap_or_default())
+                .unwrap_or_default();
+            let documented_query: Vec<String> = operation["parameters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|param| param["in"] == "query")
+                .map(|param| param["name"].as_str().unwrap_or_default().to_string())
+                .collect();
+            if query != documented_query {
+                mismatch("query", &query, &documented_query);
+            }
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no routes found in router.rs");
+    assert!(mismatches.is_empty(), "OpenAPI stubs out of sync with handlers:\n{}", mismatches.join("\n"));
+}